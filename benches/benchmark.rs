@@ -6,14 +6,11 @@ use xenogb::core::{
     cpu::{CPUSpeed, LR35902CPU},
     mem::{boot::BootRom, bus::Bus, cartridge::Cartridge},
 };
-use xenogb::debugger::init_metrics;
 
 fn setup_cpu(cartridge: PathBuf) -> LR35902CPU {
     let (vcs, _) = unbounded();
     let (acs, _) = unbounded();
 
-    init_metrics(false);
-
     LR35902CPU::new(
         Bus::new(
             Cartridge::new(PathBuf::from("benches/roms").join(cartridge)),
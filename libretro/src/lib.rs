@@ -0,0 +1,387 @@
+// Pointers passed to the entry points follow the contracts of libretro.h
+#![allow(clippy::missing_safety_doc)]
+
+pub mod libretro;
+
+use std::ffi::{c_char, c_uint, c_void, CStr};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::{Mutex, MutexGuard};
+
+use libretro::*;
+use log::error;
+use xenogb::core::cpu::CLOCK_SPEED;
+use xenogb::core::io::joypad::JOYPAD_INPUT;
+use xenogb::core::io::video::ppu::{RESX, RESY, TICKS_PER_FRAME};
+use xenogb::core::mem::cheats::Cheat;
+use xenogb::emulator::Emulator;
+
+const SAMPLE_RATE: u32 = 48000;
+// Save states embed a variable amount of data, such as the sprites of the
+// current line, while frontends expect a fixed size
+const STATE_SLACK: usize = 0x100;
+
+const BUTTONS: [(c_uint, u8); 8] = [
+    (RETRO_DEVICE_ID_JOYPAD::A, JOYPAD_INPUT::A),
+    (RETRO_DEVICE_ID_JOYPAD::B, JOYPAD_INPUT::B),
+    (RETRO_DEVICE_ID_JOYPAD::START, JOYPAD_INPUT::START),
+    (RETRO_DEVICE_ID_JOYPAD::SELECT, JOYPAD_INPUT::SELECT),
+    (RETRO_DEVICE_ID_JOYPAD::RIGHT, JOYPAD_INPUT::RIGHT),
+    (RETRO_DEVICE_ID_JOYPAD::LEFT, JOYPAD_INPUT::LEFT),
+    (RETRO_DEVICE_ID_JOYPAD::UP, JOYPAD_INPUT::UP),
+    (RETRO_DEVICE_ID_JOYPAD::DOWN, JOYPAD_INPUT::DOWN),
+];
+
+struct Core {
+    environment: Option<RetroEnvironmentFn>,
+    video_refresh: Option<RetroVideoRefreshFn>,
+    audio_sample_batch: Option<RetroAudioSampleBatchFn>,
+    input_poll: Option<RetroInputPollFn>,
+    input_state: Option<RetroInputStateFn>,
+
+    emu: Option<Emulator>,
+    // XRGB8888 frame handed to the frontend
+    frame: Vec<u32>,
+    samples: Vec<i16>,
+}
+
+// libretro cores are single instance, the frontend owns the only one
+static CORE: Mutex<Core> = Mutex::new(Core {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None,
+    emu: None,
+    frame: Vec::new(),
+    samples: Vec::new(),
+});
+
+fn core() -> MutexGuard<'static, Core> {
+    CORE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Core {
+    fn render_frame(&mut self) {
+        let Some(emu) = &self.emu else { return };
+
+        self.frame.clear();
+        self.frame.extend(
+            emu.frame_buffer()
+                .iter()
+                .map(|p| (p.r as u32) << 16 | (p.g as u32) << 8 | p.b as u32),
+        );
+    }
+
+    fn render_audio(&mut self) {
+        let Some(emu) = &mut self.emu else { return };
+
+        self.samples.clear();
+        self.samples.extend(
+            emu.drain_audio()
+                .iter()
+                .flatten()
+                .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16),
+        );
+    }
+}
+
+// The callbacks are called without holding the core, frontends may call
+// back into it from them
+fn buttons(input_state: Option<RetroInputStateFn>) -> u8 {
+    let Some(input_state) = input_state else {
+        return 0;
+    };
+
+    BUTTONS
+        .iter()
+        .filter(|(id, _)| input_state(0, RETRO_DEVICE_JOYPAD, 0, *id) != 0)
+        .fold(0, |buttons, (_, button)| buttons | button)
+}
+
+fn present_frame(video_refresh: Option<RetroVideoRefreshFn>, frame: &[u32]) {
+    if let Some(video_refresh) = video_refresh {
+        video_refresh(
+            frame.as_ptr() as *const c_void,
+            RESX as c_uint,
+            RESY as c_uint,
+            RESX * size_of::<u32>(),
+        );
+    }
+}
+
+fn play_audio(audio_sample_batch: Option<RetroAudioSampleBatchFn>, mut samples: &[i16]) {
+    let Some(audio_sample_batch) = audio_sample_batch else {
+        return;
+    };
+
+    // The frontend may not take every frame at once
+    while !samples.is_empty() {
+        let frames = audio_sample_batch(samples.as_ptr(), samples.len() / 2);
+        if frames == 0 {
+            break;
+        }
+        samples = &samples[(frames * 2).min(samples.len())..];
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    RETRO_API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(cb: RetroEnvironmentFn) {
+    core().environment = Some(cb);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(cb: RetroVideoRefreshFn) {
+    core().video_refresh = Some(cb);
+}
+
+// Samples are always sent in batches
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_: RetroAudioSampleFn) {}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(cb: RetroAudioSampleBatchFn) {
+    core().audio_sample_batch = Some(cb);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(cb: RetroInputPollFn) {
+    core().input_poll = Some(cb);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(cb: RetroInputStateFn) {
+    core().input_state = Some(cb);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    core().emu = None;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut RetroSystemInfo) {
+    *info = RetroSystemInfo {
+        library_name: c"xenogb".as_ptr(),
+        library_version: c"0.1.0".as_ptr(),
+        valid_extensions: c"gb|gbc".as_ptr(),
+        // ROMs are loaded from their path, next to their save files
+        need_fullpath: true,
+        block_extract: false,
+    };
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut RetroSystemAvInfo) {
+    *info = RetroSystemAvInfo {
+        geometry: RetroGameGeometry {
+            base_width: RESX as c_uint,
+            base_height: RESY as c_uint,
+            max_width: RESX as c_uint,
+            max_height: RESY as c_uint,
+            aspect_ratio: RESX as f32 / RESY as f32,
+        },
+        timing: RetroSystemTiming {
+            fps: CLOCK_SPEED as f64 / TICKS_PER_FRAME as f64,
+            sample_rate: SAMPLE_RATE as f64,
+        },
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_: c_uint, _: c_uint) {}
+
+// Power cycle, the cartridge RAM survives it
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    // The frontend keeps the SRAM pointer, the cartridge must stay in place
+    if let Some(emu) = core().emu.as_mut() {
+        emu.reset();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    let (input_poll, input_state) = {
+        let core = core();
+        (core.input_poll, core.input_state)
+    };
+    if let Some(input_poll) = input_poll {
+        input_poll();
+    }
+    let buttons = buttons(input_state);
+
+    // The buffers are lent to the frontend, then handed back for the next frame
+    let (video_refresh, audio_sample_batch, frame, samples) = {
+        let mut core = core();
+        let Some(emu) = &mut core.emu else { return };
+        emu.set_buttons(buttons);
+        emu.run_frame();
+
+        core.render_frame();
+        core.render_audio();
+        (
+            core.video_refresh,
+            core.audio_sample_batch,
+            std::mem::take(&mut core.frame),
+            std::mem::take(&mut core.samples),
+        )
+    };
+
+    present_frame(video_refresh, &frame);
+    play_audio(audio_sample_batch, &samples);
+
+    let mut core = core();
+    core.frame = frame;
+    core.samples = samples;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    core()
+        .emu
+        .as_ref()
+        .map_or(0, |e| size_of::<u32>() + e.save_state().len() + STATE_SLACK)
+}
+
+// The state is prefixed with its length, the rest of the buffer is padding
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    let core = core();
+    let Some(emu) = &core.emu else { return false };
+
+    let state = emu.save_state();
+    let len = size_of::<u32>() + state.len();
+    if data.is_null() || len > size {
+        return false;
+    }
+
+    let out = std::slice::from_raw_parts_mut(data as *mut u8, size);
+    out[..size_of::<u32>()].copy_from_slice(&(state.len() as u32).to_le_bytes());
+    out[size_of::<u32>()..len].copy_from_slice(&state);
+    out[len..].fill(0);
+    true
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    let mut core = core();
+    let Some(emu) = &mut core.emu else {
+        return false;
+    };
+    if data.is_null() || size < size_of::<u32>() {
+        return false;
+    }
+
+    let data = std::slice::from_raw_parts(data as *const u8, size);
+    let (len, state) = data.split_at(size_of::<u32>());
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    if len > state.len() {
+        return false;
+    }
+
+    match emu.load_state(&state[..len]) {
+        Ok(_) => true,
+        Err(e) => {
+            error!("Could not load the state: {e}");
+            false
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {
+    if let Some(emu) = &mut core().emu {
+        emu.cheats().clear();
+    }
+}
+
+// Cheats are owned by the frontend, they are not written to a file
+#[no_mangle]
+pub unsafe extern "C" fn retro_cheat_set(index: c_uint, enabled: bool, code: *const c_char) {
+    let mut core = core();
+    let Some(emu) = &mut core.emu else { return };
+    if code.is_null() {
+        return;
+    }
+
+    let code = CStr::from_ptr(code).to_string_lossy();
+    match Cheat::new("", &code, enabled) {
+        Ok(cheat) => emu.cheats().set(index as usize, cheat),
+        Err(e) => error!("{e}"),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const RetroGameInfo) -> bool {
+    if game.is_null() || (*game).path.is_null() {
+        return false;
+    }
+
+    let Some(environment) = core().environment else {
+        return false;
+    };
+    let mut format = RETRO_PIXEL_FORMAT_XRGB8888;
+    if !environment(
+        RETRO_ENVIRONMENT_SET_PIXEL_FORMAT,
+        &mut format as *mut c_uint as *mut c_void,
+    ) {
+        error!("XRGB8888 is not supported by the frontend");
+        return false;
+    }
+
+    let path = PathBuf::from(CStr::from_ptr((*game).path).to_string_lossy().into_owned());
+    match Emulator::load_rom(&path) {
+        Ok(mut emu) => {
+            emu.set_sample_rate(SAMPLE_RATE);
+            core().emu = Some(emu);
+            true
+        }
+        Err(e) => {
+            error!("Could not load {}: {e}", path.display());
+            false
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(_: c_uint, _: *const RetroGameInfo, _: usize) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    core().emu = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    RETRO_REGION_NTSC
+}
+
+// The cartridge RAM is contiguous, and does not move until the game is unloaded
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(id: c_uint) -> *mut c_void {
+    let mut core = core();
+    match (id, core.emu.as_mut().and_then(|e| e.sram())) {
+        (RETRO_MEMORY_SAVE_RAM, Some(sram)) => sram.as_mut_ptr() as *mut c_void,
+        _ => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(id: c_uint) -> usize {
+    let mut core = core();
+    match (id, core.emu.as_mut().and_then(|e| e.sram())) {
+        (RETRO_MEMORY_SAVE_RAM, Some(sram)) => sram.len(),
+        _ => 0,
+    }
+}
@@ -0,0 +1,74 @@
+// Subset of libretro.h used by the core
+use std::ffi::{c_char, c_uint, c_void};
+
+pub const RETRO_API_VERSION: c_uint = 1;
+
+pub const RETRO_DEVICE_JOYPAD: c_uint = 1;
+
+#[allow(nonstandard_style)]
+pub mod RETRO_DEVICE_ID_JOYPAD {
+    use std::ffi::c_uint;
+
+    pub const B: c_uint = 0;
+    pub const SELECT: c_uint = 2;
+    pub const START: c_uint = 3;
+    pub const UP: c_uint = 4;
+    pub const DOWN: c_uint = 5;
+    pub const LEFT: c_uint = 6;
+    pub const RIGHT: c_uint = 7;
+    pub const A: c_uint = 8;
+}
+
+pub const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT: c_uint = 10;
+pub const RETRO_PIXEL_FORMAT_XRGB8888: c_uint = 1;
+
+pub const RETRO_REGION_NTSC: c_uint = 0;
+
+pub const RETRO_MEMORY_SAVE_RAM: c_uint = 0;
+
+pub type RetroEnvironmentFn = extern "C" fn(cmd: c_uint, data: *mut c_void) -> bool;
+pub type RetroVideoRefreshFn =
+    extern "C" fn(data: *const c_void, width: c_uint, height: c_uint, pitch: usize);
+pub type RetroAudioSampleFn = extern "C" fn(left: i16, right: i16);
+pub type RetroAudioSampleBatchFn = extern "C" fn(data: *const i16, frames: usize) -> usize;
+pub type RetroInputPollFn = extern "C" fn();
+pub type RetroInputStateFn =
+    extern "C" fn(port: c_uint, device: c_uint, index: c_uint, id: c_uint) -> i16;
+
+#[repr(C)]
+pub struct RetroSystemInfo {
+    pub library_name: *const c_char,
+    pub library_version: *const c_char,
+    pub valid_extensions: *const c_char,
+    pub need_fullpath: bool,
+    pub block_extract: bool,
+}
+
+#[repr(C)]
+pub struct RetroGameGeometry {
+    pub base_width: c_uint,
+    pub base_height: c_uint,
+    pub max_width: c_uint,
+    pub max_height: c_uint,
+    pub aspect_ratio: f32,
+}
+
+#[repr(C)]
+pub struct RetroSystemTiming {
+    pub fps: f64,
+    pub sample_rate: f64,
+}
+
+#[repr(C)]
+pub struct RetroSystemAvInfo {
+    pub geometry: RetroGameGeometry,
+    pub timing: RetroSystemTiming,
+}
+
+#[repr(C)]
+pub struct RetroGameInfo {
+    pub path: *const c_char,
+    pub data: *const c_void,
+    pub size: usize,
+    pub meta: *const c_char,
+}
@@ -0,0 +1,182 @@
+// Minimal libretro frontend, driving the core through its entry points
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+use std::ffi::{c_uint, c_void, CStr, CString};
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+
+use xenogb_libretro::libretro::*;
+use xenogb_libretro::*;
+
+const MBC1_RAM_BATTERY: u8 = 0x03;
+const RAM_8KB: u8 = 0x02;
+
+// Copies the action buttons to the cartridge RAM
+const PROGRAM: [u8; 16] = [
+    0x3e, 0x0a, 0xea, 0x00, 0x00, // Enable the cartridge RAM
+    0x3e, 0x10, 0xe0, 0x00, // P1: select the action buttons
+    0xf0, 0x00, // LDH A, (P1)
+    0xea, 0x00, 0xa0, // LD (0xa000), A
+    0x18, 0xf5, // JR -11
+];
+
+static PIXEL_FORMAT: AtomicU32 = AtomicU32::new(u32::MAX);
+static FRAMES: AtomicUsize = AtomicUsize::new(0);
+static FRAME_SIZE: AtomicUsize = AtomicUsize::new(0);
+static AUDIO_FRAMES: AtomicUsize = AtomicUsize::new(0);
+static PRESS_A: AtomicBool = AtomicBool::new(false);
+
+// Callbacks may call back into the core
+extern "C" fn environment(cmd: c_uint, data: *mut c_void) -> bool {
+    // No game is loaded yet while asked for the pixel format
+    assert_eq!(retro_serialize_size(), 0);
+    match cmd {
+        RETRO_ENVIRONMENT_SET_PIXEL_FORMAT => {
+            PIXEL_FORMAT.store(unsafe { *(data as *const c_uint) }, Ordering::SeqCst);
+            true
+        }
+        _ => false,
+    }
+}
+
+extern "C" fn video_refresh(data: *const c_void, width: c_uint, height: c_uint, pitch: usize) {
+    assert!(!data.is_null());
+    assert_eq!(pitch, width as usize * 4);
+    FRAME_SIZE.store(width as usize * height as usize, Ordering::SeqCst);
+    assert_eq!(retro_get_memory_size(RETRO_MEMORY_SAVE_RAM), 0x2000);
+    FRAMES.fetch_add(1, Ordering::SeqCst);
+}
+
+extern "C" fn audio_sample(_: i16, _: i16) {
+    unreachable!()
+}
+
+extern "C" fn audio_sample_batch(_: *const i16, frames: usize) -> usize {
+    AUDIO_FRAMES.fetch_add(frames, Ordering::SeqCst);
+    frames
+}
+
+extern "C" fn input_poll() {}
+
+extern "C" fn input_state(port: c_uint, device: c_uint, _: c_uint, id: c_uint) -> i16 {
+    let pressed = port == 0
+        && device == RETRO_DEVICE_JOYPAD
+        && id == RETRO_DEVICE_ID_JOYPAD::A
+        && PRESS_A.load(Ordering::SeqCst);
+    pressed as i16
+}
+
+fn build_rom() -> CString {
+    let mut rom = common::build_rom(&PROGRAM);
+    rom[0x147] = MBC1_RAM_BATTERY;
+    rom[0x149] = RAM_8KB;
+
+    let rom_path = common::write_rom(&common::temp_dir("libretro"), &rom);
+    CString::new(rom_path.to_str().unwrap()).unwrap()
+}
+
+fn sram() -> &'static mut [u8] {
+    let data = retro_get_memory_data(RETRO_MEMORY_SAVE_RAM);
+    assert!(!data.is_null());
+    unsafe {
+        std::slice::from_raw_parts_mut(
+            data as *mut u8,
+            retro_get_memory_size(RETRO_MEMORY_SAVE_RAM),
+        )
+    }
+}
+
+fn run_frames(frames: usize) {
+    for _ in 0..frames {
+        retro_run();
+    }
+}
+
+// The core is a singleton, so the whole session runs in a single test
+#[test]
+fn session() {
+    assert_eq!(retro_api_version(), RETRO_API_VERSION);
+
+    retro_set_environment(environment);
+    retro_set_video_refresh(video_refresh);
+    retro_set_audio_sample(audio_sample);
+    retro_set_audio_sample_batch(audio_sample_batch);
+    retro_set_input_poll(input_poll);
+    retro_set_input_state(input_state);
+    retro_init();
+
+    let info = unsafe {
+        let mut info = MaybeUninit::uninit();
+        retro_get_system_info(info.as_mut_ptr());
+        info.assume_init()
+    };
+    assert_eq!(unsafe { CStr::from_ptr(info.library_name) }, c"xenogb");
+    assert!(info.need_fullpath);
+
+    // Loading
+    let rom_path = build_rom();
+    let game = RetroGameInfo {
+        path: rom_path.as_ptr(),
+        data: std::ptr::null(),
+        size: 0,
+        meta: std::ptr::null(),
+    };
+    assert!(unsafe { retro_load_game(&game) });
+    assert_eq!(
+        PIXEL_FORMAT.load(Ordering::SeqCst),
+        RETRO_PIXEL_FORMAT_XRGB8888
+    );
+
+    let av_info = unsafe {
+        let mut info = MaybeUninit::uninit();
+        retro_get_system_av_info(info.as_mut_ptr());
+        info.assume_init()
+    };
+    assert_eq!(av_info.geometry.base_width, 160);
+    assert_eq!(av_info.geometry.base_height, 144);
+    assert!((av_info.timing.fps - 59.7275).abs() < 0.001);
+
+    // A second of emulation, with A held down
+    PRESS_A.store(true, Ordering::SeqCst);
+    run_frames(60);
+    assert_eq!(FRAMES.load(Ordering::SeqCst), 60);
+    assert_eq!(FRAME_SIZE.load(Ordering::SeqCst), 160 * 144);
+    let audio_frames = AUDIO_FRAMES.load(Ordering::SeqCst) as f64;
+    assert!((audio_frames / av_info.timing.sample_rate - 60.0 / av_info.timing.fps).abs() < 0.05);
+
+    assert_eq!(sram().len(), 0x2000);
+    assert_eq!(sram()[0] & 0xf, 0b1110);
+
+    // Save states
+    let mut state = vec![0; retro_serialize_size()];
+    assert!(unsafe { retro_serialize(state.as_mut_ptr() as *mut c_void, state.len()) });
+
+    PRESS_A.store(false, Ordering::SeqCst);
+    run_frames(2);
+    assert_eq!(sram()[0] & 0xf, 0b1111);
+
+    assert!(unsafe { retro_unserialize(state.as_ptr() as *const c_void, state.len()) });
+    assert_eq!(sram()[0] & 0xf, 0b1110);
+    assert!(!unsafe { retro_unserialize(state.as_ptr() as *const c_void, 8) });
+
+    // The cartridge RAM survives a reset
+    let sram_ptr = sram().as_ptr();
+    sram()[0x100] = 0xab;
+    retro_reset();
+    assert_eq!(sram().as_ptr(), sram_ptr);
+    assert_eq!(sram()[0x100], 0xab);
+
+    // GameShark codes on the cartridge RAM
+    unsafe { retro_cheat_set(0, true, c"805510A0".as_ptr()) };
+    run_frames(2);
+    assert_eq!(sram()[0x10], 0x55);
+    retro_cheat_reset();
+    sram()[0x10] = 0;
+    run_frames(2);
+    assert_eq!(sram()[0x10], 0);
+
+    retro_unload_game();
+    assert!(retro_get_memory_data(RETRO_MEMORY_SAVE_RAM).is_null());
+    retro_deinit();
+}
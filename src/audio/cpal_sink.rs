@@ -0,0 +1,126 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{BufferSize, SampleRate, StreamConfig};
+use log::error;
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+
+use std::time::Duration;
+
+use super::run_audio::buffer_size;
+use super::sink::AudioSink;
+use crate::core::io::audio::rate_control::RateControl;
+
+struct AudioConsumer {
+    consumer: HeapCons<[f32; 2]>,
+    last_sample: [f32; 2],
+}
+
+impl AudioConsumer {
+    fn new(consumer: HeapCons<[f32; 2]>) -> Self {
+        Self {
+            consumer,
+            last_sample: [0.0, 0.0],
+        }
+    }
+
+    fn pop(&mut self) -> [f32; 2] {
+        if let Some(s) = self.consumer.try_pop() {
+            self.last_sample = s;
+            s
+        } else {
+            self.last_sample
+        }
+    }
+}
+
+// Sample rate of the default output device, None if there is no device
+pub fn output_sample_rate() -> Option<u32> {
+    cpal::default_host()
+        .default_output_device()
+        .and_then(|device| device.default_output_config().ok())
+        .map(|config| config.sample_rate().0)
+}
+
+// Plays the samples on the default output device.
+// When audio_sync is set, the emulation is paced by the output device: samples
+// are only consumed when there is room for them. Otherwise, the emulation
+// output rate follows the buffer fill level through the rate control
+pub struct CpalSink {
+    producer: HeapProd<[f32; 2]>,
+    capacity: usize,
+    rate_control: RateControl,
+    audio_sync: bool,
+    _stream: cpal::Stream,
+}
+
+impl CpalSink {
+    pub fn new(
+        sample_rate: u32,
+        rate_control: RateControl,
+        audio_sync: bool,
+    ) -> Result<Self, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("No output device")?;
+
+        let config = StreamConfig {
+            channels: 2,
+            sample_rate: SampleRate(sample_rate),
+            buffer_size: BufferSize::Default,
+        };
+
+        let capacity = buffer_size(sample_rate);
+        let (producer, consumer) = HeapRb::<[f32; 2]>::new(capacity).split();
+
+        let stream = build_stream(&device, &config, AudioConsumer::new(consumer))?;
+        stream.play().map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            producer,
+            capacity,
+            rate_control,
+            audio_sync,
+            _stream: stream,
+        })
+    }
+}
+
+impl AudioSink for CpalSink {
+    fn push(&mut self, sample: [f32; 2]) {
+        if self.audio_sync {
+            while self.producer.is_full() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        _ = self.producer.try_push(sample);
+
+        if !self.audio_sync {
+            self.rate_control
+                .update(self.producer.occupied_len() as f32 / self.capacity as f32);
+        }
+    }
+}
+
+fn build_stream(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut consumer: AudioConsumer,
+) -> Result<cpal::Stream, String> {
+    let channels = config.channels as usize;
+
+    device
+        .build_output_stream(
+            config,
+            move |output: &mut [f32], _info| {
+                for frame in output.chunks_mut(channels) {
+                    for out in frame.iter_mut().zip(consumer.pop().iter()) {
+                        *out.0 = *out.1;
+                    }
+                }
+            },
+            |err| error!("Stream error: {}", err),
+            None,
+        )
+        .map_err(|e| e.to_string())
+}
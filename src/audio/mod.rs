@@ -1 +1,3 @@
+pub mod cpal_sink;
 pub mod run_audio;
+pub mod sink;
@@ -1,82 +1,89 @@
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{BufferSize, SampleRate, StreamConfig};
-use crossbeam_channel::Receiver;
+use crossbeam_channel::{bounded, Receiver};
+use log::warn;
 
-use ringbuf::traits::{Consumer, Producer, Split};
-use ringbuf::{HeapCons, HeapRb};
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+use std::time::Duration;
 
-use crate::core::io::audio::apu::SAMPLE_RATE;
+use super::cpal_sink::CpalSink;
+use super::sink::{AudioSink, FileSink, NullSink};
+use crate::core::io::audio::rate_control::RateControl;
 
-struct AudioConsumer {
-    consumer: HeapCons<[f32; 2]>,
-    last_sample: [f32; 2],
+// Audio buffered ahead of the output device
+const BUFFER_DURATION: Duration = Duration::from_millis(100);
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum AudioBackend {
+    #[default]
+    CPAL,
+    NULL,
+    FILE,
 }
 
-impl AudioConsumer {
-    fn new(consumer: HeapCons<[f32; 2]>) -> Self {
-        Self {
-            consumer,
-            last_sample: [0.0, 0.0],
-        }
-    }
+#[derive(Debug, Clone)]
+pub struct AudioConfig {
+    pub backend: AudioBackend,
+    pub sample_rate: u32,
+    pub audio_sync: bool,
+    // Output of the file backend
+    pub file: Option<PathBuf>,
+}
 
-    fn pop(&mut self) -> [f32; 2] {
-        if let Some(s) = self.consumer.try_pop() {
-            self.last_sample = s;
-            s
-        } else {
-            self.last_sample
+// Number of samples buffered, also used to bound the samples channel when
+// the emulation is synced to the audio output
+pub fn buffer_size(sample_rate: u32) -> usize {
+    (sample_rate as f64 * BUFFER_DURATION.as_secs_f64()) as usize
+}
+
+fn create_sink(
+    config: &AudioConfig,
+    rate_control: RateControl,
+) -> Result<Box<dyn AudioSink>, String> {
+    Ok(match config.backend {
+        AudioBackend::CPAL => Box::new(CpalSink::new(
+            config.sample_rate,
+            rate_control,
+            config.audio_sync,
+        )?),
+        AudioBackend::NULL => Box::new(NullSink),
+        AudioBackend::FILE => {
+            let path = config.file.as_ref().ok_or("No audio file given")?;
+            Box::new(FileSink::new(path, config.sample_rate).map_err(|e| e.to_string())?)
         }
-    }
+    })
 }
 
-pub fn run_audio_thread(sample_rx: Receiver<[f32; 2]>) {
-    std::thread::spawn(move || {
-        let host = cpal::default_host();
-        let device = host.default_output_device().unwrap();
+// Feeds the samples to the configured sink, falls back to the null sink if it
+// can't be opened. Returns the backend actually in use once the sink is open.
+// The thread stops when the emulator is gone
+pub fn run_audio_thread(
+    sample_rx: Receiver<[f32; 2]>,
+    config: AudioConfig,
+    rate_control: RateControl,
+) -> (JoinHandle<()>, AudioBackend) {
+    let (opened_sd, opened_rc) = bounded(1);
 
-        let config = StreamConfig {
-            channels: 2,
-            sample_rate: SampleRate(SAMPLE_RATE as u32),
-            buffer_size: BufferSize::Default,
+    let thread = std::thread::spawn(move || {
+        let mut sink = match create_sink(&config, rate_control) {
+            Ok(sink) => {
+                _ = opened_sd.send(config.backend);
+                sink
+            }
+            Err(e) => {
+                warn!(
+                    "Could not open the {:?} audio sink, audio is disabled: {e}",
+                    config.backend
+                );
+                _ = opened_sd.send(AudioBackend::NULL);
+                Box::new(NullSink)
+            }
         };
 
-        let rb = HeapRb::<[f32; 2]>::new(16384);
-        let (mut prod, cons) = rb.split();
-
-        let stream = build_stream(&device, &config, AudioConsumer::new(cons)).unwrap();
-
-        stream.play().unwrap();
-
-        loop {
-            if let Ok(s) = sample_rx.try_recv() {
-                _ = prod.try_push(s);
-            }
+        while let Ok(s) = sample_rx.recv() {
+            sink.push(s);
         }
     });
-}
-
-fn build_stream(
-    device: &cpal::Device,
-    config: &cpal::StreamConfig,
-    mut consumer: AudioConsumer,
-) -> Result<cpal::Stream, ()> {
-    let channels = config.channels as usize;
-
-    let stream = device
-        .build_output_stream(
-            config,
-            move |output: &mut [f32], _info| {
-                for frame in output.chunks_mut(channels) {
-                    for out in frame.iter_mut().zip(consumer.pop().iter()) {
-                        *out.0 = *out.1;
-                    }
-                }
-            },
-            |err| eprintln!("Stream error: {}", err),
-            None,
-        )
-        .unwrap();
 
-    Ok(stream)
+    (thread, opened_rc.recv().unwrap_or(AudioBackend::NULL))
 }
@@ -0,0 +1,56 @@
+use log::error;
+
+use std::path::Path;
+
+use crate::core::io::audio::wav::WavWriter;
+
+// Destination of the audio samples, fed from the audio thread
+pub trait AudioSink {
+    fn push(&mut self, sample: [f32; 2]);
+}
+
+// Discards every sample, used when there is no audio output
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn push(&mut self, _sample: [f32; 2]) {}
+}
+
+// Writes the samples to a WAV file, as they would be played
+pub struct FileSink {
+    // Dropped on the first write error
+    writer: Option<WavWriter>,
+    sample_rate: u32,
+    frames_since_flush: u32,
+}
+
+impl FileSink {
+    pub fn new(path: &Path, sample_rate: u32) -> std::io::Result<Self> {
+        Ok(Self {
+            writer: Some(WavWriter::create(path, 2, sample_rate)?),
+            sample_rate,
+            frames_since_flush: 0,
+        })
+    }
+}
+
+impl AudioSink for FileSink {
+    fn push(&mut self, sample: [f32; 2]) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        let mut written = writer.write(&sample);
+
+        // Keep the file valid if the emulator is killed
+        self.frames_since_flush += 1;
+        if written.is_ok() && self.frames_since_flush >= self.sample_rate {
+            self.frames_since_flush = 0;
+            written = writer.flush();
+        }
+
+        if let Err(e) = written {
+            error!("Could not write the audio file, audio is disabled: {e}");
+            self.writer = None;
+        }
+    }
+}
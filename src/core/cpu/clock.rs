@@ -65,6 +65,9 @@ impl Clock {
     }
 
     pub fn switch_speed(&mut self, double_speed: bool) {
+        // Keep running unthrottled if it was
+        let throttled = self.frame_target_duration > Duration::ZERO;
+
         match double_speed {
             false => {
                 self.speed_mode = CPUSpeed::NORMAL;
@@ -77,6 +80,10 @@ impl Clock {
                     Duration::from_secs_f64(TICKS_PER_FRAME as f64 / DOUBLE_CLOCK_SPEED as f64);
             }
         }
+
+        if !throttled {
+            self.frame_target_duration = Duration::ZERO;
+        }
         info!("Speed switched to {:?}", self.speed_mode);
     }
 
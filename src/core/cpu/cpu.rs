@@ -2,12 +2,16 @@ use std::time::Instant;
 
 use super::clock::Clock;
 use super::instructions::{stack::_push, CPURegisterId, Instruction, INSTRUCTIONS};
-use super::interrupts::{InterruptFlags, INTERRUPT_ENABLE, INTERRUPT_FLAGS};
+use super::interrupts::InterruptFlags;
 use crate::core::cpu::CPUSpeed;
 use crate::core::mem::bus::Bus;
+use crate::core::save_state::{SaveState, StateError};
 use crate::dbg::print_serial;
-use crate::debugger::{cpu_metrics, CpuMetricFields};
-use crate::flag_set;
+use crate::debugger::{CpuMetricFields, CpuMetrics, MetricsHandler};
+use crate::{flag_set, save_state};
+
+// M-cycles the CPU is paused for while switching speed
+const SPEED_SWITCH_CYCLES: u16 = 2050;
 
 #[allow(nonstandard_style)]
 pub mod CPUFlags {
@@ -32,6 +36,19 @@ pub struct CPURegisters {
     pub sp: u16,
 }
 
+save_state!(CPURegisters {
+    a,
+    f,
+    b,
+    c,
+    d,
+    e,
+    h,
+    l,
+    pc,
+    sp
+});
+
 impl CPURegisters {
     pub fn new(pc: u16) -> Self {
         Self {
@@ -58,10 +75,21 @@ pub struct LR35902CPU {
 
     pub current_instruction: &'static Instruction,
     pub halt: bool,
+    // PC is not incremented after the next opcode fetch
+    pub halt_bug: bool,
+    // STOP mode, left when a selected joypad button is pressed
+    pub stopped: bool,
     pub int_master: bool,
     pub enabling_ints: bool,
 
     pub clock: Clock,
+
+    // M-cycles elapsed since the start of the current tick
+    cycles: u16,
+    // M-cycles elapsed since power on
+    pub total_cycles: u64,
+
+    pub metrics: MetricsHandler<CpuMetrics>,
 }
 
 impl LR35902CPU {
@@ -72,16 +100,54 @@ impl LR35902CPU {
             serial,
             current_instruction: &INSTRUCTIONS[0],
             halt: false,
+            halt_bug: false,
+            stopped: false,
             registers: CPURegisters::new(pc),
             int_master: false,
             enabling_ints: false,
             clock: Clock::new(speed),
+            cycles: 0,
+            total_cycles: 0,
+            metrics: MetricsHandler::default(),
         }
     }
 
-    pub fn tick(&mut self) -> u8 {
+    // Power cycle, once the bus has been reset
+    pub fn reset(&mut self, speed: CPUSpeed) {
+        let pc = if self.bus.booting { 0 } else { 0x100 };
+        self.registers = CPURegisters::new(pc);
+        self.current_instruction = &INSTRUCTIONS[0];
+        self.halt = false;
+        self.halt_bug = false;
+        self.stopped = false;
+        self.int_master = false;
+        self.enabling_ints = false;
+        self.clock = Clock::new(speed);
+        self.cycles = 0;
+        self.total_cycles = 0;
+    }
+
+    fn instruction_idx(&self) -> usize {
+        INSTRUCTIONS
+            .iter()
+            .position(|i| std::ptr::eq(i, self.current_instruction))
+            .unwrap()
+    }
+
+    pub fn tick(&mut self) -> u16 {
+        self.cycles = 0;
         let mut cycles: u8 = 1;
 
+        if self.stopped {
+            if !self.bus.io.joypad.pressed() {
+                // Everything is frozen, only keep the pace
+                self.clock.tick();
+                self.total_cycles += 1;
+                return 1;
+            }
+            self.stopped = false;
+        }
+
         if !self.halt {
             self.set_instruction();
 
@@ -90,10 +156,20 @@ impl LR35902CPU {
             }
 
             cycles = (self.current_instruction.func)(self);
-            cpu_metrics().count(CpuMetricFields::INSTRUCTIONS, 1);
+            self.metrics.count(CpuMetricFields::INSTRUCTIONS, 1);
+        }
+
+        // Remaining cycles are internal ones, where the bus is left idle
+        while self.cycles < cycles as u16 {
+            self.tick_cycle();
+        }
+
+        // The CPU is stalled while a VRAM DMA is copying data
+        while self.bus.vram_dma_stalling() {
+            self.tick_cycle();
         }
 
-        if INTERRUPT_FLAGS.get() > 0 {
+        if self.pending_ints() > 0 {
             // Interrupt pending, wake up
             self.halt = false;
         }
@@ -108,27 +184,66 @@ impl LR35902CPU {
             self.int_master = true;
         }
 
-        cpu_metrics().count(CpuMetricFields::CYCLES, cycles as u32);
-        cycles
+        self.metrics
+            .count(CpuMetricFields::CYCLES, self.cycles as u32);
+        self.total_cycles += self.cycles as u64;
+        self.cycles
     }
 
     pub fn step(&mut self) {
         let start = Instant::now();
         let cycles = self.tick();
-        for _ in 0..cycles {
-            let div_apu = self.bus.io.timer.tick(self.clock.speed_mode);
-            self.bus.tick();
-            self.bus.io.ppu.tick(self.clock.speed_mode);
-            self.bus.io.apu.tick(div_apu, self.clock.speed_mode);
-            self.clock.tick();
-        }
 
-        cpu_metrics().mean_time(
+        self.metrics.mean_time(
             CpuMetricFields::TICK_TIME,
             (Instant::now() - start) / (cycles as u32 * 4),
         );
     }
 
+    // Toggle the armed speed switch, which pauses the CPU for a while
+    pub fn switch_speed(&mut self) {
+        let double_speed = self.bus.switch_speed();
+        self.clock.switch_speed(double_speed);
+
+        for _ in 0..SPEED_SWITCH_CYCLES {
+            self.tick_cycle();
+        }
+    }
+
+    // Advance every peripheral by one M-cycle
+    pub fn tick_cycle(&mut self) {
+        let div_apu = self
+            .bus
+            .io
+            .timer
+            .tick(self.clock.speed_mode, &mut self.bus.interrupts);
+        self.bus.tick(self.clock.speed_mode, self.halt);
+        self.bus
+            .io
+            .ppu
+            .tick(self.clock.speed_mode, &mut self.bus.interrupts);
+        self.bus.io.apu.tick(div_apu, self.clock.speed_mode);
+        self.clock.tick();
+        self.cycles += 1;
+    }
+
+    pub fn read(&mut self, addr: u16) -> u8 {
+        let v = self.bus.cpu_read(addr);
+        self.tick_cycle();
+        v
+    }
+
+    pub fn read16(&mut self, addr: u16) -> u16 {
+        let lo = self.read(addr);
+        let hi = self.read(addr.wrapping_add(1));
+        ((hi as u16) << 8) | lo as u16
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        self.bus.cpu_write(addr, value);
+        self.tick_cycle();
+    }
+
     pub fn set_register(&mut self, register: &CPURegisterId, value: u16) {
         match register {
             CPURegisterId::A => {
@@ -215,11 +330,11 @@ impl LR35902CPU {
     }
 
     pub fn inc_sp(&mut self) {
-        self.registers.sp += 1;
+        self.registers.sp = self.registers.sp.wrapping_add(1);
     }
 
     pub fn dec_sp(&mut self) {
-        self.registers.sp -= 1;
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
     }
 
     pub fn sp(&self) -> u16 {
@@ -249,41 +364,49 @@ impl LR35902CPU {
     }
 
     pub fn set_instruction(&mut self) {
-        let mut opcode = self.bus.read(self.registers.pc) as usize;
-        self.registers.pc += 1;
+        let mut opcode = self.read(self.registers.pc) as usize;
+        if self.halt_bug {
+            self.halt_bug = false;
+        } else {
+            self.registers.pc += 1;
+        }
 
         if opcode == 0xcb {
-            opcode = (1 << 8) | self.bus.read(self.registers.pc) as usize;
+            opcode = (1 << 8) | self.read(self.registers.pc) as usize;
             self.registers.pc += 1;
         }
 
         self.current_instruction = &INSTRUCTIONS[opcode];
     }
 
+    // Interrupts both requested and enabled
+    pub fn pending_ints(&self) -> u8 {
+        self.bus.interrupts.pending()
+    }
+
+    // Interrupt dispatch takes 5 M-cycles
     fn handle_ints(&mut self) {
-        let mut handle_int = |int: u8, addr: u16| -> bool {
-            if flag_set!(INTERRUPT_FLAGS.get(), int) && flag_set!(INTERRUPT_ENABLE.get(), int) {
-                // Push high byte of PC
-                _push(self, (self.registers.pc >> 8) as u8);
-
-                // If the interrupt's bit was cleared from IE, cancel the dispatch
-                if !flag_set!(INTERRUPT_ENABLE.get(), int) {
-                    self.halt = false;
-                    self.int_master = false;
-                    self.registers.pc = 0x0000;
-                    return false;
-                }
-
-                // Push low byte of PC
-                _push(self, (self.registers.pc & 0xff) as u8);
-                INTERRUPT_FLAGS.set(INTERRUPT_FLAGS.get() ^ int);
-                self.halt = false;
-                self.int_master = false;
-                self.registers.pc = addr;
-                return true;
-            }
-            false
-        };
+        if self.pending_ints() == 0 {
+            return;
+        }
+
+        self.int_master = false;
+        self.halt = false;
+
+        // Two internal cycles before pushing PC
+        self.tick_cycle();
+        self.tick_cycle();
+
+        // Push high byte of PC
+        _push(self, (self.registers.pc >> 8) as u8);
+
+        // The vector is only picked now, as pushing to IE may have cancelled the
+        // interrupt. If nothing is pending anymore, jump to 0x0000
+        let pending = self.pending_ints();
+
+        // Push low byte of PC
+        _push(self, (self.registers.pc & 0xff) as u8);
+        self.tick_cycle();
 
         for (int, addr) in [
             (InterruptFlags::VBLANK, 0x40),
@@ -292,9 +415,50 @@ impl LR35902CPU {
             (InterruptFlags::SERIAL, 0x58),
             (InterruptFlags::JOYPAD, 0x60),
         ] {
-            if handle_int(int, addr) {
+            if flag_set!(pending, int) {
+                self.bus.interrupts.flags ^= int;
+                self.registers.pc = addr;
                 return;
             }
         }
+
+        self.registers.pc = 0x0000;
+    }
+}
+
+impl SaveState for LR35902CPU {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        self.registers.save_state(out);
+        self.instruction_idx().save_state(out);
+        self.halt.save_state(out);
+        self.halt_bug.save_state(out);
+        self.stopped.save_state(out);
+        self.int_master.save_state(out);
+        self.enabling_ints.save_state(out);
+        self.total_cycles.save_state(out);
+        self.bus.save_state(out);
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        self.registers.load_state(data)?;
+
+        let mut idx = 0usize;
+        idx.load_state(data)?;
+        self.current_instruction = INSTRUCTIONS.get(idx).ok_or(StateError::Invalid)?;
+
+        self.halt.load_state(data)?;
+        self.halt_bug.load_state(data)?;
+        self.stopped.load_state(data)?;
+        self.int_master.load_state(data)?;
+        self.enabling_ints.load_state(data)?;
+        self.total_cycles.load_state(data)?;
+        self.bus.load_state(data)?;
+
+        // The clock follows the speed of the loaded state
+        let double_speed = flag_set!(self.bus.speed_mode, 0x80);
+        if double_speed != matches!(self.clock.speed_mode, CPUSpeed::DOUBLE) {
+            self.clock.switch_speed(double_speed);
+        }
+        Ok(())
     }
 }
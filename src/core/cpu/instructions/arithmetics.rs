@@ -15,7 +15,7 @@ pub fn add(cpu: &mut LR35902CPU) -> u8 {
         AddrMode::R_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
             op1 = cpu.get_register(instr.reg1.as_ref().unwrap()) as u32;
-            op2 = cpu.bus.read(addr) as u32;
+            op2 = cpu.read(addr) as u32;
             result = op1 + op2;
             z = if (result as u8) == 0 { 1 } else { 0 };
             c = result > 0xff;
@@ -32,7 +32,7 @@ pub fn add(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::R_IMM => {
             op1 = cpu.get_register(instr.reg1.as_ref().unwrap()) as u32;
-            op2 = cpu.bus.read(cpu.pc()) as u32;
+            op2 = cpu.read(cpu.pc()) as u32;
             result = op1 + op2;
             cpu.inc_pc(1);
             z = if (result as u8) == 0 { 1 } else { 0 };
@@ -41,7 +41,7 @@ pub fn add(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::R16_IMM16 => {
             op1 = cpu.get_register16(instr.reg1.as_ref().unwrap()) as u32;
-            op2 = cpu.bus.read16(cpu.pc()) as u32;
+            op2 = cpu.read16(cpu.pc()) as u32;
             result = op1 + op2;
             cpu.inc_pc(2);
             c = result > 0xffff;
@@ -56,7 +56,7 @@ pub fn add(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::R16_SIMM => {
             let op1 = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            let op2 = cpu.bus.read(cpu.pc()) as i8;
+            let op2 = cpu.read(cpu.pc()) as i8;
             result = op1.wrapping_add_signed(op2.into()) as u32;
             cpu.inc_pc(1);
             z = 0;
@@ -83,14 +83,14 @@ pub fn adc(cpu: &mut LR35902CPU) -> u8 {
     match instr.addr_mode {
         AddrMode::R_IMM => {
             op1 = cpu.get_register(instr.reg1.as_ref().unwrap()) as u32;
-            op2 = cpu.bus.read(cpu.pc()) as u32;
+            op2 = cpu.read(cpu.pc()) as u32;
             result = op1 + op2 + c as u32;
             cpu.inc_pc(1);
         }
         AddrMode::R_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
             op1 = cpu.get_register(instr.reg1.as_ref().unwrap()) as u32;
-            op2 = cpu.bus.read(addr) as u32;
+            op2 = cpu.read(addr) as u32;
             result = op1 + op2 + c as u32;
         }
         AddrMode::R_R => {
@@ -127,11 +127,11 @@ pub fn sub(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::R_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             result = a as i16 - value as i16;
         }
         AddrMode::R_IMM => {
-            value = cpu.bus.read(cpu.pc());
+            value = cpu.read(cpu.pc());
             cpu.inc_pc(1);
             result = a as i16 - value as i16;
         }
@@ -158,10 +158,10 @@ pub fn sbc(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::R_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
         }
         AddrMode::R_IMM => {
-            value = cpu.bus.read(cpu.pc());
+            value = cpu.read(cpu.pc());
             cpu.inc_pc(1);
         }
         _ => unreachable!(),
@@ -186,8 +186,8 @@ pub fn inc(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            result = cpu.bus.read(addr) as u32 + 1;
-            cpu.bus.write(addr, result as u8);
+            result = cpu.read(addr) as u32 + 1;
+            cpu.write(addr, result as u8);
             cycles = 3;
         }
         AddrMode::R16 => {
@@ -221,8 +221,8 @@ pub fn dec(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            result = cpu.bus.read(addr) as i32 - 1;
-            cpu.bus.write(addr, result as u8);
+            result = cpu.read(addr) as i32 - 1;
+            cpu.write(addr, result as u8);
             cycles = 3;
         }
         AddrMode::R16 => {
@@ -257,10 +257,10 @@ pub fn cp(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::R_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            value = cpu.bus.read(addr) as i16;
+            value = cpu.read(addr) as i16;
         }
         AddrMode::R_IMM => {
-            value = cpu.bus.read(cpu.pc()) as i16;
+            value = cpu.read(cpu.pc()) as i16;
             cpu.inc_pc(1);
         }
         _ => unreachable!(),
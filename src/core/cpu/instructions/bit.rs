@@ -14,10 +14,10 @@ pub fn and(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::R_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
         }
         AddrMode::R_IMM => {
-            value = cpu.bus.read(cpu.pc());
+            value = cpu.read(cpu.pc());
             cpu.inc_pc(1);
         }
         _ => unreachable!(),
@@ -42,10 +42,10 @@ pub fn or(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::R_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
         }
         AddrMode::R_IMM => {
-            value = cpu.bus.read(cpu.pc());
+            value = cpu.read(cpu.pc());
             cpu.inc_pc(1);
         }
         _ => unreachable!(),
@@ -70,10 +70,10 @@ pub fn xor(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::R_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
         }
         AddrMode::R_IMM => {
-            value = cpu.bus.read(cpu.pc());
+            value = cpu.read(cpu.pc());
             cpu.inc_pc(1);
         }
         _ => unreachable!(),
@@ -96,7 +96,7 @@ pub fn bit(cpu: &mut LR35902CPU, nth_bit: u8) -> u8 {
         }
         AddrMode::IMM_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             cycles = 3;
         }
         _ => unreachable!(),
@@ -119,9 +119,9 @@ pub fn res(cpu: &mut LR35902CPU, nth_bit: u8) -> u8 {
         }
         AddrMode::IMM_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             value &= !(1 << nth_bit);
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
@@ -143,9 +143,9 @@ pub fn set(cpu: &mut LR35902CPU, nth_bit: u8) -> u8 {
         }
         AddrMode::IMM_RADDR => {
             let addr = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             value |= 1 << nth_bit;
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
@@ -166,9 +166,9 @@ pub fn swap(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             value = ((value & 0xf) << 4) | ((value & 0xf0) >> 4);
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
@@ -194,10 +194,10 @@ pub fn rl(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             new_carry = value >> 7;
             value = (value << 1) | old_carry;
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
@@ -233,10 +233,10 @@ pub fn rlc(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             new_carry = value >> 7;
             value = (value << 1) | new_carry;
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
@@ -272,10 +272,10 @@ pub fn rr(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             new_carry = value & 1;
             value = (value >> 1) | (old_carry << 7);
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
@@ -311,10 +311,10 @@ pub fn rrc(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             new_carry = value & 1;
             value = (value >> 1) | ((value & 1) << 7);
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
@@ -349,10 +349,10 @@ pub fn sla(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             new_carry = (value >> 7) & 1;
             value <<= 1;
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
@@ -377,10 +377,10 @@ pub fn sra(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             new_carry = value & 1;
             value = (value >> 1) | (value & 0x80);
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
@@ -405,10 +405,10 @@ pub fn srl(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::RADDR => {
             let addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            value = cpu.bus.read(addr);
+            value = cpu.read(addr);
             new_carry = value & 1;
             value >>= 1;
-            cpu.bus.write(addr, value);
+            cpu.write(addr, value);
             cycles = 4;
         }
         _ => unreachable!(),
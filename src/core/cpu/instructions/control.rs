@@ -21,14 +21,14 @@ fn check_cond(cpu: &mut LR35902CPU, cond: Option<&CondType>) -> bool {
 pub fn jr(cpu: &mut LR35902CPU) -> u8 {
     let instr = cpu.current_instruction;
 
+    let mut pc = cpu.pc();
+    let offset = cpu.read(pc) as i8;
+
     if !check_cond(cpu, instr.condition.as_ref()) {
         cpu.inc_pc(1);
         return 2;
     }
 
-    let mut pc = cpu.pc();
-
-    let offset = cpu.bus.read(pc) as i8;
     pc = u16::try_from((pc as i32) + (offset as i32) + 1).expect("Could not convert for jr");
 
     cpu.set_register(&CPURegisterId::PC, pc);
@@ -38,20 +38,18 @@ pub fn jr(cpu: &mut LR35902CPU) -> u8 {
 pub fn jp(cpu: &mut LR35902CPU) -> u8 {
     let instr = cpu.current_instruction;
     let pc: u16;
-    let mut cycles: u8 = 4;
-
-    if !check_cond(cpu, instr.condition.as_ref()) {
-        if matches!(instr.addr_mode, AddrMode::IMMADDR) {
-            cpu.inc_pc(2);
-        }
-        return 3;
-    }
+    let mut cycles: u8 = 1;
 
     match instr.addr_mode {
         AddrMode::R16 => pc = cpu.get_register16(instr.reg1.as_ref().unwrap()),
         AddrMode::IMMADDR => {
-            pc = cpu.bus.read16(cpu.pc());
-            cycles = 1;
+            pc = cpu.read16(cpu.pc());
+            cycles = 4;
+
+            if !check_cond(cpu, instr.condition.as_ref()) {
+                cpu.inc_pc(2);
+                return 3;
+            }
         }
         _ => unreachable!(),
     }
@@ -63,17 +61,20 @@ pub fn jp(cpu: &mut LR35902CPU) -> u8 {
 pub fn call(cpu: &mut LR35902CPU) -> u8 {
     let instr = cpu.current_instruction;
 
+    let pc = cpu.read16(cpu.pc());
+    cpu.inc_pc(2);
+
     if !check_cond(cpu, instr.condition.as_ref()) {
-        cpu.inc_pc(2);
         return 3;
     }
 
-    let v = cpu.pc() + 2;
+    // Internal delay before pushing the return address
+    cpu.tick_cycle();
+
+    let v = cpu.pc();
     _push(cpu, (v >> 8) as u8);
     _push(cpu, (v & 0xff) as u8);
 
-    let pc = cpu.bus.read16(cpu.pc());
-
     cpu.set_register(&CPURegisterId::PC, pc);
     6
 }
@@ -82,21 +83,24 @@ pub fn ret(cpu: &mut LR35902CPU) -> u8 {
     let instr = cpu.current_instruction;
     let mut cycles: u8 = 4;
 
-    if !check_cond(cpu, instr.condition.as_ref()) {
-        return 2;
-    }
-
     if instr.condition.is_some() {
+        // Internal cycle spent on evaluating the condition
+        cpu.tick_cycle();
         cycles = 5;
     }
 
+    if !check_cond(cpu, instr.condition.as_ref()) {
+        return 2;
+    }
+
     let pc: u16 = (_pop(cpu) as u16) | ((_pop(cpu) as u16) << 8);
     cpu.set_register(&CPURegisterId::PC, pc);
     cycles
 }
 
 pub fn reti(cpu: &mut LR35902CPU) -> u8 {
-    cpu.enabling_ints = true;
+    // Unlike EI, IME is set right away
+    cpu.int_master = true;
     cpu.halt = false;
 
     let pc: u16 = (_pop(cpu) as u16) | ((_pop(cpu) as u16) << 8);
@@ -105,6 +109,9 @@ pub fn reti(cpu: &mut LR35902CPU) -> u8 {
 }
 
 pub fn rst(cpu: &mut LR35902CPU, addr: u8) -> u8 {
+    // Internal delay before pushing the return address
+    cpu.tick_cycle();
+
     let v = cpu.pc();
     _push(cpu, (v >> 8) as u8);
     _push(cpu, (v & 0xff) as u8);
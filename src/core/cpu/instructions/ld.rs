@@ -8,25 +8,24 @@ pub fn ldr(cpu: &mut LR35902CPU) -> u8 {
 
     match instr.addr_mode {
         AddrMode::R16_IMM16 => {
-            value = cpu.bus.read16(cpu.pc());
+            value = cpu.read16(cpu.pc());
             cpu.inc_pc(2);
+            cycles = 3;
         }
         AddrMode::R_R => {
             value = cpu.get_register(instr.reg2.as_ref().unwrap()) as u16;
             cycles = 1;
         }
         AddrMode::R_IMM => {
-            value = cpu.bus.read(cpu.pc()) as u16;
+            value = cpu.read(cpu.pc()) as u16;
             cpu.inc_pc(1);
         }
         AddrMode::R_RADDR => {
-            value = cpu
-                .bus
-                .read(cpu.get_register16(instr.reg2.as_ref().unwrap())) as u16;
+            value = cpu.read(cpu.get_register16(instr.reg2.as_ref().unwrap())) as u16;
         }
         AddrMode::R16_R16_IMM => {
             let sp = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            let offset = cpu.bus.read(cpu.pc()) as i8;
+            let offset = cpu.read(cpu.pc()) as i8;
             cpu.inc_pc(1);
 
             value = sp.wrapping_add_signed(offset.into());
@@ -36,9 +35,9 @@ pub fn ldr(cpu: &mut LR35902CPU) -> u8 {
             cycles = 3;
         }
         AddrMode::R_IMMADDR => {
-            let addr = cpu.bus.read16(cpu.pc());
+            let addr = cpu.read16(cpu.pc());
             cpu.inc_pc(2);
-            value = cpu.bus.read(addr) as u16;
+            value = cpu.read(addr) as u16;
             cycles = 4;
         }
         AddrMode::R16_R16 => {
@@ -53,7 +52,7 @@ pub fn ldr(cpu: &mut LR35902CPU) -> u8 {
 
 pub fn ldm(cpu: &mut LR35902CPU) -> u8 {
     let instr = cpu.current_instruction;
-    let addr: u16;
+    let mut addr: u16;
     let value: u8;
     let mut cycles: u8 = 2;
 
@@ -64,29 +63,30 @@ pub fn ldm(cpu: &mut LR35902CPU) -> u8 {
         }
         AddrMode::IMMADDR_R => {
             value = cpu.get_register(instr.reg2.as_ref().unwrap());
-            addr = cpu.bus.read16(cpu.pc());
+            addr = cpu.read16(cpu.pc());
             cpu.inc_pc(2);
             cycles = 4;
         }
         AddrMode::IMMADDR_R16 => {
-            addr = cpu.bus.read16(cpu.pc());
+            addr = cpu.read16(cpu.pc());
             cpu.inc_pc(2);
 
             let reg = cpu.get_register16(instr.reg2.as_ref().unwrap());
-            cpu.bus.write(addr + 1, (reg >> 8) as u8);
-            value = (reg & 0xff) as u8;
+            cpu.write(addr, (reg & 0xff) as u8);
+            addr = addr.wrapping_add(1);
+            value = (reg >> 8) as u8;
             cycles = 5;
         }
         AddrMode::RADDR_IMM => {
             addr = cpu.get_register16(instr.reg1.as_ref().unwrap());
-            value = cpu.bus.read(cpu.pc());
+            value = cpu.read(cpu.pc());
             cpu.inc_pc(1);
             cycles = 3;
         }
         _ => unreachable!(),
     }
 
-    cpu.bus.write(addr, value);
+    cpu.write(addr, value);
     cycles
 }
 
@@ -99,26 +99,25 @@ pub fn ldh(cpu: &mut LR35902CPU) -> u8 {
         let mut addr = 0xff00;
         if matches!(instr.addr_mode, AddrMode::IMM_R) {
             // opcode 0xe0
-            addr += cpu.bus.read(cpu.pc()) as u16;
+            addr += cpu.read(cpu.pc()) as u16;
             cpu.inc_pc(1);
         } else {
             // opcode 0xe2
             addr += cpu.get_register(instr.reg1.as_ref().unwrap()) as u16;
             cycles = 2;
         }
-        cpu.bus
-            .write(addr, cpu.get_register(instr.reg2.as_ref().unwrap()));
+        cpu.write(addr, cpu.get_register(instr.reg2.as_ref().unwrap()));
     } else {
         let value: u16;
         if matches!(instr.addr_mode, AddrMode::R_IMMADDR) {
             // opcode 0xf0
-            let addr = cpu.bus.read(cpu.pc()) as u16;
+            let addr = cpu.read(cpu.pc()) as u16;
             cpu.inc_pc(1);
-            value = cpu.bus.read(0xff00 + addr) as u16;
+            value = cpu.read(0xff00 + addr) as u16;
         } else {
             // opcode 0xf2
             let reg = cpu.get_register(instr.reg2.as_ref().unwrap()) as u16;
-            value = cpu.bus.read(0xff00 + reg) as u16;
+            value = cpu.read(0xff00 + reg) as u16;
             cycles = 2;
         }
         cpu.set_register(instr.reg1.as_ref().unwrap(), value);
@@ -133,13 +132,12 @@ pub fn ldi(cpu: &mut LR35902CPU) -> u8 {
         // opcode 0x22
         let hl = cpu.get_register16(instr.reg1.as_ref().unwrap());
         cpu.set_register(instr.reg1.as_ref().unwrap(), hl + 1);
-        cpu.bus
-            .write(hl, cpu.get_register(instr.reg2.as_ref().unwrap()));
+        cpu.write(hl, cpu.get_register(instr.reg2.as_ref().unwrap()));
     } else {
         // opcode 0x2a
         let hl = cpu.get_register16(instr.reg2.as_ref().unwrap());
         cpu.set_register(instr.reg2.as_ref().unwrap(), hl + 1);
-        let value = cpu.bus.read(hl);
+        let value = cpu.read(hl);
         cpu.set_register(instr.reg1.as_ref().unwrap(), value as u16);
     }
     2
@@ -152,13 +150,12 @@ pub fn ldd(cpu: &mut LR35902CPU) -> u8 {
         // opcode 0x32
         let hl = cpu.get_register16(instr.reg1.as_ref().unwrap());
         cpu.set_register(instr.reg1.as_ref().unwrap(), hl - 1);
-        cpu.bus
-            .write(hl, cpu.get_register(instr.reg2.as_ref().unwrap()));
+        cpu.write(hl, cpu.get_register(instr.reg2.as_ref().unwrap()));
     } else {
         //opcode 0x3a
         let hl = cpu.get_register16(instr.reg2.as_ref().unwrap());
         cpu.set_register(instr.reg2.as_ref().unwrap(), hl - 1);
-        let value = cpu.bus.read(hl);
+        let value = cpu.read(hl);
         cpu.set_register(instr.reg1.as_ref().unwrap(), value as u16);
     }
     2
@@ -1,8 +1,7 @@
 use super::CPURegisterId;
-use crate::{
-    core::cpu::cpu::{CPUFlags, LR35902CPU},
-    flag_set,
-};
+use crate::core::cpu::cpu::{CPUFlags, LR35902CPU};
+
+use log::warn;
 
 pub fn ccf(cpu: &mut LR35902CPU) -> u8 {
     let c = cpu.get_flag(CPUFlags::C);
@@ -55,20 +54,44 @@ pub fn daa(cpu: &mut LR35902CPU) -> u8 {
 }
 
 pub fn halt(cpu: &mut LR35902CPU) -> u8 {
-    cpu.halt = true;
+    let int_pending = cpu.pending_ints() != 0;
+
+    if int_pending && !cpu.int_master {
+        // HALT bug: HALT is skipped and the next byte is read twice
+        cpu.halt_bug = true;
+    } else {
+        cpu.halt = true;
+    }
     1
 }
 
+// https://gbdev.io/pandocs/Reducing_Power_Consumption.html#using-the-stop-instruction
 pub fn stop(cpu: &mut LR35902CPU) -> u8 {
-    cpu.inc_pc(1);
-    if flag_set!(cpu.bus.speed_mode, 1) {
-        // Clear bit 0 & flip bit 7
-        cpu.bus.speed_mode = ((cpu.bus.speed_mode ^ (1 << 7)) >> 1) << 1;
-        cpu.clock.switch_speed(flag_set!(cpu.bus.speed_mode, 0x80));
+    let int_pending = cpu.pending_ints() != 0;
+
+    // The second byte is skipped unless an interrupt is pending
+    if !int_pending {
+        cpu.inc_pc(1);
+    }
+
+    if cpu.bus.io.joypad.pressed() {
+        // STOP is a 1-byte opcode doing nothing, or a HALT
+        cpu.halt = !int_pending;
+        return 1;
+    }
+
+    // DIV is reset in every other case
+    cpu.bus.io.timer.write(0xff04, 0);
+
+    if !cpu.bus.speed_switch_armed() {
+        cpu.stopped = true;
+        return 1;
+    }
+
+    if int_pending && cpu.int_master {
+        warn!("STOP with a pending interrupt and IME set, the CPU would glitch");
     }
-    // TODO: Make the CPU chill for 2050 M-cycles (~1.025 ms with CPU @ 2.10 MHz) somehow
-    // Note that we can't return 2051 cycles here as the CPU should be in a strange state
-    // https://gbdev.io/pandocs/CGB_Registers.html#ff4d--key1-cgb-mode-only-prepare-speed-switch
+    cpu.switch_speed();
     1
 }
 
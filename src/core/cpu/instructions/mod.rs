@@ -5,6 +5,8 @@ mod ld;
 mod misc;
 pub mod stack;
 
+use std::str::FromStr;
+
 use crate::core::cpu::cpu::LR35902CPU;
 use arithmetics::*;
 use bit::*;
@@ -63,6 +65,30 @@ pub enum CPURegisterId {
     SP,
 }
 
+impl FromStr for CPURegisterId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "A" => Ok(Self::A),
+            "F" => Ok(Self::F),
+            "B" => Ok(Self::B),
+            "C" => Ok(Self::C),
+            "D" => Ok(Self::D),
+            "E" => Ok(Self::E),
+            "H" => Ok(Self::H),
+            "L" => Ok(Self::L),
+            "AF" => Ok(Self::AF),
+            "BC" => Ok(Self::BC),
+            "DE" => Ok(Self::DE),
+            "HL" => Ok(Self::HL),
+            "SP" => Ok(Self::SP),
+            "PC" => Ok(Self::PC),
+            _ => Err(format!("Invalid register {s}")),
+        }
+    }
+}
+
 type FnType = fn(&mut LR35902CPU) -> u8;
 
 #[derive(PartialEq)]
@@ -2,7 +2,7 @@ use super::CPURegisterId;
 use crate::core::cpu::cpu::LR35902CPU;
 
 pub fn _pop(cpu: &mut LR35902CPU) -> u8 {
-    let v = cpu.bus.read(cpu.sp());
+    let v = cpu.read(cpu.sp());
     cpu.inc_sp();
     v
 }
@@ -30,7 +30,7 @@ pub fn pop(cpu: &mut LR35902CPU) -> u8 {
 
 pub fn _push(cpu: &mut LR35902CPU, value: u8) {
     cpu.dec_sp();
-    cpu.bus.write(cpu.sp(), value);
+    cpu.write(cpu.sp(), value);
 }
 
 pub fn push(cpu: &mut LR35902CPU) -> u8 {
@@ -44,6 +44,9 @@ pub fn push(cpu: &mut LR35902CPU) -> u8 {
         _ => unreachable!(),
     };
 
+    // Internal delay before the SP decrement
+    cpu.tick_cycle();
+
     for reg in regs {
         let mut v = cpu.get_register(reg);
         if reg == &CPURegisterId::F {
@@ -1,9 +1,4 @@
-use std::cell::Cell;
-
-thread_local! {
-    pub static INTERRUPT_FLAGS: Cell<u8> = const { Cell::new(0) };
-    pub static INTERRUPT_ENABLE: Cell<u8> = const { Cell::new(0) };
-}
+use crate::save_state;
 
 #[allow(nonstandard_style)]
 pub mod InterruptFlags {
@@ -14,6 +9,23 @@ pub mod InterruptFlags {
     pub const JOYPAD: u8 = 0x10;
 }
 
-pub fn request_interrupt(int: u8) {
-    INTERRUPT_FLAGS.set(INTERRUPT_FLAGS.get() | int);
+#[derive(Default)]
+pub struct Interrupts {
+    // IF
+    pub flags: u8,
+    // IE
+    pub enable: u8,
+}
+
+save_state!(Interrupts { flags, enable });
+
+impl Interrupts {
+    pub fn request(&mut self, int: u8) {
+        self.flags |= int;
+    }
+
+    // Interrupts both requested and enabled
+    pub fn pending(&self) -> u8 {
+        self.flags & self.enable & 0x1f
+    }
 }
@@ -1,13 +1,17 @@
 use crossbeam_channel::Sender;
-use log::warn;
+use log::{error, info, warn};
+use std::path::Path;
 use std::time::Instant;
 
+use super::blip::BlipBuf;
 use super::channels::{NoiseChannel, PulseChannel, WaveChannel};
+use super::high_pass::HighPass;
+use super::rate_control::RateControl;
+use super::recorder::AudioRecorder;
 use crate::core::cpu::{CPUSpeed, CLOCK_SPEED};
-use crate::flag_set;
+use crate::{flag_set, save_state};
 
-pub const SAMPLE_RATE: f32 = 44100.0;
-const TICKS_PER_SAMPLE: f32 = CLOCK_SPEED as f32 / SAMPLE_RATE;
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
 
 #[allow(nonstandard_style, dead_code)]
 pub mod APU_AMC_FLAGS {
@@ -42,6 +46,28 @@ pub mod APU_MVVP_FLAGS {
 pub struct MasterVolume {
     pub left: u8,
     pub right: u8,
+    pub vin_left: bool,
+    pub vin_right: bool,
+}
+
+save_state!(MasterVolume {
+    left,
+    right,
+    vin_left,
+    vin_right
+});
+
+impl MasterVolume {
+    fn read(&self) -> u8 {
+        (self.vin_left as u8) << 7 | self.left << 4 | (self.vin_right as u8) << 3 | self.right
+    }
+
+    fn write(&mut self, value: u8) {
+        self.left = (value & APU_MVVP_FLAGS::VOLUME_LEFT) >> 4;
+        self.right = value & APU_MVVP_FLAGS::VOLUME_RIGHT;
+        self.vin_left = flag_set!(value, APU_MVVP_FLAGS::VIN_LEFT);
+        self.vin_right = flag_set!(value, APU_MVVP_FLAGS::VIN_RIGHT);
+    }
 }
 
 pub struct APU {
@@ -56,7 +82,17 @@ pub struct APU {
     pub channel3: WaveChannel,
     pub channel4: NoiseChannel,
 
-    ticks_since_sample: f32,
+    // Analog input from the cartridge, mixed in through the NR50 VIN bits. No
+    // supported cartridge drives it, so it stays silent
+    vin: f32,
+
+    is_cgb: bool,
+    sample_rate: u32,
+    blip: BlipBuf<2>,
+    rate_control: RateControl,
+    amplitude: [f32; 2],
+    hpf: HighPass<2>,
+    recorder: Option<AudioRecorder>,
     pub last_sample: f32,
     pub last_sample_at: Instant,
     audio_channel_sd: Sender<[f32; 2]>,
@@ -68,8 +104,20 @@ pub struct APU {
     dbg_volume_right: f32,
 }
 
+save_state!(APU {
+    master_control,
+    panning,
+    master_volume,
+    div_apu,
+    channel1,
+    channel2,
+    channel3,
+    channel4,
+    vin
+});
+
 impl APU {
-    pub fn new(audio_channel_sd: Sender<[f32; 2]>) -> Self {
+    pub fn new(audio_channel_sd: Sender<[f32; 2]>, is_cgb: bool) -> Self {
         Self {
             master_control: 0,
             master_volume: MasterVolume::default(),
@@ -79,7 +127,14 @@ impl APU {
             channel2: PulseChannel::new(false),
             channel3: WaveChannel::new(),
             channel4: NoiseChannel::new(),
-            ticks_since_sample: 0.0,
+            vin: 0.0,
+            is_cgb,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            blip: BlipBuf::new(CLOCK_SPEED, DEFAULT_SAMPLE_RATE),
+            rate_control: RateControl::new(),
+            amplitude: [0.0, 0.0],
+            hpf: HighPass::new(is_cgb, DEFAULT_SAMPLE_RATE),
+            recorder: None,
             last_sample: 0.0,
             last_sample_at: Instant::now(),
             audio_channel_sd,
@@ -91,6 +146,47 @@ impl APU {
         }
     }
 
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.blip = BlipBuf::new(CLOCK_SPEED, sample_rate);
+        self.amplitude = [0.0, 0.0];
+        self.hpf = HighPass::new(self.is_cgb, sample_rate);
+    }
+
+    pub fn set_rate_control(&mut self, rate_control: RateControl) {
+        self.rate_control = rate_control;
+    }
+
+    // Records the output, and each channel if stems is set, at the current
+    // sample rate
+    pub fn start_recording(&mut self, path: &Path, stems: bool) -> std::io::Result<()> {
+        if self.recorder.is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "Audio is already being recorded",
+            ));
+        }
+
+        self.recorder = Some(AudioRecorder::new(
+            path,
+            stems,
+            self.is_cgb,
+            self.sample_rate,
+        )?);
+        info!("Recording audio to {path:?}, stems:{stems}");
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) {
+        if self.recorder.take().is_some() {
+            info!("Audio recording stopped");
+        }
+    }
+
     fn reset(&mut self) {
         self.master_control = 0;
         self.panning = 0;
@@ -108,7 +204,7 @@ impl APU {
             0xff16..=0xff19 => self.channel2.read(addr),
             0xff1a..=0xff1e => self.channel3.read(addr),
             0xff20..=0xff23 => self.channel4.read(addr),
-            0xff24 => self.master_volume.left << 4 | self.master_volume.right,
+            0xff24 => self.master_volume.read(),
             0xff25 => self.panning,
             0xff26 => {
                 if self.enabled() {
@@ -139,10 +235,7 @@ impl APU {
             0xff16..=0xff19 => self.channel2.write(addr, value, self.div_apu),
             0xff1a..=0xff1e => self.channel3.write(addr, value, self.div_apu),
             0xff20..=0xff23 => self.channel4.write(addr, value, self.div_apu),
-            0xff24 => {
-                self.master_volume.left = (value & 0x70) >> 4;
-                self.master_volume.right = value & 0x07;
-            }
+            0xff24 => self.master_volume.write(value),
             0xff25 => self.panning = value,
             0xff26 => {
                 if !self.enabled() && flag_set!(value, APU_AMC_FLAGS::AUDIO_ON) {
@@ -166,82 +259,117 @@ impl APU {
         if div_apu {
             self.div_apu = (self.div_apu + 1) % 8;
 
-            if !self.enabled() {
-                return;
-            }
-
-            if self.div_apu.is_multiple_of(2) {
-                self.channel1.tick_length_timer();
-                self.channel2.tick_length_timer();
-                self.channel3.tick_length_timer();
-                self.channel4.tick_length_timer();
-            }
-
-            if self.div_apu % 4 == 2 {
-                self.channel1.freq_sweep();
-            }
-
-            if self.div_apu == 7 {
-                self.channel1.envelope.tick();
-                self.channel2.envelope.tick();
-                self.channel4.envelope.tick();
+            if self.enabled() {
+                self.tick_frame_sequencer();
             }
         }
 
-        if !self.enabled() {
-            return;
+        // Keep outputting (silent) samples while the APU is off, the audio
+        // output may be pacing the emulation
+        if self.enabled() {
+            self.channel1.tick();
+            self.channel2.tick();
+            self.channel3.tick();
+            self.channel4.tick();
         }
 
-        self.ticks_since_sample += if matches!(speed_mode, CPUSpeed::DOUBLE) {
-            2.0
+        let samples = [
+            self.channel1.sample(),
+            self.channel2.sample(),
+            self.channel3.sample(),
+            self.channel4.sample(),
+        ];
+        let clocks = if matches!(speed_mode, CPUSpeed::DOUBLE) {
+            2
         } else {
-            4.0
+            4
         };
 
-        self.channel1.tick();
-        self.channel2.tick();
-        self.channel3.tick();
-        self.channel4.tick();
+        // Feed the amplitude changes to the band-limited synthesis, at the
+        // real APU clock (unaffected by the double speed mode)
+        let amplitude = self.mix_amplitude(samples);
+        for (side, (new, old)) in amplitude.iter().zip(self.amplitude).enumerate() {
+            let delta = new - old;
+            if delta != 0.0 {
+                self.blip.add_delta(side, delta);
+            }
+        }
+        self.amplitude = amplitude;
+
+        self.blip.adjust_rate(self.rate_control.ratio());
+        self.blip.advance(clocks);
+
+        while let Some(samples) = self.blip.read() {
+            let samples = self.hpf.apply(samples);
+            self.last_sample = (samples[0] + samples[1]) / 2.0;
+            self.last_sample_at = Instant::now();
 
-        if self.ticks_since_sample >= TICKS_PER_SAMPLE {
-            self.ticks_since_sample -= TICKS_PER_SAMPLE;
-            let samples = self.mix();
+            let samples = self.mix(samples);
             _ = self.audio_channel_sd.send(samples);
         }
+
+        if let Some(recorder) = &mut self.recorder {
+            if let Err(e) = recorder.tick(amplitude, samples, clocks) {
+                error!("Audio recording failed: {e}");
+                self.recorder = None;
+            }
+        }
     }
 
-    fn hpf(&mut self, sample: f32) {
-        let alpha = 0.8;
-        self.last_sample = alpha * self.last_sample + (1.0 - alpha) * sample;
+    fn tick_frame_sequencer(&mut self) {
+        if self.div_apu.is_multiple_of(2) {
+            self.channel1.tick_length_timer();
+            self.channel2.tick_length_timer();
+            self.channel3.tick_length_timer();
+            self.channel4.tick_length_timer();
+        }
+
+        if self.div_apu % 4 == 2 {
+            self.channel1.freq_sweep();
+        }
+
+        if self.div_apu == 7 {
+            self.channel1.envelope.tick();
+            self.channel2.envelope.tick();
+            self.channel4.envelope.tick();
+        }
     }
 
-    fn mix_sample(&mut self) -> f32 {
-        let mut sample = 0.0;
+    fn mix_amplitude(&self, samples: [f32; 4]) -> [f32; 2] {
+        // Route each channel to the left and/or right output, following NR51
+        let (mut left, mut right) = (0.0, 0.0);
+        for (i, sample) in samples.iter().enumerate() {
+            if flag_set!(self.panning, APU_SP_FLAGS::CH1_LEFT << i) {
+                left += sample;
+            }
+            if flag_set!(self.panning, APU_SP_FLAGS::CH1_RIGHT << i) {
+                right += sample;
+            }
+        }
 
-        sample += self.channel1.sample();
-        sample += self.channel2.sample();
-        sample += self.channel3.sample();
-        sample += self.channel4.sample();
+        if self.master_volume.vin_left {
+            left += self.vin;
+        }
+        if self.master_volume.vin_right {
+            right += self.vin;
+        }
 
-        sample /= 4.0;
-        self.hpf(sample);
-        self.last_sample_at = Instant::now();
-        self.last_sample
+        [
+            left / 4.0 * ((self.master_volume.left as f32 + 1.0) / 8.0),
+            right / 4.0 * ((self.master_volume.right as f32 + 1.0) / 8.0),
+        ]
     }
 
-    fn mix(&mut self) -> [f32; 2] {
+    fn mix(&self, [left, right]: [f32; 2]) -> [f32; 2] {
         if self.muted {
             return [0.0, 0.0];
         }
 
-        let sample = self.mix_sample() * (1. + self.user_volume * 3.).log2() * self.dbg_volume;
-
-        let sample_left =
-            sample * ((self.master_volume.left as f32 + 1.0) / 8.0) * self.dbg_volume_left;
-        let sample_right =
-            sample * ((self.master_volume.right as f32 + 1.0) / 8.0) * self.dbg_volume_right;
-
-        [sample_left, sample_right]
+        let volume = (1. + self.user_volume * 3.).log2() * self.dbg_volume;
+        [
+            left * volume * self.dbg_volume_left,
+            right * volume * self.dbg_volume_right,
+        ]
     }
 
     pub fn mute(&mut self, muted: bool) {
@@ -0,0 +1,103 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+// Sub-sample resolution of the step positions
+const PHASES: usize = 32;
+// Taps of the band-limited impulse, the output is delayed by half of it
+const WIDTH: usize = 16;
+// Fraction of the output Nyquist frequency kept by the low-pass
+const CUTOFF: f64 = 0.9;
+
+// Band-limited step synthesis, in the spirit of blargg's Blip_Buffer.
+// Amplitude changes are added as deltas spread over a windowed sinc impulse at
+// their exact (fractional) output position, the output is the running sum of
+// those deltas. This avoids the aliasing of point sampling the channels
+pub struct BlipBuf<const N: usize> {
+    // Output samples per input clock
+    base_ratio: f64,
+    ratio: f64,
+    // Position of the next delta, in output samples from the front of deltas
+    time: f64,
+    deltas: VecDeque<[f32; N]>,
+    integrator: [f32; N],
+    kernel: Vec<[f32; WIDTH]>,
+}
+
+impl<const N: usize> BlipBuf<N> {
+    pub fn new(clock_rate: u32, sample_rate: u32) -> Self {
+        Self {
+            base_ratio: sample_rate as f64 / clock_rate as f64,
+            ratio: sample_rate as f64 / clock_rate as f64,
+            time: 0.0,
+            deltas: VecDeque::from(vec![[0.0; N]; WIDTH + 1]),
+            integrator: [0.0; N],
+            kernel: Self::build_kernel(),
+        }
+    }
+
+    fn build_kernel() -> Vec<[f32; WIDTH]> {
+        (0..=PHASES)
+            .map(|phase| {
+                let frac = phase as f64 / PHASES as f64;
+                let mut taps = [0.0; WIDTH];
+
+                for (i, tap) in taps.iter_mut().enumerate() {
+                    let x = i as f64 - (WIDTH / 2) as f64 + 1.0 - frac;
+                    let sinc = if x == 0.0 {
+                        1.0
+                    } else {
+                        (PI * CUTOFF * x).sin() / (PI * CUTOFF * x)
+                    };
+                    // Blackman window over the impulse span
+                    let w = (x + (WIDTH / 2) as f64) / WIDTH as f64;
+                    let window = 0.42 - 0.5 * (2.0 * PI * w).cos() + 0.08 * (4.0 * PI * w).cos();
+                    *tap = (sinc * window) as f32;
+                }
+
+                // Each step must add up to exactly its delta
+                let sum: f32 = taps.iter().sum();
+                taps.iter_mut().for_each(|t| *t /= sum);
+                taps
+            })
+            .collect()
+    }
+
+    // Adds an amplitude change on the given output channel at the current time
+    pub fn add_delta(&mut self, channel: usize, delta: f32) {
+        let idx = self.time as usize;
+        let phase = ((self.time - idx as f64) * PHASES as f64) as usize;
+
+        if self.deltas.len() < idx + WIDTH {
+            self.deltas.resize(idx + WIDTH, [0.0; N]);
+        }
+
+        for (i, tap) in self.kernel[phase].iter().enumerate() {
+            self.deltas[idx + i][channel] += delta * tap;
+        }
+    }
+
+    // Scales the output rate, for dynamic rate control
+    pub fn adjust_rate(&mut self, factor: f32) {
+        self.ratio = self.base_ratio * factor as f64;
+    }
+
+    pub fn advance(&mut self, clocks: u32) {
+        self.time += clocks as f64 * self.ratio;
+    }
+
+    // Returns the next output sample once enough clocks went by
+    pub fn read(&mut self) -> Option<[f32; N]> {
+        if self.time < 1.0 {
+            return None;
+        }
+        self.time -= 1.0;
+
+        let delta = self.deltas.pop_front().unwrap_or([0.0; N]);
+        self.deltas.push_back([0.0; N]);
+
+        for (integrator, delta) in self.integrator.iter_mut().zip(delta) {
+            *integrator += delta;
+        }
+        Some(self.integrator)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::core::io::audio::{envelope::Envelope, length_counter::LengthCounter};
+use crate::save_state;
 use log::warn;
 
 #[derive(Default)]
@@ -19,6 +20,17 @@ pub struct NoiseChannel {
     dbg_muted: bool,
 }
 
+save_state!(NoiseChannel {
+    enabled,
+    length_counter,
+    clock_div,
+    lfsr_width,
+    clock_shift,
+    lfsr,
+    div,
+    envelope
+});
+
 impl NoiseChannel {
     pub fn new() -> Self {
         Self {
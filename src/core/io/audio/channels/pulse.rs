@@ -5,6 +5,7 @@ use crate::core::io::audio::{
     length_counter::LengthCounter,
     sweep::{FreqOverflow, Sweep},
 };
+use crate::save_state;
 
 const DUTY_TABLE: [[u8; 8]; 4] = [
     [0, 1, 0, 0, 0, 0, 0, 0], // 12.5%
@@ -31,6 +32,17 @@ pub struct PulseChannel {
     dbg_muted: bool,
 }
 
+save_state!(PulseChannel {
+    enabled,
+    div,
+    sweep,
+    envelope,
+    length_counter,
+    duty_idx,
+    wave_duty,
+    period
+});
+
 impl PulseChannel {
     pub fn new(sweep: bool) -> Self {
         let sweep = if sweep { Some(Sweep::default()) } else { None };
@@ -1,6 +1,7 @@
 use log::warn;
 
 use crate::core::io::audio::length_counter::LengthCounter;
+use crate::save_state;
 
 #[derive(Default)]
 pub struct WaveChannel {
@@ -21,6 +22,17 @@ pub struct WaveChannel {
     dbg_muted: bool,
 }
 
+save_state!(WaveChannel {
+    enabled,
+    dac_enabled,
+    length_counter,
+    div,
+    volume,
+    period,
+    wave_ram_idx,
+    wave_ram
+});
+
 impl WaveChannel {
     pub fn new() -> Self {
         Self {
@@ -1,3 +1,5 @@
+use crate::save_state;
+
 #[derive(Default)]
 pub struct Envelope {
     init_vol: u8,
@@ -9,6 +11,14 @@ pub struct Envelope {
     timer: u8,
 }
 
+save_state!(Envelope {
+    init_vol,
+    direction,
+    period,
+    volume,
+    timer
+});
+
 impl Envelope {
     pub fn set(&mut self, value: u8) {
         self.period = value & 0x7;
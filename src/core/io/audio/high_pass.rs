@@ -0,0 +1,35 @@
+use crate::core::cpu::CLOCK_SPEED;
+
+// Output capacitor charge factors per clock, see pandocs "Obscure behavior"
+const DMG_CHARGE_FACTOR: f64 = 0.999958;
+const CGB_CHARGE_FACTOR: f64 = 0.998943;
+
+// DC-blocking high-pass filter, modeled after the output capacitors
+pub struct HighPass<const N: usize> {
+    capacitors: [f32; N],
+    charge_factor: f32,
+}
+
+impl<const N: usize> HighPass<N> {
+    pub fn new(is_cgb: bool, sample_rate: u32) -> Self {
+        let factor = if is_cgb {
+            CGB_CHARGE_FACTOR
+        } else {
+            DMG_CHARGE_FACTOR
+        };
+
+        Self {
+            capacitors: [0.0; N],
+            charge_factor: factor.powf(CLOCK_SPEED as f64 / sample_rate as f64) as f32,
+        }
+    }
+
+    pub fn apply(&mut self, samples: [f32; N]) -> [f32; N] {
+        let mut out = [0.0; N];
+        for ((out, sample), capacitor) in out.iter_mut().zip(samples).zip(&mut self.capacitors) {
+            *out = sample - *capacitor;
+            *capacitor = sample - *out * self.charge_factor;
+        }
+        out
+    }
+}
@@ -1,3 +1,5 @@
+use crate::save_state;
+
 #[derive(Default, Clone, Copy)]
 pub struct LengthCounter {
     enabled: bool,
@@ -5,6 +7,12 @@ pub struct LengthCounter {
     max: u16,
 }
 
+save_state!(LengthCounter {
+    enabled,
+    value,
+    max
+});
+
 impl LengthCounter {
     pub fn new(max: u16) -> Self {
         Self {
@@ -1,5 +1,10 @@
 pub mod apu;
+pub mod blip;
 mod channels;
 mod envelope;
+pub mod high_pass;
 mod length_counter;
+pub mod rate_control;
+mod recorder;
 mod sweep;
+pub mod wav;
@@ -0,0 +1,38 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+// Maximum deviation of the output rate, small enough to be inaudible
+pub const MAX_RATE_DELTA: f32 = 0.005;
+
+// Dynamic rate control, shared between the audio thread which measures the
+// buffer fill level and the APU which slightly speeds up or slows down its
+// output to keep the buffer half full
+#[derive(Clone)]
+pub struct RateControl {
+    // f32 ratio, stored as bits
+    ratio: Arc<AtomicU32>,
+}
+
+impl RateControl {
+    pub fn new() -> Self {
+        Self {
+            ratio: Arc::new(AtomicU32::new(1f32.to_bits())),
+        }
+    }
+
+    pub fn ratio(&self) -> f32 {
+        f32::from_bits(self.ratio.load(Ordering::Relaxed))
+    }
+
+    // Fill level is between 0 (empty) and 1 (full)
+    pub fn update(&self, fill: f32) {
+        let ratio = 1.0 + MAX_RATE_DELTA * (1.0 - 2.0 * fill.clamp(0.0, 1.0));
+        self.ratio.store(ratio.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl Default for RateControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
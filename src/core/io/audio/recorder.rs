@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use super::blip::BlipBuf;
+use super::high_pass::HighPass;
+use super::wav::WavWriter;
+use crate::core::cpu::CLOCK_SPEED;
+
+const STEM_NAMES: [&str; 4] = ["pulse1", "pulse2", "wave", "noise"];
+
+// Band-limited and filtered output of N channels, written either interleaved
+// in a single file or in one file per channel
+struct Track<const N: usize> {
+    writers: Vec<WavWriter>,
+    blip: BlipBuf<N>,
+    hpf: HighPass<N>,
+    amplitude: [f32; N],
+}
+
+impl<const N: usize> Track<N> {
+    fn new(writers: Vec<WavWriter>, is_cgb: bool, sample_rate: u32) -> Self {
+        Self {
+            writers,
+            blip: BlipBuf::new(CLOCK_SPEED, sample_rate),
+            hpf: HighPass::new(is_cgb, sample_rate),
+            amplitude: [0.0; N],
+        }
+    }
+
+    // Returns the number of frames written
+    fn tick(&mut self, amplitude: [f32; N], clocks: u32) -> std::io::Result<u32> {
+        for (channel, (new, old)) in amplitude.iter().zip(self.amplitude).enumerate() {
+            if *new != old {
+                self.blip.add_delta(channel, new - old);
+            }
+        }
+        self.amplitude = amplitude;
+        self.blip.advance(clocks);
+
+        let mut frames = 0;
+        while let Some(samples) = self.blip.read() {
+            let samples = self.hpf.apply(samples);
+            if let [writer] = self.writers.as_mut_slice() {
+                writer.write(&samples)?;
+            } else {
+                for (writer, sample) in self.writers.iter_mut().zip(samples) {
+                    writer.write(&[sample])?;
+                }
+            }
+            frames += 1;
+        }
+        Ok(frames)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writers.iter_mut().try_for_each(|w| w.flush())
+    }
+}
+
+// Records the APU output to WAV files. It runs its own synthesis at a fixed
+// rate, unaffected by the dynamic rate control and user volume, so the output
+// only depends on the emulation
+pub struct AudioRecorder {
+    mix: Track<2>,
+    stems: Option<Track<4>>,
+    sample_rate: u32,
+    frames_since_flush: u32,
+}
+
+impl AudioRecorder {
+    // Stems are written next to the mix, as <name>_<channel>.wav
+    pub fn new(path: &Path, stems: bool, is_cgb: bool, sample_rate: u32) -> std::io::Result<Self> {
+        let mix = Track::new(
+            vec![WavWriter::create(path, 2, sample_rate)?],
+            is_cgb,
+            sample_rate,
+        );
+
+        let stems = if stems {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            let writers = STEM_NAMES
+                .iter()
+                .map(|stem| {
+                    WavWriter::create(
+                        &path.with_file_name(format!("{name}_{stem}.wav")),
+                        1,
+                        sample_rate,
+                    )
+                })
+                .collect::<std::io::Result<Vec<_>>>()?;
+            Some(Track::new(writers, is_cgb, sample_rate))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            mix,
+            stems,
+            sample_rate,
+            frames_since_flush: 0,
+        })
+    }
+
+    // Mix is after panning and master volume, channels before
+    pub fn tick(&mut self, mix: [f32; 2], channels: [f32; 4], clocks: u32) -> std::io::Result<()> {
+        self.frames_since_flush += self.mix.tick(mix, clocks)?;
+        if let Some(stems) = &mut self.stems {
+            stems.tick(channels, clocks)?;
+        }
+
+        // Keep the files valid if the emulator is killed
+        if self.frames_since_flush >= self.sample_rate {
+            self.frames_since_flush = 0;
+            self.mix.flush()?;
+            if let Some(stems) = &mut self.stems {
+                stems.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use crate::save_state;
+
 #[derive(Default)]
 pub struct Sweep {
     period: u8,
@@ -10,6 +12,15 @@ pub struct Sweep {
     timer: u8,
 }
 
+save_state!(Sweep {
+    period,
+    direction,
+    shift,
+    shadow,
+    enabled,
+    timer
+});
+
 #[derive(Debug)]
 pub struct FreqOverflow;
 
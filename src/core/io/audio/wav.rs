@@ -0,0 +1,71 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const HEADER_SIZE: u32 = 44;
+const BITS_PER_SAMPLE: u16 = 16;
+
+// Minimal 16-bit PCM WAV writer
+pub struct WavWriter {
+    file: BufWriter<File>,
+    channels: u16,
+    data_size: u32,
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, channels: u16, sample_rate: u32) -> std::io::Result<Self> {
+        let mut writer = Self {
+            file: BufWriter::new(File::create(path)?),
+            channels,
+            data_size: 0,
+        };
+
+        let block_align = channels * BITS_PER_SAMPLE / 8;
+        let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+        header.extend(b"RIFF");
+        header.extend((HEADER_SIZE - 8).to_le_bytes());
+        header.extend(b"WAVEfmt ");
+        header.extend(16u32.to_le_bytes());
+        header.extend(1u16.to_le_bytes()); // PCM
+        header.extend(channels.to_le_bytes());
+        header.extend(sample_rate.to_le_bytes());
+        header.extend((sample_rate * block_align as u32).to_le_bytes());
+        header.extend(block_align.to_le_bytes());
+        header.extend(BITS_PER_SAMPLE.to_le_bytes());
+        header.extend(b"data");
+        header.extend(0u32.to_le_bytes());
+        writer.file.write_all(&header)?;
+
+        Ok(writer)
+    }
+
+    // Writes one frame, a sample per channel
+    pub fn write(&mut self, frame: &[f32]) -> std::io::Result<()> {
+        debug_assert_eq!(frame.len(), self.channels as usize);
+
+        for sample in frame {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.file.write_all(&sample.to_le_bytes())?;
+        }
+        self.data_size += self.channels as u32 * BITS_PER_SAMPLE as u32 / 8;
+        Ok(())
+    }
+
+    // Writes the current sizes in the header, so the file is valid even if the
+    // writer is never dropped
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file
+            .write_all(&(HEADER_SIZE - 8 + self.data_size).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(HEADER_SIZE as u64 - 4))?;
+        self.file.write_all(&self.data_size.to_le_bytes())?;
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.flush()
+    }
+}
+
+impl Drop for WavWriter {
+    fn drop(&mut self) {
+        _ = self.flush();
+    }
+}
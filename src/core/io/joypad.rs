@@ -1,4 +1,5 @@
-use crate::flag_set;
+use crate::core::cpu::interrupts::{InterruptFlags, Interrupts};
+use crate::{flag_set, save_state};
 
 const PAD: u8 = 0x10;
 const ACTION: u8 = 0x20;
@@ -34,6 +35,8 @@ pub struct Joypad {
     selector: u8,
 }
 
+save_state!(Joypad { state, selector });
+
 #[allow(clippy::new_without_default)]
 impl Joypad {
     pub fn new() -> Self {
@@ -55,12 +58,23 @@ impl Joypad {
         match self.selector & (ACTION | PAD) {
             PAD => self.state & 0xf,
             ACTION => self.state >> 4,
-            _ => unreachable!(),
+            _ => (self.state & 0xf) & (self.state >> 4),
         }
     }
 
-    pub fn press(&mut self, button: u8) {
+    // Whether a button of a selected group is held down
+    pub fn pressed(&self) -> bool {
+        self.read() & 0xf != 0xf
+    }
+
+    pub fn press(&mut self, button: u8, interrupts: &mut Interrupts) {
+        let pressed = self.read();
         self.state &= !button;
+
+        // A selected line going low requests an interrupt
+        if pressed & !self.read() & 0xf != 0 {
+            interrupts.request(InterruptFlags::JOYPAD);
+        }
     }
 
     pub fn release(&mut self, button: u8) {
@@ -3,13 +3,16 @@ pub mod joypad;
 mod serial;
 mod timer;
 pub mod video;
+use crate::save_state;
 use crossbeam_channel::Sender;
 
 use audio::apu::APU;
 use joypad::Joypad;
 use log::warn;
 use serial::Serial;
+use std::path::Path;
 use timer::Timer;
+use video::capture::VideoFormat;
 use video::ppu::{Vbuf, PPU};
 
 pub struct IOMMU {
@@ -18,16 +21,67 @@ pub struct IOMMU {
     pub ppu: PPU,
     pub apu: APU,
     pub joypad: Joypad,
+
+    // The audio is recorded alongside the video
+    video_audio: bool,
 }
 
+save_state!(IOMMU {
+    serial,
+    timer,
+    ppu,
+    apu,
+    joypad
+});
+
 impl IOMMU {
-    pub fn new(video_channel_sd: Sender<Vbuf>, audio_channel_sd: Sender<[f32; 2]>, is_cgb: bool) -> Self {
+    pub fn new(
+        video_channel_sd: Sender<Vbuf>,
+        audio_channel_sd: Sender<[f32; 2]>,
+        is_cgb: bool,
+    ) -> Self {
         Self {
             serial: Serial::default(),
             timer: Timer::new(),
             ppu: PPU::new(video_channel_sd, is_cgb),
-            apu: APU::new(audio_channel_sd),
+            apu: APU::new(audio_channel_sd, is_cgb),
             joypad: Joypad::new(),
+            video_audio: false,
+        }
+    }
+
+    // Records the video and, if with_audio is set, the audio to a WAV file
+    // next to it. The APU has a single recorder, so the audio can't be
+    // recorded both on its own and with the video
+    pub fn start_video_recording(
+        &mut self,
+        path: &Path,
+        format: VideoFormat,
+        with_audio: bool,
+    ) -> std::io::Result<()> {
+        self.ppu.start_recording(path, format)?;
+        if with_audio {
+            if let Err(e) = self.apu.start_recording(&path.with_extension("wav"), false) {
+                self.ppu.stop_recording();
+                return Err(e);
+            }
+        }
+        self.video_audio = with_audio;
+        Ok(())
+    }
+
+    // The audio recorded with the video is only stopped with it
+    pub fn stop_audio_recording(&mut self) {
+        if !self.video_audio {
+            self.apu.stop_recording();
+        }
+    }
+
+    pub fn stop_video_recording(&mut self) {
+        self.ppu.stop_recording();
+        if self.video_audio {
+            self.apu.stop_recording();
+            self.video_audio = false;
         }
     }
 
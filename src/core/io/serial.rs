@@ -1,4 +1,4 @@
-use crate::flag_set;
+use crate::{flag_set, save_state};
 
 #[allow(nonstandard_style)]
 mod SerialTransferControlFlags {
@@ -7,21 +7,47 @@ mod SerialTransferControlFlags {
     pub const TRANSFER_ENABLE: u8 = 0x80;
 }
 
+// Bytes of serial output kept around
+const OUTPUT_SIZE: usize = 0x1000;
+
 #[derive(Default)]
 pub struct Serial {
     transfer_control: u8,
     transfer_data: u8,
+    output: Vec<u8>,
 }
 
+save_state!(Serial {
+    transfer_control,
+    transfer_data
+});
+
 impl Serial {
     pub fn write(&mut self, addr: u16, value: u8) {
         match addr {
             0xff01 => self.transfer_data = value,
-            0xff02 => self.transfer_control = value,
+            0xff02 => {
+                self.transfer_control = value;
+                if flag_set!(value, SerialTransferControlFlags::TRANSFER_ENABLE) {
+                    self.push_output(self.transfer_data);
+                }
+            }
             _ => unreachable!(),
         }
     }
 
+    fn push_output(&mut self, byte: u8) {
+        if self.output.len() >= OUTPUT_SIZE {
+            self.output.drain(..OUTPUT_SIZE / 2);
+        }
+        self.output.push(byte);
+    }
+
+    // Bytes sent so far, the oldest ones are dropped past OUTPUT_SIZE
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
     pub fn read(&self, addr: u16) -> u8 {
         match addr {
             0xff01 => self.transfer_data,
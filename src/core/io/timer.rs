@@ -1,6 +1,6 @@
-use crate::core::cpu::interrupts::{request_interrupt, InterruptFlags};
+use crate::core::cpu::interrupts::{InterruptFlags, Interrupts};
 use crate::core::cpu::CPUSpeed;
-use crate::flag_set;
+use crate::{flag_set, save_state};
 
 pub struct Timer {
     div: u16,
@@ -18,6 +18,16 @@ pub struct Timer {
     was_reset: bool,
 }
 
+save_state!(Timer {
+    div,
+    tima,
+    tma,
+    tac,
+    prev_div_bit,
+    overflow_delay,
+    was_reset
+});
+
 #[allow(clippy::new_without_default)]
 impl Timer {
     pub fn new() -> Self {
@@ -32,28 +42,27 @@ impl Timer {
         }
     }
 
-    pub fn tick(&mut self, speed_mode: CPUSpeed) -> bool {
+    pub fn tick(&mut self, speed_mode: CPUSpeed, interrupts: &mut Interrupts) -> bool {
         let div_apu_bit = self.div_apu_bit(speed_mode);
         self.div = self.div.wrapping_add(4);
         let div_apu = div_apu_bit && !self.div_apu_bit(speed_mode);
 
         let current_bit = self.div_bit();
 
-        if self.timer_enabled() {
-            // Finalize overflow: reload TIMA from TMA and fire interrupt
-            if self.overflow_delay {
-                self.overflow_delay = false;
-                self.tima = self.tma;
-                request_interrupt(InterruptFlags::TIMER);
-                self.was_reset = true;
-            } else if self.was_reset {
-                // Cycle after reload: clear was_reset, skip falling edge this cycle
-                self.was_reset = false;
-            } else {
-                // Normal falling edge detection
-                if self.prev_div_bit && !current_bit {
-                    self.inc_tima();
-                }
+        // Finalize overflow: reload TIMA from TMA and fire interrupt, even if
+        // the timer got disabled in the meantime
+        if self.overflow_delay {
+            self.overflow_delay = false;
+            self.tima = self.tma;
+            interrupts.request(InterruptFlags::TIMER);
+            self.was_reset = true;
+        } else if self.was_reset {
+            // Cycle after reload: clear was_reset, skip falling edge this cycle
+            self.was_reset = false;
+        } else if self.timer_enabled() {
+            // Normal falling edge detection
+            if self.prev_div_bit && !current_bit {
+                self.inc_tima();
             }
         }
 
@@ -121,7 +130,10 @@ impl Timer {
                 if self.overflow_delay {
                     self.overflow_delay = false;
                 }
-                self.tima = value;
+                // Writing to TIMA on the reload cycle is ignored, TMA wins
+                if !self.was_reset {
+                    self.tima = value;
+                }
             }
             0xff06 => {
                 self.tma = value;
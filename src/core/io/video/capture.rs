@@ -0,0 +1,112 @@
+use std::fs::{create_dir_all, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use gif::{Encoder, Frame, Repeat};
+use image::RgbImage;
+
+use super::ppu::{Vbuf, RESX, RESY};
+
+// Frames per second of the PPU, 4194304 / 70224
+const FRAME_RATE: f64 = 59.7275;
+// GIF delays are in centiseconds and most viewers slow down frames shorter
+// than 2cs, so only every other frame is kept
+const GIF_FRAME_STEP: u64 = 2;
+// NeuQuant speed, only used when a frame has more than 256 colors
+const GIF_QUANTIZE_SPEED: i32 = 10;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum VideoFormat {
+    #[default]
+    GIF,
+    // Numbered PNG sequence, the path is a directory
+    PNG,
+}
+
+pub fn to_rgb(frame: &Vbuf) -> Vec<u8> {
+    frame.iter().flat_map(|p| [p.r, p.g, p.b]).collect()
+}
+
+pub fn save_png(frame: &Vbuf, path: &Path) -> std::io::Result<()> {
+    save_rgb_png(to_rgb(frame), path)
+}
+
+// Saves a frame already converted to RGB bytes
+pub fn save_rgb_png(rgb: Vec<u8>, path: &Path) -> std::io::Result<()> {
+    RgbImage::from_raw(RESX as u32, RESY as u32, rgb)
+        .expect("Invalid frame size")
+        .save(path)
+        .map_err(std::io::Error::other)
+}
+
+enum Output {
+    Gif(Encoder<BufWriter<File>>),
+    Png(PathBuf),
+}
+
+// Records every frame produced by the PPU, at emulation speed
+pub struct VideoRecorder {
+    output: Output,
+    frames: u64,
+    // Emulated time of the recorded frames, in centiseconds, to keep the GIF
+    // delays from drifting
+    elapsed_cs: f64,
+    written_cs: u64,
+}
+
+impl VideoRecorder {
+    pub fn new(path: &Path, format: VideoFormat) -> std::io::Result<Self> {
+        let output = match format {
+            VideoFormat::GIF => {
+                let file = BufWriter::new(File::create(path)?);
+                let mut encoder = Encoder::new(file, RESX as u16, RESY as u16, &[])
+                    .map_err(std::io::Error::other)?;
+                encoder
+                    .set_repeat(Repeat::Infinite)
+                    .map_err(std::io::Error::other)?;
+                Output::Gif(encoder)
+            }
+            VideoFormat::PNG => {
+                create_dir_all(path)?;
+                Output::Png(path.to_path_buf())
+            }
+        };
+
+        Ok(Self {
+            output,
+            frames: 0,
+            elapsed_cs: 0.0,
+            written_cs: 0,
+        })
+    }
+
+    pub fn frame(&mut self, frame: &Vbuf) -> std::io::Result<()> {
+        match &mut self.output {
+            Output::Gif(encoder) => {
+                if self.frames.is_multiple_of(GIF_FRAME_STEP) {
+                    self.elapsed_cs += GIF_FRAME_STEP as f64 * 100.0 / FRAME_RATE;
+                    let delay = self.elapsed_cs.round() as u64 - self.written_cs;
+                    self.written_cs += delay;
+
+                    let mut gif_frame = Frame::from_rgb_speed(
+                        RESX as u16,
+                        RESY as u16,
+                        &to_rgb(frame),
+                        GIF_QUANTIZE_SPEED,
+                    );
+                    gif_frame.delay = delay as u16;
+                    encoder
+                        .write_frame(&gif_frame)
+                        .map_err(std::io::Error::other)?;
+                }
+            }
+            Output::Png(dir) => {
+                save_png(frame, &dir.join(format!("{:06}.png", self.frames)))?;
+            }
+        }
+
+        self.frames += 1;
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use super::ppu::TileAttributes;
 use crate::{
-    core::cpu::interrupts::{request_interrupt, InterruptFlags},
-    flag_set,
+    core::cpu::interrupts::{InterruptFlags, Interrupts},
+    flag_set, save_state,
 };
 
 const DMG_COLORS: [u8; 4] = [0xff, 0xaa, 0x55, 0x00];
@@ -37,6 +37,8 @@ pub struct Pixel {
     pub priority: bool,
 }
 
+save_state!(Pixel { r, g, b, priority });
+
 impl Default for Pixel {
     fn default() -> Self {
         Self {
@@ -81,6 +83,11 @@ struct PaletteIndex {
     pub addr: usize,
 }
 
+save_state!(PaletteIndex {
+    auto_increment,
+    addr
+});
+
 impl PaletteIndex {
     pub fn get(&mut self) -> usize {
         let addr = self.addr;
@@ -130,6 +137,23 @@ pub struct LCD {
     pub wx: u8,
 }
 
+save_state!(LCD {
+    lcdc,
+    lcds,
+    scy,
+    scx,
+    ly,
+    lyc,
+    dmg_bg_palette,
+    dmg_obj_palettes,
+    bg_palette_index,
+    bg_cram,
+    obj_palette_index,
+    obj_cram,
+    wy,
+    wx
+});
+
 impl Default for LCD {
     fn default() -> Self {
         Self {
@@ -214,14 +238,14 @@ impl LCD {
         self.lcds = (self.lcds & 0xfc) | val;
     }
 
-    pub fn inc_ly(&mut self) {
+    pub fn inc_ly(&mut self, interrupts: &mut Interrupts) {
         self.ly += 1;
 
         if self.ly == self.lyc {
             self.lcds |= LCDS_FLAGS::LYC_EQ_LY;
 
             if self.lcds & LCDS_FLAGS::MODE_LYC_EQ_LY_STAT == LCDS_FLAGS::MODE_LYC_EQ_LY_STAT {
-                request_interrupt(InterruptFlags::STAT);
+                interrupts.request(InterruptFlags::STAT);
             }
         } else {
             self.lcds &= !LCDS_FLAGS::LYC_EQ_LY;
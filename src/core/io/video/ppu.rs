@@ -1,10 +1,14 @@
+use super::capture::{VideoFormat, VideoRecorder};
 use super::lcd::{PPUMode, Pixel, LCD, LCDC_FLAGS, LCDS_FLAGS};
-use crate::core::cpu::interrupts::{request_interrupt, InterruptFlags};
+use crate::core::cpu::interrupts::{InterruptFlags, Interrupts};
 use crate::core::cpu::CPUSpeed;
-use crate::debugger::{ppu_metrics, PpuMetricFields};
-use crate::flag_set;
+use crate::core::save_state::{SaveState, StateError};
+use crate::debugger::{MetricsHandler, PpuMetricFields, PpuMetrics};
+use crate::{flag_set, save_state};
 
 use crossbeam_channel::Sender;
+use log::{error, info};
+use std::path::Path;
 
 const LINES_PER_FRAME: u8 = 154;
 const TICKS_PER_LINE: u16 = 456;
@@ -55,6 +59,19 @@ impl From<&PriorityStyle> for u8 {
     }
 }
 
+impl SaveState for PriorityStyle {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        u8::from(self).save_state(out);
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        let mut value = 0u8;
+        value.load_state(data)?;
+        *self = PriorityStyle::from(value);
+        Ok(())
+    }
+}
+
 #[allow(nonstandard_style)]
 pub mod TileAttributes {
     pub const CGB_PALETTE: u8 = 0x7;
@@ -66,7 +83,7 @@ pub mod TileAttributes {
     pub const PRIORITY: u8 = 0x80;
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Default, Clone, Copy, Debug)]
 struct Sprite {
     pub y: u8,
     pub x: u8,
@@ -74,6 +91,13 @@ struct Sprite {
     pub flags: u8,
 }
 
+save_state!(Sprite {
+    y,
+    x,
+    tile_idx,
+    flags
+});
+
 impl Sprite {
     fn new() -> Self {
         Self {
@@ -101,6 +125,7 @@ pub struct PPU {
 
     vbuf: Vbuf,
     video_channel_sd: Sender<Vbuf>,
+    recorder: Option<VideoRecorder>,
 
     pub frames: u64,
 
@@ -110,8 +135,25 @@ pub struct PPU {
 
     priority_style: PriorityStyle,
     is_cgb: bool,
+
+    pub metrics: MetricsHandler<PpuMetrics>,
 }
 
+save_state!(PPU {
+    oam,
+    vram,
+    vram_bank,
+    lcd,
+    line_ticks,
+    line_x,
+    line_sprites,
+    window_line,
+    window_drawn,
+    vbuf,
+    frames,
+    priority_style
+});
+
 impl PPU {
     pub fn new(video_channel_sd: Sender<Vbuf>, is_cgb: bool) -> Self {
         let mut lcd = LCD::default();
@@ -132,6 +174,7 @@ impl PPU {
             last_frame: std::time::Instant::now(),
             vbuf: [Pixel::default(); RESX * RESY],
             video_channel_sd,
+            recorder: None,
             frames: 0,
             draw_background: true,
             draw_window: true,
@@ -142,6 +185,25 @@ impl PPU {
                 PriorityStyle::DMG
             },
             is_cgb,
+            metrics: MetricsHandler::default(),
+        }
+    }
+
+    // Frame being drawn, complete right after frames is incremented
+    pub fn vbuf(&self) -> &Vbuf {
+        &self.vbuf
+    }
+
+    // Records every frame from now on
+    pub fn start_recording(&mut self, path: &Path, format: VideoFormat) -> std::io::Result<()> {
+        self.recorder = Some(VideoRecorder::new(path, format)?);
+        info!("Recording video to {path:?} as {format:?}");
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) {
+        if self.recorder.take().is_some() {
+            info!("Video recording stopped");
         }
     }
 
@@ -210,7 +272,7 @@ impl PPU {
         self.vram[bank as usize][(addr - 0x8000) as usize]
     }
 
-    pub fn tick(&mut self, speed_mode: CPUSpeed) {
+    pub fn tick(&mut self, speed_mode: CPUSpeed, interrupts: &mut Interrupts) {
         if !flag_set!(self.lcd.lcdc, LCDC_FLAGS::LCD_PPU_ENABLE) {
             return;
         }
@@ -222,10 +284,10 @@ impl PPU {
         for _ in 0..dots {
             self.line_ticks += 1;
             match self.lcd.get_ppu_mode() {
-                PPUMode::HBlank => self.hblank(),
-                PPUMode::VBlank => self.vblank(),
+                PPUMode::HBlank => self.hblank(interrupts),
+                PPUMode::VBlank => self.vblank(interrupts),
                 PPUMode::OAMScan => self.oam_scan(),
-                PPUMode::Draw => self.draw(),
+                PPUMode::Draw => self.draw(interrupts),
             }
         }
     }
@@ -480,12 +542,12 @@ impl PPU {
         }
     }
 
-    fn draw(&mut self) {
+    fn draw(&mut self, interrupts: &mut Interrupts) {
         if self.line_x as usize >= RESX {
             self.lcd.set_ppu_mode(PPUMode::HBlank);
 
             if flag_set!(self.lcd.lcds, LCDS_FLAGS::MODE_HBLANK_STAT) {
-                request_interrupt(InterruptFlags::STAT);
+                interrupts.request(InterruptFlags::STAT);
             }
 
             return;
@@ -496,9 +558,9 @@ impl PPU {
         self.line_x += 1;
     }
 
-    fn hblank(&mut self) {
+    fn hblank(&mut self, interrupts: &mut Interrupts) {
         if self.line_ticks >= TICKS_PER_LINE {
-            self.lcd.inc_ly();
+            self.lcd.inc_ly(interrupts);
             self.line_ticks = 0;
             self.line_sprites = None;
             self.line_x = 0;
@@ -511,24 +573,24 @@ impl PPU {
             if self.lcd.ly as usize >= RESY {
                 self.lcd.set_ppu_mode(PPUMode::VBlank);
 
-                request_interrupt(InterruptFlags::VBLANK);
+                interrupts.request(InterruptFlags::VBLANK);
 
                 if flag_set!(self.lcd.lcds, LCDS_FLAGS::MODE_VBLANK_STAT) {
-                    request_interrupt(InterruptFlags::STAT);
+                    interrupts.request(InterruptFlags::STAT);
                 }
             } else {
                 self.lcd.set_ppu_mode(PPUMode::OAMScan);
 
                 if flag_set!(self.lcd.lcds, LCDS_FLAGS::MODE_OAM_STAT) {
-                    request_interrupt(InterruptFlags::STAT);
+                    interrupts.request(InterruptFlags::STAT);
                 }
             }
         }
     }
 
-    fn vblank(&mut self) {
+    fn vblank(&mut self, interrupts: &mut Interrupts) {
         if self.line_ticks >= TICKS_PER_LINE {
-            self.lcd.inc_ly();
+            self.lcd.inc_ly(interrupts);
 
             if self.lcd.ly >= LINES_PER_FRAME {
                 self.lcd.ly = 0;
@@ -538,11 +600,18 @@ impl PPU {
                     _ = self.video_channel_sd.send(self.vbuf);
                 }
 
+                if let Some(recorder) = &mut self.recorder {
+                    if let Err(e) = recorder.frame(&self.vbuf) {
+                        error!("Video recording failed: {e}");
+                        self.recorder = None;
+                    }
+                }
+
                 self.frames += 1;
-                ppu_metrics().count(PpuMetricFields::FRAME_RATE, 1);
+                self.metrics.count(PpuMetricFields::FRAME_RATE, 1);
 
                 if flag_set!(self.lcd.lcds, LCDS_FLAGS::MODE_OAM_STAT) {
-                    request_interrupt(InterruptFlags::STAT);
+                    interrupts.request(InterruptFlags::STAT);
                 }
 
                 let now = std::time::Instant::now();
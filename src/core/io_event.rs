@@ -1,8 +1,14 @@
 use super::cpu::cpu::LR35902CPU;
+use super::io::video::capture::VideoFormat;
 use super::playback::Playback;
 
+use chrono::Local;
 use crossbeam_channel::Receiver;
+use log::error;
 use std::fmt::Display;
+use std::path::PathBuf;
+
+const RECORDINGS_DIR: &str = "recordings";
 
 #[derive(Debug)]
 pub struct IOEventError;
@@ -15,6 +21,11 @@ pub enum IOEvent {
     CLOSE,
     SOUND_MUTE(bool),
     SOUND_VOLUME(f32),
+    AUDIO_RECORD_START(bool),
+    AUDIO_RECORD_STOP,
+    // Format, with audio
+    VIDEO_RECORD_START(VideoFormat, bool),
+    VIDEO_RECORD_STOP,
 }
 
 impl Display for IOEvent {
@@ -25,6 +36,12 @@ impl Display for IOEvent {
             IOEvent::CLOSE => write!(f, "CLOSE"),
             IOEvent::SOUND_MUTE(muted) => write!(f, "SOUND MUTED: {}", muted),
             IOEvent::SOUND_VOLUME(level) => write!(f, "SOUND VOLUME: {}", level),
+            IOEvent::AUDIO_RECORD_START(stems) => write!(f, "AUDIO RECORD STEMS: {}", stems),
+            IOEvent::AUDIO_RECORD_STOP => write!(f, "AUDIO RECORD STOP"),
+            IOEvent::VIDEO_RECORD_START(format, audio) => {
+                write!(f, "VIDEO RECORD {:?} AUDIO: {}", format, audio)
+            }
+            IOEvent::VIDEO_RECORD_STOP => write!(f, "VIDEO RECORD STOP"),
         }
     }
 }
@@ -54,6 +71,29 @@ impl IOEvent {
     }
 }
 
+// Timestamped path in the recordings directory, without extension
+fn recording_path() -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(RECORDINGS_DIR)?;
+    Ok(PathBuf::from(RECORDINGS_DIR).join(Local::now().format("%Y-%m-%d_%H-%M-%S").to_string()))
+}
+
+fn start_audio_recording(cpu: &mut LR35902CPU, stems: bool) -> std::io::Result<()> {
+    let path = recording_path()?.with_extension("wav");
+    cpu.bus.io.apu.start_recording(&path, stems)
+}
+
+fn start_video_recording(
+    cpu: &mut LR35902CPU,
+    format: VideoFormat,
+    audio: bool,
+) -> std::io::Result<()> {
+    let path = match format {
+        VideoFormat::GIF => recording_path()?.with_extension("gif"),
+        VideoFormat::PNG => recording_path()?,
+    };
+    cpu.bus.io.start_video_recording(&path, format, audio)
+}
+
 pub struct IOListener {
     event_rc: Receiver<IOEvent>,
 }
@@ -68,11 +108,27 @@ impl IOListener {
         let cpu_ticks = cpu.clock.clock_ticks;
 
         let mut dispatch_event = |event| match event {
-            IOEvent::JOYPAD_PRESS(key) => cpu.bus.io.joypad.press(key),
+            IOEvent::JOYPAD_PRESS(key) => cpu.bus.io.joypad.press(key, &mut cpu.bus.interrupts),
             IOEvent::JOYPAD_RELEASE(key) => cpu.bus.io.joypad.release(key),
-            IOEvent::CLOSE => cpu.bus.cartridge.mbc.save(),
+            IOEvent::CLOSE => {
+                cpu.bus.cartridge.save();
+                cpu.bus.io.apu.stop_recording();
+                cpu.bus.io.stop_video_recording();
+            }
             IOEvent::SOUND_MUTE(muted) => cpu.bus.io.apu.mute(muted),
             IOEvent::SOUND_VOLUME(volume) => cpu.bus.io.apu.user_volume(volume),
+            IOEvent::AUDIO_RECORD_START(stems) => {
+                if let Err(e) = start_audio_recording(cpu, stems) {
+                    error!("Could not start audio recording: {e}");
+                }
+            }
+            IOEvent::AUDIO_RECORD_STOP => cpu.bus.io.stop_audio_recording(),
+            IOEvent::VIDEO_RECORD_START(format, audio) => {
+                if let Err(e) = start_video_recording(cpu, format, audio) {
+                    error!("Could not start video recording: {e}");
+                }
+            }
+            IOEvent::VIDEO_RECORD_STOP => cpu.bus.io.stop_video_recording(),
         };
 
         if playback.player.enabled() {
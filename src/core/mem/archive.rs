@@ -0,0 +1,86 @@
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+const ROM_EXTENSIONS: [&str; 2] = ["gb", "gbc"];
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase)
+}
+
+fn is_rom(name: &str) -> bool {
+    extension(Path::new(name)).is_some_and(|ext| ROM_EXTENSIONS.contains(&ext.as_str()))
+}
+
+// `archive.zip#entry.gbc` names an entry of the archive
+fn split(path: &Path) -> (PathBuf, Option<String>) {
+    if path.exists() {
+        return (path.to_path_buf(), None);
+    }
+    match path.to_str().and_then(|p| p.rsplit_once('#')) {
+        Some((archive, entry)) => (archive.into(), Some(entry.into())),
+        None => (path.to_path_buf(), None),
+    }
+}
+
+// Returns the name of the entry read along with its contents
+fn read_zip(path: &Path, entry: Option<String>) -> io::Result<(String, Vec<u8>)> {
+    let mut zip = ZipArchive::new(File::open(path)?).map_err(io::Error::other)?;
+
+    // The first ROM of the archive, unless one is named
+    let name = match entry {
+        Some(entry) => entry,
+        None => (0..zip.len())
+            .filter_map(|i| zip.name_for_index(i))
+            .find(|name| is_rom(name))
+            .ok_or(io::Error::new(
+                io::ErrorKind::NotFound,
+                "No ROM in the archive",
+            ))?
+            .to_string(),
+    };
+
+    let mut file = zip.by_name(&name).map_err(|e| match e {
+        zip::result::ZipError::FileNotFound => io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{name} is not in the archive"),
+        ),
+        e => io::Error::other(e),
+    })?;
+    let mut contents = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut contents)?;
+    Ok((name, contents))
+}
+
+// Reads a ROM, possibly from a zip or gzip archive. Returns it along with the
+// path its save files are named after, next to the archive. Each ROM of a zip
+// gets its own saves
+pub fn read_rom(path: &Path) -> io::Result<(PathBuf, Vec<u8>)> {
+    let (path, entry) = split(path);
+
+    match extension(&path).as_deref() {
+        // game.zip#roms/game.gbc is saved as game.gbc next to game.zip
+        Some("zip") => {
+            let (name, contents) = read_zip(&path, entry)?;
+            let file_name = Path::new(&name).file_name().unwrap_or(OsStr::new(&name));
+            Ok((path.with_file_name(file_name), contents))
+        }
+        // game.gb.gz is saved as game.gb would be
+        Some("gz") => {
+            let mut contents = vec![];
+            GzDecoder::new(File::open(&path)?).read_to_end(&mut contents)?;
+            Ok((path.with_extension(""), contents))
+        }
+        _ if entry.is_some() => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Only zip archives have entries",
+        )),
+        _ => Ok((path.clone(), fs::read(&path)?)),
+    }
+}
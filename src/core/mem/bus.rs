@@ -2,12 +2,14 @@ use super::boot::{get_boot_rom, BootRom};
 use super::cartridge::Cartridge;
 use super::dma::{OamDMA, VramDMA, VramDMAMode};
 use super::ram::RAM;
-use crate::core::cpu::interrupts::{INTERRUPT_ENABLE, INTERRUPT_FLAGS};
+use crate::core::cpu::interrupts::Interrupts;
+use crate::core::cpu::CPUSpeed;
 use crate::core::io::video::{
-    lcd::PPUMode,
-    ppu::{Vbuf, RESX},
+    lcd::{PPUMode, LCDC_FLAGS},
+    ppu::Vbuf,
 };
 use crate::core::io::IOMMU;
+use crate::{flag_set, save_state};
 
 use crossbeam_channel::Sender;
 use log::warn;
@@ -36,8 +38,21 @@ pub struct Bus {
     pub speed_mode: u8,
     pub booting: bool,
     boot_rom: (&'static [u8; 0x100], Option<&'static [u8; 0x700]>),
+
+    pub interrupts: Interrupts,
 }
 
+save_state!(Bus {
+    cartridge,
+    ram,
+    io,
+    oam_dma,
+    vram_dma,
+    speed_mode,
+    booting,
+    interrupts
+});
+
 impl Bus {
     pub fn new(
         cartridge: Cartridge,
@@ -55,9 +70,28 @@ impl Bus {
             speed_mode: 0,
             booting: !matches!(boot_rom, BootRom::NONE),
             boot_rom: get_boot_rom(boot_rom),
+            interrupts: Interrupts::default(),
         }
     }
 
+    // Power cycle, keeping the cartridge in place
+    pub fn reset(
+        &mut self,
+        boot_rom: BootRom,
+        video_channel_sd: Sender<Vbuf>,
+        audio_channel_sd: Sender<[f32; 2]>,
+    ) {
+        self.cartridge.reset();
+        self.ram = RAM::new();
+        self.io = IOMMU::new(video_channel_sd, audio_channel_sd, self.cartridge.is_cgb());
+        self.oam_dma = OamDMA::default();
+        self.vram_dma = VramDMA::default();
+        self.speed_mode = 0;
+        self.booting = !matches!(boot_rom, BootRom::NONE);
+        self.boot_rom = get_boot_rom(boot_rom);
+        self.interrupts = Interrupts::default();
+    }
+
     pub fn read(&self, addr: u16) -> u8 {
         if self.booting {
             if addr < 0x100 {
@@ -78,18 +112,15 @@ impl Bus {
             0xc000..=0xdfff => self.ram.read(addr),
             0xe000..=0xfdff => self.ram.read(addr - 0x2000),
             0xfe00..=0xfe9f => self.io.read(addr),
-            0xff0f => INTERRUPT_FLAGS.get() | 0xe0,
-            0xff46 => {
-                warn!("Invalid DMA read at 0x{addr:04X}");
-                0xff
-            }
-            0xff4d => self.speed_mode,
+            0xff0f => self.interrupts.flags | 0xe0,
+            0xff46 => self.oam_dma.reg,
+            0xff4d => self.speed_mode | 0x7e,
             0xff51..=0xff55 => self.vram_dma.read(addr),
             0xff6c => self.io.read(addr),
             0xff70 => self.ram.read(addr),
             0xff00..=0xff7f => self.io.read(addr),
             0xff80..=0xfffe => self.ram.read(addr),
-            0xffff => INTERRUPT_ENABLE.get() | 0xe0,
+            0xffff => self.interrupts.enable | 0xe0,
             _ => {
                 warn!("bus.read: unhandled address 0x{addr:04X}");
                 0xff
@@ -97,9 +128,42 @@ impl Bus {
         }
     }
 
+    // Memory accesses issued by the CPU, which can conflict with an OAM DMA
+    pub fn cpu_read(&self, addr: u16) -> u8 {
+        if self.oam_dma_conflict(addr) {
+            return match addr {
+                0xfe00..=0xfeff => 0xff,
+                _ => self.read(self.oam_dma.current_addr()),
+            };
+        }
+        self.read(addr)
+    }
+
+    pub fn cpu_write(&mut self, addr: u16, value: u8) {
+        if self.oam_dma_conflict(addr) {
+            return;
+        }
+        self.write(addr, value);
+    }
+
+    // Whether the address sits on the bus being used by the OAM DMA
+    fn oam_dma_conflict(&self, addr: u16) -> bool {
+        if !self.oam_dma.is_active() {
+            return false;
+        }
+
+        let vram_src = (0x8000..=0x9fff).contains(&self.oam_dma.src);
+        match addr {
+            0x8000..=0x9fff => vram_src,
+            0..=0xfdff => !vram_src,
+            0xfe00..=0xfeff => true,
+            _ => false,
+        }
+    }
+
     pub fn read16(&self, addr: u16) -> u16 {
         let lo = self.read(addr);
-        let hi = self.read(addr + 1);
+        let hi = self.read(addr.wrapping_add(1));
         ((hi as u16) << 8) | lo as u16
     }
 
@@ -111,67 +175,112 @@ impl Bus {
             0xc000..=0xdfff => self.ram.write(addr, value),
             0xe000..=0xfdff => self.ram.write(addr - 0x2000, value),
             0xfe00..=0xfe9f => self.io.write(addr, value),
-            0xff0f => INTERRUPT_FLAGS.set(value),
+            0xff0f => self.interrupts.flags = value,
             0xff46 => self.oam_dma.init(value),
-            0xff4d => self.speed_mode = ((self.speed_mode >> 1) << 1) | value & 1,
+            0xff4d if self.cartridge.is_cgb() => {
+                self.speed_mode = ((self.speed_mode >> 1) << 1) | value & 1
+            }
             0xff50 => self.booting = false,
             0xff51..=0xff55 => self.vram_dma.write(addr, value),
             0xff6c => self.io.write(addr, value),
             0xff70 => self.ram.write(addr, value),
             0xff00..=0xff7f => self.io.write(addr, value),
             0xff80..=0xfffe => self.ram.write(addr, value),
-            0xffff => INTERRUPT_ENABLE.set(value),
+            0xffff => self.interrupts.enable = value,
             _ => warn!("bus.write: unhandled address 0x{addr:04X}"),
         }
     }
 
-    pub fn tick(&mut self) {
+    pub fn tick(&mut self, speed_mode: CPUSpeed, halted: bool) {
         self.oam_dma_tick();
-        self.vram_dma_tick();
+        self.vram_dma_tick(speed_mode, halted);
+        self.cheats_tick();
+        self.cartridge.tick(speed_mode);
     }
 
-    fn oam_dma_tick(&mut self) {
-        if self.oam_dma.src == 0 {
-            return;
+    // GameShark codes are written once a frame, at the end of VBlank, only
+    // ever to the cartridge RAM, WRAM and HRAM
+    fn cheats_tick(&mut self) {
+        let frame = self.io.ppu.frames;
+        for (bank, addr, value) in self.cartridge.cheats.frame_writes(frame).to_vec() {
+            match (bank & 0xf0, addr) {
+                (0x80 | 0x90, 0xd000..=0xdfff) => self.ram.write_bank(bank, addr, value),
+                (0x80, 0xa000..=0xbfff) => {
+                    let offset = (bank & 0xf) as usize * 0x2000 + (addr - 0xa000) as usize;
+                    if let Some(byte) = self.cartridge.mbc.ram().get_mut(offset) {
+                        *byte = value;
+                    }
+                }
+                (_, 0xa000..=0xdfff | 0xff80..=0xfffe) => self.write(addr, value),
+                _ => (),
+            }
         }
+    }
 
-        self.write(self.oam_dma.dst, self.read(self.oam_dma.src));
-        self.oam_dma.src += 1;
-        self.oam_dma.dst += 1;
+    pub fn ram(&self) -> &RAM {
+        &self.ram
+    }
 
-        if self.oam_dma.dst > 0xfe9f {
-            self.oam_dma.src = 0;
+    pub fn speed_switch_armed(&self) -> bool {
+        flag_set!(self.speed_mode, 1)
+    }
+
+    // Returns whether the CPU is now running in double speed
+    pub fn switch_speed(&mut self) -> bool {
+        // Clear bit 0 & flip bit 7
+        self.speed_mode = (self.speed_mode ^ 0x80) & 0x80;
+        flag_set!(self.speed_mode, 0x80)
+    }
+
+    pub fn vram_dma_stalling(&self) -> bool {
+        self.vram_dma.block > 0 || self.vram_dma.mode == VramDMAMode::GENERAL
+    }
+
+    fn oam_dma_tick(&mut self) {
+        if let Some((src, dst)) = self.oam_dma.tick() {
+            self.write(dst, self.read(src));
         }
     }
 
-    fn vram_dma_tick(&mut self) {
-        match self.vram_dma.mode {
-            VramDMAMode::IDLE => (),
-            VramDMAMode::HBLANK => {
-                if self.io.ppu.lcd.get_ppu_mode() == PPUMode::HBlank {
-                    let transfer_size = 16;
-                    // Start of HBlank, copy 16 bytes
-                    if self.io.ppu.line_x as usize == RESX {
-                        for i in 0..transfer_size {
-                            self.write(self.vram_dma.dst + i, self.read(self.vram_dma.src + i));
-                        }
-                        self.vram_dma.src += transfer_size;
-                        self.vram_dma.dst += transfer_size;
-                        self.vram_dma.remaining -= transfer_size;
-                    }
+    fn vram_dma_tick(&mut self, speed_mode: CPUSpeed, halted: bool) {
+        let lcd_on = flag_set!(self.io.ppu.lcd.lcdc, LCDC_FLAGS::LCD_PPU_ENABLE);
+        let hblank = lcd_on && self.io.ppu.lcd.get_ppu_mode() == PPUMode::HBlank;
+        let hblank_start = hblank && !self.vram_dma.hblank;
+        self.vram_dma.hblank = hblank;
+        let just_started = std::mem::take(&mut self.vram_dma.just_started);
 
-                    if self.vram_dma.remaining == 0 {
-                        self.vram_dma.reset();
+        if self.vram_dma.block == 0 {
+            match self.vram_dma.mode {
+                VramDMAMode::IDLE => return,
+                VramDMAMode::HBLANK => {
+                    // One block per HBlank, paused while the CPU is halted. When
+                    // started with the LCD off, the first block is copied right away
+                    let start = (hblank_start && !halted) || (just_started && !lcd_on);
+                    if !start {
+                        return;
                     }
                 }
+                VramDMAMode::GENERAL => (),
             }
-            VramDMAMode::GENERAL => {
-                for i in 0..self.vram_dma.remaining {
-                    self.write(self.vram_dma.dst + i, self.read(self.vram_dma.src + i));
-                }
+            self.vram_dma.block = 0x10;
+        }
 
-                self.vram_dma.reset();
-            }
+        // 2 bytes per M-cycle in normal speed, 1 in double speed
+        let bytes = match speed_mode {
+            CPUSpeed::DOUBLE => 1,
+            _ => 2,
+        };
+        for _ in 0..bytes {
+            let value = self.read(self.vram_dma.src);
+            self.write(0x8000 | (self.vram_dma.dst & 0x1fff), value);
+            self.vram_dma.src = self.vram_dma.src.wrapping_add(1);
+            self.vram_dma.dst = self.vram_dma.dst.wrapping_add(1);
+            self.vram_dma.remaining -= 1;
+            self.vram_dma.block -= 1;
+        }
+
+        if self.vram_dma.remaining == 0 {
+            self.vram_dma.reset();
         }
     }
 }
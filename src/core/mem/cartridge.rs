@@ -1,97 +1,290 @@
+use super::archive;
+use super::cheats::Cheats;
+use super::header::CartridgeHeader;
 use super::mbc::{mbc, MemoryBankController};
-use std::{fs, path::PathBuf};
-
-#[allow(unused)]
-pub struct CartridgeHeader {
-    //0104-0133 — Nintendo logo
-    //0134-0143 — Title
-    //013F-0142 — Manufacturer code
-    //0143 — CGB flag
-    //0144–0145 — New licensee code
-    //0146 — SGB flag
-    //0147 — Cartridge type
-    //0148 — ROM size
-    //0149 — RAM size
-    //014A — Destination code
-    //014B — Old licensee code
-    //014C — Mask ROM version number
-    //014D — Header checksum
-    //014E-014F — Global checksum
-    title: String,             // 11-16 bytes
-    manufacturer_code: String, // 4 bytes
-    cgb_flag: u8,
-    new_licensee_code: String, // 2 bytes
-    sgb_flag: u8,
-    cartridge_type: u8,
-    rom_size: u8,
-    ram_size: u8,
-    dest_code: u8,
-    old_licensee_code: u8,
-    rom_version: u8,
-    header_checksum: u8,
-    global_checksum: u16,
-}
+use super::patch;
+use super::rtc::RtcClock;
+use super::save;
+use crate::core::cpu::{CPUSpeed, CLOCK_SPEED};
+use crate::core::save_state::{SaveState, StateError};
+use log::{error, info, warn};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-impl CartridgeHeader {
-    // CGB flag: 0x80 = CGB only, 0xC0 = CGB+DMG, 0x00 = DMG only
-    pub fn is_cgb(&self) -> bool {
-        self.cgb_flag & 0xC0 != 0
-    }
-
-    pub fn new(header: &[u8]) -> Self {
-        Self {
-            title: std::str::from_utf8(&header[0..11])
-                .expect("Invalid cartridge title")
-                .into(),
-            manufacturer_code: std::str::from_utf8(&header[11..15])
-                .expect("Invalid cartridge title")
-                .into(),
-            cgb_flag: header[15],
-            new_licensee_code: std::str::from_utf8(&header[16..18])
-                .expect("Invalid licensee code")
-                .into(),
-            sgb_flag: header[18],
-            cartridge_type: header[19],
-            rom_size: header[20],
-            ram_size: header[21],
-            dest_code: header[22],
-            old_licensee_code: header[23],
-            rom_version: header[24],
-            header_checksum: header[25],
-            global_checksum: u16::from_be_bytes(header[26..28].try_into().unwrap()),
-        }
-    }
-}
+// SRAM is saved once the game stopped writing to it for a while
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(3);
+// Or when it keeps writing to it
+const AUTOSAVE_MAX_DELAY: Duration = Duration::from_secs(30);
 
-#[allow(unused)]
 pub struct Cartridge {
     header: CartridgeHeader,
     pub mbc: Box<dyn MemoryBankController + Send + Sync>,
+    rom_path: PathBuf,
+    save_path: PathBuf,
+    pub cheats: Cheats,
+
+    sram_written: bool,
+    // First and last writes since the last save
+    unsaved: Option<(Instant, Instant)>,
+
+    rtc_clock: RtcClock,
+    // Cycles of the current emulated RTC second
+    rtc_ticks: u32,
+}
+
+// The checksums identify the ROM the state was made with
+impl SaveState for Cartridge {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        self.header.header_checksum.save_state(out);
+        self.header.global_checksum.save_state(out);
+        self.rtc_ticks.save_state(out);
+        self.mbc.save_state(out);
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        let (mut header_checksum, mut global_checksum) = (0u8, 0u16);
+        header_checksum.load_state(data)?;
+        global_checksum.load_state(data)?;
+
+        if header_checksum != self.header.header_checksum
+            || global_checksum != self.header.global_checksum
+        {
+            return Err(StateError::WrongRom);
+        }
+        self.sram_written = true;
+        self.rtc_ticks.load_state(data)?;
+        self.mbc.load_state(data)
+    }
 }
 
 impl Cartridge {
     pub fn new(rom_path: PathBuf) -> Self {
-        let contents: Vec<u8> = fs::read(&rom_path).expect("Unable to read the rom_path");
+        Self::load(rom_path).expect("Unable to read the rom_path")
+    }
 
-        let header = CartridgeHeader::new(&contents[0x134..=0x14f]);
+    // Patches next to the ROM are applied
+    pub fn load(rom_path: PathBuf) -> std::io::Result<Self> {
+        Self::open(rom_path, None)
+    }
+
+    // Patches are applied in order, the ROM file is left untouched
+    pub fn load_patched(rom_path: PathBuf, patches: &[PathBuf]) -> std::io::Result<Self> {
+        Self::open(rom_path, Some(patches))
+    }
+
+    fn open(rom_path: PathBuf, patches: Option<&[PathBuf]>) -> std::io::Result<Self> {
+        // Archived ROMs are named after their file in the archive
+        let (rom_path, mut contents) = archive::read_rom(&rom_path)?;
+        let patches = patches.map_or_else(|| patch::find(&rom_path), <[PathBuf]>::to_vec);
+
+        for path in &patches {
+            contents = patch::apply(&contents, &fs::read(path)?).map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}: {e}", path.display()),
+                )
+            })?;
+            info!("Patched the ROM with {}", path.display());
+        }
+
+        if contents.len() < 0x150 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Missing cartridge header",
+            ));
+        }
+
+        let header = CartridgeHeader::new(&contents);
+        if !header.is_supported() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("Unsupported cartridge type: {}", header.cartridge_type()),
+            ));
+        }
+        if header.rom_size().is_none() || header.ram_size().is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Invalid ROM or RAM size in the cartridge header",
+            ));
+        }
 
         let mbc = mbc(
             header.cartridge_type,
             header.ram_size,
             header.rom_size,
             contents,
-            rom_path,
         );
 
-        Self { header, mbc }
+        let mut cartridge = Self {
+            header,
+            mbc,
+            save_path: save::path(&rom_path, None),
+            rom_path,
+            cheats: Cheats::default(),
+            sram_written: false,
+            unsaved: None,
+            rtc_clock: RtcClock::WALL,
+            rtc_ticks: 0,
+        };
+        cartridge.load_save();
+        Ok(cartridge)
+    }
+
+    // Saves go to the directory from now on, the save found there is loaded
+    pub fn set_save_dir(&mut self, save_dir: &Path) {
+        self.save_path = save::path(&self.rom_path, Some(save_dir));
+        self.load_save();
+    }
+
+    pub fn save_path(&self) -> &Path {
+        &self.save_path
+    }
+
+    fn load_save(&mut self) {
+        if self.mbc.sram().is_none() {
+            return;
+        }
+
+        let legacy_path = save::legacy_path(&self.rom_path);
+        let Some(path) = [self.save_path.clone(), legacy_path]
+            .into_iter()
+            .find(|path| path.is_file())
+        else {
+            return;
+        };
+
+        match fs::read(&path) {
+            Ok(data) => {
+                self.import_save(&data);
+                info!("Loaded the save {}", path.display());
+            }
+            Err(e) => warn!("Could not read the save {}: {e}", path.display()),
+        }
+    }
+
+    // Reads a .sav file, with or without a clock footer
+    pub fn import_save(&mut self, data: &[u8]) {
+        let (data, rtc) = save::decode(data);
+        if let Some(sram) = self.mbc.sram() {
+            let len = sram.len().min(data.len());
+            sram[..len].copy_from_slice(&data[..len]);
+        }
+        if let (Some(rtc), Some(footer)) = (self.mbc.rtc(), rtc) {
+            rtc.load_footer(&footer);
+        }
+    }
+
+    // A .sav file, None when the cartridge has no battery. Only the RAM size
+    // declared in the header is saved, more is allocated for smaller ones
+    pub fn export_save(&mut self) -> Option<Vec<u8>> {
+        let rtc = self.mbc.rtc().map(|rtc| rtc.footer());
+        let sram = self.sram()?;
+        Some(save::encode(sram, rtc.as_ref()))
+    }
+
+    // The battery backed RAM, as much of it as the header declares
+    pub fn sram(&mut self) -> Option<&mut [u8]> {
+        let ram_size = self.header.ram_size().unwrap_or_default();
+        let sram = self.mbc.sram()?;
+        let len = ram_size.min(sram.len());
+        Some(&mut sram[..len])
+    }
+
+    pub fn save(&mut self) {
+        self.sram_written = false;
+        self.unsaved = None;
+
+        let Some(data) = self.export_save() else {
+            return;
+        };
+
+        match save::write(&self.save_path, &data) {
+            Ok(()) => info!("SRAM saved to {}", self.save_path.display()),
+            Err(e) => error!("Could not save to {}: {e}", self.save_path.display()),
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.sram_written || self.unsaved.is_some()
+    }
+
+    // Called every frame, saves a few seconds after the last SRAM write
+    pub fn autosave(&mut self) {
+        self.autosave_at(Instant::now());
+    }
+
+    pub fn autosave_at(&mut self, now: Instant) {
+        if std::mem::take(&mut self.sram_written) {
+            let first = self.unsaved.map_or(now, |(first, _)| first);
+            self.unsaved = Some((first, now));
+        }
+
+        if let Some((first, last)) = self.unsaved {
+            if now - last >= AUTOSAVE_DELAY || now - first >= AUTOSAVE_MAX_DELAY {
+                self.save();
+            }
+        }
+    }
+
+    // Meant to be set before running, the save is reloaded with the new clock
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.rtc_clock = clock;
+        self.rtc_ticks = 0;
+        if let Some(rtc) = self.mbc.rtc() {
+            rtc.set_clock(clock);
+            self.load_save();
+        }
+    }
+
+    // An emulated RTC counts the cycles of the base clock, whatever the CPU speed
+    pub fn tick(&mut self, speed_mode: CPUSpeed) {
+        if self.rtc_clock != RtcClock::EMULATED {
+            return;
+        }
+
+        self.rtc_ticks += match speed_mode {
+            CPUSpeed::DOUBLE => 2,
+            _ => 4,
+        };
+        if self.rtc_ticks >= CLOCK_SPEED {
+            self.rtc_ticks -= CLOCK_SPEED;
+            if let Some(rtc) = self.mbc.rtc() {
+                rtc.tick_second();
+            }
+        }
+    }
+
+    // Power cycle, the SRAM, clock and cheats are kept
+    pub fn reset(&mut self) {
+        self.mbc.reset();
+    }
+
+    pub fn header(&self) -> &CartridgeHeader {
+        &self.header
     }
 
     pub fn read(&self, addr: u16) -> u8 {
-        self.mbc.read(addr)
+        let value = self.mbc.read(addr);
+        match addr {
+            0..=0x7fff => self.cheats.patch(addr, value),
+            _ => value,
+        }
     }
 
     pub fn write(&mut self, addr: u16, value: u8) {
-        self.mbc.write(addr, value);
+        self.sram_written |= self.mbc.write(addr, value);
+
+        // The emulated second restarts along with the RTC's own counter
+        if self.mbc.rtc().is_some_and(|rtc| rtc.take_seconds_written()) {
+            self.rtc_ticks = 0;
+        }
+    }
+
+    pub fn rom_path(&self) -> &Path {
+        &self.rom_path
     }
 
     pub fn is_cgb(&self) -> bool {
@@ -0,0 +1,267 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::{error, info, warn};
+
+#[allow(nonstandard_style)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatCode {
+    // Replaces a ROM byte, only if it holds the compare value when there is one
+    GAME_GENIE {
+        addr: u16,
+        value: u8,
+        compare: Option<u8>,
+    },
+    // Written to RAM every frame. 0x8X and 0x9X select the bank X of the
+    // cartridge RAM or the WRAM, other types write to the current banks
+    GAMESHARK {
+        bank: u8,
+        addr: u16,
+        value: u8,
+    },
+}
+
+impl FromStr for CheatCode {
+    type Err = String;
+
+    // ABC-DEF or ABC-DEF-GHI for Game Genie, ABCDEFGH for GameShark
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits: Vec<u8> = s
+            .chars()
+            .filter(|c| *c != '-')
+            .map(|c| c.to_digit(16).map(|d| d as u8))
+            .collect::<Option<_>>()
+            .ok_or(format!("Invalid cheat code {s}"))?;
+        let byte = |i: usize| digits[i] << 4 | digits[i + 1];
+
+        match digits.len() {
+            6 | 9 => Ok(CheatCode::GAME_GENIE {
+                addr: ((digits[5] as u16 ^ 0xf) << 12)
+                    | (digits[2] as u16) << 8
+                    | (digits[3] as u16) << 4
+                    | digits[4] as u16,
+                value: byte(0),
+                // H is not used, G and I hold the scrambled compare value
+                compare: (digits.len() == 9)
+                    .then(|| (digits[6] << 4 | digits[8]).rotate_right(2) ^ 0xba),
+            }),
+            8 => match u16::from_le_bytes([byte(4), byte(6)]) {
+                addr @ (0xa000..=0xdfff | 0xff80..=0xfffe) => Ok(CheatCode::GAMESHARK {
+                    bank: byte(0),
+                    value: byte(2),
+                    addr,
+                }),
+                addr => Err(format!("GameShark address {addr:04X} out of RAM in {s}")),
+            },
+            _ => Err(format!("Invalid cheat code {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cheat {
+    pub name: String,
+    // As typed, several codes can be joined with '+'
+    pub code: String,
+    pub enabled: bool,
+    codes: Vec<CheatCode>,
+}
+
+impl Cheat {
+    pub fn new(name: &str, code: &str, enabled: bool) -> Result<Self, String> {
+        Ok(Self {
+            name: name.trim().to_string(),
+            code: code.trim().to_uppercase(),
+            enabled,
+            codes: code
+                .split('+')
+                .map(|c| c.trim().parse())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn codes(&self) -> &[CheatCode] {
+        &self.codes
+    }
+}
+
+// Cheats of a ROM, kept in the RetroArch .cht format
+#[derive(Default)]
+pub struct Cheats {
+    cheats: Vec<Cheat>,
+    path: Option<PathBuf>,
+
+    // Enabled codes, by kind
+    genie: Vec<(u16, u8, Option<u8>)>,
+    shark: Vec<(u8, u16, u8)>,
+    last_frame: u64,
+}
+
+impl Cheats {
+    // Next to the ROM, or in the saves directory when there is one
+    pub fn path(rom_path: &Path, save_dir: Option<&Path>) -> PathBuf {
+        let path = rom_path.with_extension("cht");
+        match (save_dir, path.file_name()) {
+            (Some(dir), Some(name)) => dir.join(name),
+            _ => path,
+        }
+    }
+
+    // Changes are written back to the file
+    pub fn load(path: PathBuf) -> Self {
+        let mut cheats = Self::default();
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                cheats.cheats = Self::parse(&contents);
+                info!("Loaded {} cheats from {path:?}", cheats.cheats.len());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => error!("Could not read cheats from {path:?}: {e}"),
+        }
+
+        cheats.path = Some(path);
+        cheats.update();
+        cheats
+    }
+
+    fn parse(contents: &str) -> Vec<Cheat> {
+        let mut fields: Vec<[Option<&str>; 3]> = vec![];
+
+        for line in contents.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let Some((idx, field)) = key
+                .trim()
+                .strip_prefix("cheat")
+                .and_then(|k| k.split_once('_'))
+                .and_then(|(idx, field)| Some((idx.parse::<usize>().ok()?, field)))
+            else {
+                continue;
+            };
+
+            let field = match field {
+                "desc" => 0,
+                "code" => 1,
+                "enable" => 2,
+                _ => continue,
+            };
+            if fields.len() <= idx {
+                fields.resize(idx + 1, [None; 3]);
+            }
+            fields[idx][field] = Some(value.trim().trim_matches('"'));
+        }
+
+        fields
+            .iter()
+            .filter_map(|[name, code, enabled]| {
+                let cheat = Cheat::new(
+                    name.unwrap_or_default(),
+                    code.unwrap_or_default(),
+                    *enabled == Some("true"),
+                );
+                cheat.inspect_err(|e| warn!("Skipping cheat: {e}")).ok()
+            })
+            .collect()
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else { return };
+
+        let mut contents = format!("cheats = {}\n", self.cheats.len());
+        for (i, cheat) in self.cheats.iter().enumerate() {
+            _ = write!(
+                contents,
+                "\ncheat{i}_desc = \"{}\"\ncheat{i}_code = \"{}\"\ncheat{i}_enable = {}\n",
+                cheat.name, cheat.code, cheat.enabled
+            );
+        }
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let result = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(path, contents));
+        if let Err(e) = result {
+            error!("Could not save cheats to {path:?}: {e}");
+        }
+    }
+
+    fn update(&mut self) {
+        self.genie.clear();
+        self.shark.clear();
+
+        for code in self
+            .cheats
+            .iter()
+            .filter(|c| c.enabled)
+            .flat_map(|c| c.codes())
+        {
+            match *code {
+                CheatCode::GAME_GENIE {
+                    addr,
+                    value,
+                    compare,
+                } => self.genie.push((addr, value, compare)),
+                CheatCode::GAMESHARK { bank, addr, value } => self.shark.push((bank, addr, value)),
+            }
+        }
+    }
+
+    pub fn list(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    // Replaces the cheat at idx, or appends it past the end
+    pub fn set(&mut self, idx: usize, cheat: Cheat) {
+        match self.cheats.get_mut(idx) {
+            Some(c) => *c = cheat,
+            None => self.cheats.push(cheat),
+        }
+        self.update();
+        self.save();
+    }
+
+    pub fn add(&mut self, cheat: Cheat) {
+        self.set(self.cheats.len(), cheat);
+    }
+
+    pub fn remove(&mut self, idx: usize) {
+        if idx < self.cheats.len() {
+            self.cheats.remove(idx);
+            self.update();
+            self.save();
+        }
+    }
+
+    pub fn set_enabled(&mut self, idx: usize, enabled: bool) {
+        if let Some(cheat) = self.cheats.get_mut(idx) {
+            cheat.enabled = enabled;
+            self.update();
+            self.save();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cheats.clear();
+        self.update();
+        self.save();
+    }
+
+    // Game Genie patches, applied on ROM reads
+    #[inline]
+    pub fn patch(&self, addr: u16, value: u8) -> u8 {
+        self.genie
+            .iter()
+            .find(|(a, _, compare)| *a == addr && compare.is_none_or(|c| c == value))
+            .map_or(value, |(_, v, _)| *v)
+    }
+
+    // GameShark writes, once per frame
+    pub fn frame_writes(&mut self, frame: u64) -> &[(u8, u16, u8)] {
+        if frame == self.last_frame {
+            return &[];
+        }
+        self.last_frame = frame;
+        &self.shark
+    }
+}
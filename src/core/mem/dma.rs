@@ -1,6 +1,7 @@
-use log::{error, info};
+use log::info;
 
-use crate::{flag_set, set_u16_hi, set_u16_lo};
+use crate::core::save_state::{SaveState, StateError};
+use crate::{flag_set, save_state, set_u16_hi, set_u16_lo};
 
 const DMA_MODE: u8 = 0x80;
 
@@ -12,13 +13,54 @@ pub enum VramDMAMode {
     GENERAL,
 }
 
+impl SaveState for VramDMAMode {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        let mode: u8 = match self {
+            VramDMAMode::IDLE => 0,
+            VramDMAMode::HBLANK => 1,
+            VramDMAMode::GENERAL => 2,
+        };
+        mode.save_state(out);
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        let mut mode = 0u8;
+        mode.load_state(data)?;
+
+        *self = match mode {
+            0 => VramDMAMode::IDLE,
+            1 => VramDMAMode::HBLANK,
+            2 => VramDMAMode::GENERAL,
+            _ => return Err(StateError::Invalid),
+        };
+        Ok(())
+    }
+}
+
 pub struct VramDMA {
     pub src: u16,
     pub dst: u16,
     pub mode: VramDMAMode,
     pub remaining: u16,
+
+    // Bytes left in the 16 bytes block being copied, the CPU is stalled meanwhile
+    pub block: u16,
+    // Whether the PPU was in HBlank during the previous M-cycle
+    pub hblank: bool,
+    // Set when an HBlank DMA was just started
+    pub just_started: bool,
 }
 
+save_state!(VramDMA {
+    src,
+    dst,
+    mode,
+    remaining,
+    block,
+    hblank,
+    just_started
+});
+
 impl std::fmt::Debug for VramDMA {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -36,6 +78,9 @@ impl Default for VramDMA {
             dst: 0xff,
             mode: VramDMAMode::IDLE,
             remaining: 0,
+            block: 0,
+            hblank: false,
+            just_started: false,
         }
     }
 }
@@ -48,7 +93,7 @@ impl VramDMA {
             0xff53 => self.dst = set_u16_hi!(self.dst, value),
             0xff54 => self.dst = set_u16_lo!(self.dst, value),
             0xff55 => {
-                if self.remaining > 0 && !flag_set!(value, DMA_MODE) {
+                if self.mode == VramDMAMode::HBLANK && !flag_set!(value, DMA_MODE) {
                     // Ongoing HBlank DMA - stop requested
                     self.mode = VramDMAMode::IDLE;
                     return;
@@ -67,7 +112,7 @@ impl VramDMA {
                     true => VramDMAMode::HBLANK,
                     false => VramDMAMode::GENERAL,
                 };
-                // dbg!(self);
+                self.just_started = true;
             }
             _ => unreachable!(),
         }
@@ -79,17 +124,18 @@ impl VramDMA {
                 info!("Attempt to read write-only value @0x{addr:04X}");
                 0xff
             }
-            0xff55 => match self.mode {
-                VramDMAMode::IDLE => {
-                    if self.remaining == 0 {
-                        0xff
-                    } else {
-                        ((self.remaining / 16) - 1) as u8 | 0x80
-                    }
+            0xff55 => {
+                if self.remaining == 0 {
+                    return 0xff;
+                }
+
+                // Remaining blocks minus one, bit 7 set once stopped
+                let blocks = ((self.remaining - 1) / 16) as u8;
+                match self.mode {
+                    VramDMAMode::IDLE => blocks | 0x80,
+                    _ => blocks,
                 }
-                VramDMAMode::HBLANK => ((self.remaining / 16) - 1) as u8,
-                _ => unreachable!(),
-            },
+            }
             _ => unreachable!(),
         }
     }
@@ -97,24 +143,81 @@ impl VramDMA {
     pub fn reset(&mut self) {
         self.mode = VramDMAMode::IDLE;
         self.remaining = 0;
+        self.block = 0;
         self.src = 0xffff;
         self.dst = 0xffff;
     }
 }
 
+const OAM_DMA_LENGTH: u16 = 0xa0;
+// M-cycles between the write to 0xFF46 and the first transferred byte
+const OAM_DMA_STARTUP: u8 = 2;
+
 #[derive(Default)]
 pub struct OamDMA {
+    // Last value written to 0xFF46
+    pub reg: u8,
     pub src: u16,
-    pub dst: u16,
+    // Index of the next byte to transfer, None when idle
+    pub idx: Option<u16>,
+
+    // Pending (re)start, counting down the startup delay
+    requested: Option<(u16, u8)>,
 }
 
+save_state!(OamDMA {
+    reg,
+    src,
+    idx,
+    requested
+});
+
 impl OamDMA {
     pub fn init(&mut self, src: u8) {
-        if self.src > 0 {
-            error!("Overwriting OAM DMA!");
+        self.reg = src;
+
+        // 0xE000-0xFFFF sources are mapped to WRAM
+        let mut src = src as u16 * 0x100;
+        if src >= 0xe000 {
+            src -= 0x2000;
+        }
+
+        // An ongoing transfer keeps going until the new one starts
+        self.requested = Some((src, OAM_DMA_STARTUP));
+    }
+
+    // Returns the (src, dst) addresses of the byte to transfer this M-cycle
+    pub fn tick(&mut self) -> Option<(u16, u16)> {
+        let transfer = self.idx.map(|idx| (self.src + idx, 0xfe00 + idx));
+
+        if let Some(idx) = self.idx {
+            self.idx = match idx + 1 {
+                OAM_DMA_LENGTH => None,
+                next => Some(next),
+            };
         }
 
-        self.src = src as u16 * 0x100;
-        self.dst = 0xfe00;
+        if let Some((src, delay)) = self.requested {
+            if delay > 1 {
+                self.requested = Some((src, delay - 1));
+            } else {
+                self.requested = None;
+                self.src = src;
+                self.idx = Some(0);
+            }
+        }
+
+        transfer
+    }
+
+    // While a transfer is running, the CPU can only reach HRAM
+    pub fn is_active(&self) -> bool {
+        self.idx.is_some()
+    }
+
+    // Address of the byte currently on the DMA bus, the last one transferred
+    // since the index already moved to the next byte
+    pub fn current_addr(&self) -> u16 {
+        self.src + self.idx.unwrap_or(0).saturating_sub(1)
     }
 }
@@ -0,0 +1,503 @@
+use std::fmt::Display;
+
+const NINTENDO_LOGO: [u8; 0x30] = [
+    0xce, 0xed, 0x66, 0x66, 0xcc, 0x0d, 0x00, 0x0b, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0c, 0x00, 0x0d,
+    0x00, 0x08, 0x11, 0x1f, 0x88, 0x89, 0x00, 0x0e, 0xdc, 0xcc, 0x6e, 0xe6, 0xdd, 0xdd, 0xd9, 0x99,
+    0xbb, 0xbb, 0x67, 0x63, 0x6e, 0x0e, 0xec, 0xcc, 0xdd, 0xdc, 0x99, 0x9f, 0xbb, 0xb9, 0x33, 0x3e,
+];
+
+// Old licensee code telling the new one should be used instead
+const USE_NEW_LICENSEE: u8 = 0x33;
+
+#[derive(Clone)]
+pub struct CartridgeHeader {
+    //0104-0133 — Nintendo logo
+    //0134-0143 — Title
+    //013F-0142 — Manufacturer code
+    //0143 — CGB flag
+    //0144–0145 — New licensee code
+    //0146 — SGB flag
+    //0147 — Cartridge type
+    //0148 — ROM size
+    //0149 — RAM size
+    //014A — Destination code
+    //014B — Old licensee code
+    //014C — Mask ROM version number
+    //014D — Header checksum
+    //014E-014F — Global checksum
+    title: String,             // 11-16 bytes
+    manufacturer_code: String, // 4 bytes
+    cgb_flag: u8,
+    new_licensee_code: String, // 2 bytes
+    sgb_flag: u8,
+    pub(super) cartridge_type: u8,
+    pub(super) rom_size: u8,
+    pub(super) ram_size: u8,
+    dest_code: u8,
+    old_licensee_code: u8,
+    rom_version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+
+    // Computed over the whole ROM, to validate the dump
+    logo_ok: bool,
+    computed_header_checksum: u8,
+    computed_global_checksum: u16,
+    file_size: usize,
+}
+
+fn text(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+fn size(bytes: usize) -> String {
+    match bytes {
+        0 => "None".into(),
+        _ if bytes.is_multiple_of(0x100000) => format!("{} MiB", bytes / 0x100000),
+        _ => format!("{} KiB", bytes / 0x400),
+    }
+}
+
+impl CartridgeHeader {
+    // CGB flag: 0x80 = CGB only, 0xC0 = CGB+DMG, 0x00 = DMG only
+    pub fn is_cgb(&self) -> bool {
+        self.cgb_flag & 0xC0 != 0
+    }
+
+    // Parsed from the whole ROM, which must hold the header
+    pub fn new(rom: &[u8]) -> Self {
+        let header = &rom[0x134..=0x14f];
+        let title_len = match header[15] & 0x80 {
+            0 => 16,
+            _ => 15,
+        };
+
+        Self {
+            title: text(&header[0..title_len]),
+            manufacturer_code: text(&header[11..15]),
+            cgb_flag: header[15],
+            new_licensee_code: text(&header[16..18]),
+            sgb_flag: header[18],
+            cartridge_type: header[19],
+            rom_size: header[20],
+            ram_size: header[21],
+            dest_code: header[22],
+            old_licensee_code: header[23],
+            rom_version: header[24],
+            header_checksum: header[25],
+            global_checksum: u16::from_be_bytes(header[26..28].try_into().unwrap()),
+            logo_ok: rom[0x104..0x134] == NINTENDO_LOGO,
+            computed_header_checksum: header[..25]
+                .iter()
+                .fold(0u8, |sum, b| sum.wrapping_sub(*b).wrapping_sub(1)),
+            computed_global_checksum: rom
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !(0x14e..=0x14f).contains(i))
+                .fold(0u16, |sum, (_, b)| sum.wrapping_add(*b as u16)),
+            file_size: rom.len(),
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn licensee(&self) -> String {
+        match self.old_licensee_code {
+            USE_NEW_LICENSEE => format!(
+                "{} ({})",
+                new_licensee(&self.new_licensee_code),
+                self.new_licensee_code
+            ),
+            code => format!("{} ({code:02X})", old_licensee(code)),
+        }
+    }
+
+    pub fn cartridge_type(&self) -> &'static str {
+        match self.cartridge_type {
+            0x00 => "ROM ONLY",
+            0x01 => "MBC1",
+            0x02 => "MBC1+RAM",
+            0x03 => "MBC1+RAM+BATTERY",
+            0x05 => "MBC2",
+            0x06 => "MBC2+BATTERY",
+            0x08 => "ROM+RAM",
+            0x09 => "ROM+RAM+BATTERY",
+            0x0b => "MMM01",
+            0x0c => "MMM01+RAM",
+            0x0d => "MMM01+RAM+BATTERY",
+            0x0f => "MBC3+TIMER+BATTERY",
+            0x10 => "MBC3+TIMER+RAM+BATTERY",
+            0x11 => "MBC3",
+            0x12 => "MBC3+RAM",
+            0x13 => "MBC3+RAM+BATTERY",
+            0x19 => "MBC5",
+            0x1a => "MBC5+RAM",
+            0x1b => "MBC5+RAM+BATTERY",
+            0x1c => "MBC5+RUMBLE",
+            0x1d => "MBC5+RUMBLE+RAM",
+            0x1e => "MBC5+RUMBLE+RAM+BATTERY",
+            0x20 => "MBC6",
+            0x22 => "MBC7+SENSOR+RUMBLE+RAM+BATTERY",
+            0xfc => "POCKET CAMERA",
+            0xfd => "BANDAI TAMA5",
+            0xfe => "HuC3",
+            0xff => "HuC1+RAM+BATTERY",
+            _ => "Unknown",
+        }
+    }
+
+    // Controllers emulated by xenogb
+    pub fn is_supported(&self) -> bool {
+        matches!(self.cartridge_type, 0x00..=0x03 | 0x0f..=0x13 | 0x19..=0x1e)
+    }
+
+    pub fn rom_size(&self) -> Option<usize> {
+        match self.rom_size {
+            0x00..=0x08 => Some(0x8000 << self.rom_size),
+            0x52 => Some(72 * 0x4000),
+            0x53 => Some(80 * 0x4000),
+            0x54 => Some(96 * 0x4000),
+            _ => None,
+        }
+    }
+
+    pub fn ram_size(&self) -> Option<usize> {
+        match self.ram_size {
+            0x00 => Some(0),
+            0x01 => Some(0x800),
+            0x02 => Some(0x2000),
+            0x03 => Some(0x8000),
+            0x04 => Some(0x20000),
+            0x05 => Some(0x10000),
+            _ => None,
+        }
+    }
+
+    pub fn destination(&self) -> &'static str {
+        match self.dest_code {
+            0x00 => "Japan",
+            0x01 => "Overseas",
+            _ => "Unknown",
+        }
+    }
+
+    pub fn logo_ok(&self) -> bool {
+        self.logo_ok
+    }
+
+    pub fn header_checksum_ok(&self) -> bool {
+        self.header_checksum == self.computed_header_checksum
+    }
+
+    pub fn global_checksum_ok(&self) -> bool {
+        self.global_checksum == self.computed_global_checksum
+    }
+
+    // Field names and values, as shown to the user
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![("Title", self.title.clone())];
+        // Older CGB titles run over the manufacturer code
+        let code = &self.manufacturer_code;
+        if self.is_cgb()
+            && code.len() == 4
+            && code
+                .bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        {
+            rows.push(("Manufacturer", code.clone()));
+        }
+
+        let cartridge_type = format!("{} (0x{:02X})", self.cartridge_type(), self.cartridge_type);
+        rows.extend([
+            ("Licensee", self.licensee()),
+            (
+                "Cartridge type",
+                match self.is_supported() {
+                    true => cartridge_type,
+                    false => format!("{cartridge_type}, unsupported"),
+                },
+            ),
+            (
+                "ROM size",
+                match self.rom_size() {
+                    Some(rom_size) if rom_size == self.file_size => size(rom_size),
+                    Some(rom_size) => format!(
+                        "{}, but the file holds {}",
+                        size(rom_size),
+                        size(self.file_size)
+                    ),
+                    None => format!("Unknown (0x{:02X})", self.rom_size),
+                },
+            ),
+            (
+                "RAM size",
+                self.ram_size()
+                    .map_or(format!("Unknown (0x{:02X})", self.ram_size), size),
+            ),
+            (
+                "CGB",
+                match self.cgb_flag {
+                    0xc0 => "CGB only",
+                    0x80 => "CGB enhanced",
+                    _ => "No",
+                }
+                .into(),
+            ),
+            (
+                "SGB",
+                match self.sgb_flag {
+                    0x03 => "Yes",
+                    _ => "No",
+                }
+                .into(),
+            ),
+            ("Destination", self.destination().into()),
+            ("Version", self.rom_version.to_string()),
+        ]);
+        rows
+    }
+
+    // Validations of the dump, with whether they passed
+    pub fn checks(&self) -> [(&'static str, bool, String); 3] {
+        let checksum = |ok: bool, value: String, expected: String| match ok {
+            true => format!("{value} OK"),
+            false => format!("{value} BAD, expected {expected}"),
+        };
+
+        [
+            (
+                "Nintendo logo",
+                self.logo_ok(),
+                match self.logo_ok() {
+                    true => "OK".into(),
+                    false => "BAD".into(),
+                },
+            ),
+            (
+                "Header checksum",
+                self.header_checksum_ok(),
+                checksum(
+                    self.header_checksum_ok(),
+                    format!("0x{:02X}", self.header_checksum),
+                    format!("0x{:02X}", self.computed_header_checksum),
+                ),
+            ),
+            (
+                "Global checksum",
+                self.global_checksum_ok(),
+                checksum(
+                    self.global_checksum_ok(),
+                    format!("0x{:04X}", self.global_checksum),
+                    format!("0x{:04X}", self.computed_global_checksum),
+                ),
+            ),
+        ]
+    }
+}
+
+impl Display for CartridgeHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let checks = self.checks().map(|(name, _, value)| (name, value));
+        for (name, value) in self.rows().into_iter().chain(checks) {
+            writeln!(f, "{:<16} {value}", format!("{name}:"))?;
+        }
+        Ok(())
+    }
+}
+
+fn new_licensee(code: &str) -> &'static str {
+    match code {
+        "00" => "None",
+        "01" => "Nintendo Research & Development 1",
+        "08" => "Capcom",
+        "13" => "EA (Electronic Arts)",
+        "18" => "Hudson Soft",
+        "19" => "B-AI",
+        "20" => "KSS",
+        "22" => "Planning Office WADA",
+        "24" => "PCM Complete",
+        "25" => "San-X",
+        "28" => "Kemco",
+        "29" => "SETA Corporation",
+        "30" => "Viacom",
+        "31" => "Nintendo",
+        "32" => "Bandai",
+        "33" => "Ocean Software/Acclaim Entertainment",
+        "34" => "Konami",
+        "35" => "HectorSoft",
+        "37" => "Taito",
+        "38" => "Hudson Soft",
+        "39" => "Banpresto",
+        "41" => "Ubi Soft",
+        "42" => "Atlus",
+        "44" => "Malibu Interactive",
+        "46" => "Angel",
+        "47" => "Bullet-Proof Software",
+        "49" => "Irem",
+        "50" => "Absolute",
+        "51" => "Acclaim Entertainment",
+        "52" => "Activision",
+        "53" => "Sammy USA Corporation",
+        "54" => "Konami",
+        "55" => "Hi Tech Expressions",
+        "56" => "LJN",
+        "57" => "Matchbox",
+        "58" => "Mattel",
+        "59" => "Milton Bradley Company",
+        "60" => "Titus Interactive",
+        "61" => "Virgin Games Ltd.",
+        "64" => "Lucasfilm Games",
+        "67" => "Ocean Software",
+        "69" => "EA (Electronic Arts)",
+        "70" => "Infogrames",
+        "71" => "Interplay Entertainment",
+        "72" => "Broderbund",
+        "73" => "Sculptured Software",
+        "75" => "The Sales Curve Limited",
+        "78" => "THQ",
+        "79" => "Accolade",
+        "80" => "Misawa Entertainment",
+        "83" => "LOZC G.",
+        "86" => "Tokuma Shoten",
+        "87" => "Tsukuda Original",
+        "91" => "Chunsoft Co.",
+        "92" => "Video System",
+        "93" => "Ocean Software/Acclaim Entertainment",
+        "95" => "Varie",
+        "96" => "Yonezawa/S'Pal",
+        "97" => "Kaneko",
+        "99" => "Pack-In-Video",
+        "9H" => "Bottom Up",
+        "A4" => "Konami (Yu-Gi-Oh!)",
+        "BL" => "MTO",
+        "DK" => "Kodansha",
+        _ => "Unknown",
+    }
+}
+
+fn old_licensee(code: u8) -> &'static str {
+    match code {
+        0x00 => "None",
+        0x01 | 0x31 => "Nintendo",
+        0x08 | 0x38 => "Capcom",
+        0x09 => "HOT-B",
+        0x0a | 0xe0 => "Jaleco",
+        0x0b => "Coconuts Japan",
+        0x0c | 0x6e => "Elite Systems",
+        0x13 | 0x69 => "EA (Electronic Arts)",
+        0x18 => "Hudson Soft",
+        0x19 => "ITC Entertainment",
+        0x1a => "Yanoman",
+        0x1d => "Japan Clary",
+        0x1f | 0x4a | 0x61 => "Virgin Games Ltd.",
+        0x24 => "PCM Complete",
+        0x25 => "San-X",
+        0x28 | 0x7f | 0x97 | 0xc2 => "Kemco",
+        0x29 => "SETA Corporation",
+        0x30 | 0x70 => "Infogrames",
+        0x32 | 0xa2 | 0xb2 => "Bandai",
+        0x34 | 0xa4 => "Konami",
+        0x35 => "HectorSoft",
+        0x39 | 0x9d | 0xd9 => "Banpresto",
+        0x3c => "Entertainment Interactive",
+        0x3e => "Gremlin",
+        0x41 => "Ubi Soft",
+        0x42 | 0xeb => "Atlus",
+        0x44 | 0x4d => "Malibu Interactive",
+        0x46 | 0xcf => "Angel",
+        0x47 => "Spectrum HoloByte",
+        0x49 => "Irem",
+        0x4f => "U.S. Gold",
+        0x50 => "Absolute",
+        0x51 | 0xb0 => "Acclaim Entertainment",
+        0x52 => "Activision",
+        0x53 => "Sammy USA Corporation",
+        0x54 => "GameTek",
+        0x55 => "Park Place",
+        0x56 | 0xdb | 0xff => "LJN",
+        0x57 => "Matchbox",
+        0x59 => "Milton Bradley Company",
+        0x5a => "Mindscape",
+        0x5b => "Romstar",
+        0x5c | 0xd6 => "Naxat Soft",
+        0x5d => "Tradewest",
+        0x60 => "Titus Interactive",
+        0x67 => "Ocean Software",
+        0x6f => "Electro Brain",
+        0x71 => "Interplay Entertainment",
+        0x72 | 0xaa => "Broderbund",
+        0x73 => "Sculptured Software",
+        0x75 => "The Sales Curve Limited",
+        0x78 => "THQ",
+        0x79 => "Accolade",
+        0x7a => "Triffix Entertainment",
+        0x7c => "MicroProse",
+        0x80 => "Misawa Entertainment",
+        0x83 => "LOZC G.",
+        0x86 | 0xc4 => "Tokuma Shoten",
+        0x8b => "Bullet-Proof Software",
+        0x8c => "Vic Tokai Corp.",
+        0x8e => "Ape Inc.",
+        0x8f => "I'Max",
+        0x91 => "Chunsoft Co.",
+        0x92 => "Video System",
+        0x93 => "Tsubaraya Productions",
+        0x95 | 0xe3 => "Varie",
+        0x96 => "Yonezawa/S'Pal",
+        0x99 => "Arc",
+        0x9a => "Nihon Bussan",
+        0x9b => "Tecmo",
+        0x9c => "Imagineer",
+        0x9f => "Nova",
+        0xa1 => "Hori Electric",
+        0xa6 => "Kawada",
+        0xa7 => "Takara",
+        0xa9 => "Technos Japan",
+        0xac => "Toei Animation",
+        0xad => "Toho",
+        0xaf => "Namco",
+        0xb1 => "ASCII Corporation or Nexsoft",
+        0xb4 => "Square Enix",
+        0xb6 => "HAL Laboratory",
+        0xb7 => "SNK",
+        0xb9 | 0xce => "Pony Canyon",
+        0xba => "Culture Brain",
+        0xbb => "Sunsoft",
+        0xbd => "Sony Imagesoft",
+        0xbf => "Sammy Corporation",
+        0xc0 | 0xd0 => "Taito",
+        0xc3 => "Square",
+        0xc5 => "Data East",
+        0xc6 => "Tonkin House",
+        0xc8 => "Koei",
+        0xc9 => "UFL",
+        0xca => "Ultra Games",
+        0xcb => "VAP, Inc.",
+        0xcc => "Use Corporation",
+        0xcd => "Meldac",
+        0xd1 => "SOFEL",
+        0xd2 => "Quest",
+        0xd3 => "Sigma Enterprises",
+        0xd4 => "ASK Kodansha Co.",
+        0xd7 => "Copya System",
+        0xda => "Tomy",
+        0xdd => "Nippon Computer Systems",
+        0xde => "Human Ent.",
+        0xdf => "Altron",
+        0xe1 => "Towa Chiki",
+        0xe2 => "Yutaka",
+        0xe5 => "Epoch",
+        0xe7 => "Athena",
+        0xe8 => "Asmik Ace Entertainment",
+        0xe9 => "Natsume",
+        0xea => "King Records",
+        0xec => "Epic/Sony Records",
+        0xee => "IGS",
+        0xf0 => "A Wave",
+        0xf3 => "Extreme Entertainment",
+        _ => "Unknown",
+    }
+}
@@ -1,8 +1,7 @@
-use std::path::PathBuf;
-
 use log::warn;
 
 use super::MemoryBankController;
+use crate::core::save_state::{SaveState, StateError};
 
 pub struct MBC1 {
     rom: Vec<u8>,
@@ -14,19 +13,10 @@ pub struct MBC1 {
     banking_mode: u8,
 
     has_save: bool,
-    save_fname: PathBuf,
 }
 
 impl MBC1 {
-    pub fn new(
-        rom: Vec<u8>,
-        ram_banks_code: u8,
-        rom_banks_code: u8,
-        has_save: bool,
-        mut rom_fname: PathBuf,
-    ) -> Self {
-        rom_fname.set_extension("gbsave");
-
+    pub fn new(rom: Vec<u8>, ram_banks_code: u8, rom_banks_code: u8, has_save: bool) -> Self {
         Self {
             rom,
             rom_bank: 1,
@@ -34,13 +24,30 @@ impl MBC1 {
             ram_bank: 0,
             ram_enable: false,
             banking_mode: 0,
-            sram: Self::load_sram(&rom_fname, has_save, ram_banks_code),
+            sram: Self::build_sram(ram_banks_code),
             has_save,
-            save_fname: rom_fname,
         }
     }
 }
 
+impl SaveState for MBC1 {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        Self::save_sram_state(&self.sram, out);
+        self.rom_bank.save_state(out);
+        self.ram_bank.save_state(out);
+        self.ram_enable.save_state(out);
+        self.banking_mode.save_state(out);
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        Self::load_sram_state(&mut self.sram, data)?;
+        self.rom_bank.load_state(data)?;
+        self.ram_bank.load_state(data)?;
+        self.ram_enable.load_state(data)?;
+        self.banking_mode.load_state(data)
+    }
+}
+
 impl MemoryBankController for MBC1 {
     fn read(&self, addr: u16) -> u8 {
         match addr {
@@ -66,7 +73,7 @@ impl MemoryBankController for MBC1 {
         }
     }
 
-    fn write(&mut self, addr: u16, value: u8) {
+    fn write(&mut self, addr: u16, value: u8) -> bool {
         match addr {
             0x0..=0x1fff => self.ram_enable = value & 0xf == 0xa,
             0x2000..=0x3fff => self.rom_bank = (value as usize & 0x1f).max(1) & self.rom_bank_mask,
@@ -76,14 +83,25 @@ impl MemoryBankController for MBC1 {
                 if self.ram_enable {
                     self.sram[self.ram_bank][addr as usize - 0xa000] = value;
                 }
+                return self.ram_enable;
             }
             _ => warn!("mbc1.write: unhandled address 0x{addr:04X}"),
         }
+        false
     }
 
-    fn save(&self) {
-        if self.has_save {
-            Self::save_sram(&self.save_fname, &self.sram);
-        }
+    fn sram(&mut self) -> Option<&mut [u8]> {
+        self.has_save.then(|| self.sram.as_flattened_mut())
+    }
+
+    fn ram(&mut self) -> &mut [u8] {
+        self.sram.as_flattened_mut()
+    }
+
+    fn reset(&mut self) {
+        self.rom_bank = 1;
+        self.ram_bank = 0;
+        self.ram_enable = false;
+        self.banking_mode = 0;
     }
 }
@@ -1,23 +1,35 @@
 mod mbc1;
 mod mbc3;
 mod mbc5;
-use std::io::Write;
-use std::{
-    fs::{self, File},
-    path::PathBuf,
-};
-
-use log::info;
+use super::rtc::RTC;
+use crate::core::save_state::{SaveState, StateError};
 use mbc1::MBC1;
 use mbc3::MBC3;
 use mbc5::MBC5;
 
-pub trait MemoryBankController {
+pub trait MemoryBankController: SaveState {
     fn read(&self, addr: u16) -> u8;
 
-    fn write(&mut self, addr: u16, value: u8);
+    // Whether the RAM or the clock were written to, which then need saving
+    fn write(&mut self, addr: u16, value: u8) -> bool;
 
-    fn save(&self);
+    // Battery backed RAM, None when it is not saved
+    fn sram(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+
+    // The whole cartridge RAM, battery backed or not
+    fn ram(&mut self) -> &mut [u8] {
+        &mut []
+    }
+
+    // Saved along with the SRAM
+    fn rtc(&mut self) -> Option<&mut RTC> {
+        None
+    }
+
+    // Power cycle, the RAM and clock are kept as on a real cartridge
+    fn reset(&mut self) {}
 
     fn build_sram(ram_banks_code: u8) -> Vec<[u8; 0x2000]>
     where
@@ -39,33 +51,25 @@ pub trait MemoryBankController {
         vec
     }
 
-    fn save_sram(save_fname: &PathBuf, sram: &[[u8; 0x2000]])
+    // The number of banks is saved too, not to misread the rest of the state
+    fn save_sram_state(sram: &[[u8; 0x2000]], out: &mut Vec<u8>)
     where
         Self: Sized,
     {
-        let mut file = File::create(save_fname).unwrap();
-        for bank in sram.iter() {
-            file.write_all(&bank[..]).unwrap();
-        }
-        info!("SRAM saved!");
+        sram.len().save_state(out);
+        sram.save_state(out);
     }
 
-    fn load_sram(path: &PathBuf, has_save: bool, ram_banks_code: u8) -> Vec<[u8; 0x2000]>
+    fn load_sram_state(sram: &mut [[u8; 0x2000]], data: &mut &[u8]) -> Result<(), StateError>
     where
         Self: Sized,
     {
-        if !has_save || !path.exists() {
-            return Self::build_sram(ram_banks_code);
+        let mut banks = 0usize;
+        banks.load_state(data)?;
+        if banks != sram.len() {
+            return Err(StateError::Invalid);
         }
-
-        let bytes = fs::read(path).unwrap();
-        let mut sram: Vec<[u8; 0x2000]> = Vec::new();
-        sram.resize(bytes.len() / 0x2000, [0; 0x2000]);
-
-        for bank in 0..sram.len() {
-            sram[bank].copy_from_slice(&bytes[bank * 0x2000..bank * 0x2000 + 0x2000]);
-        }
-        sram
+        sram.load_state(data)
     }
 }
 
@@ -79,14 +83,22 @@ impl NoMBC {
     }
 }
 
+impl SaveState for NoMBC {
+    fn save_state(&self, _: &mut Vec<u8>) {}
+
+    fn load_state(&mut self, _: &mut &[u8]) -> Result<(), StateError> {
+        Ok(())
+    }
+}
+
 impl MemoryBankController for NoMBC {
-    fn write(&mut self, _: u16, _: u8) {}
+    fn write(&mut self, _: u16, _: u8) -> bool {
+        false
+    }
 
     fn read(&self, addr: u16) -> u8 {
         self.rom[addr as usize]
     }
-
-    fn save(&self) {}
 }
 
 pub fn mbc(
@@ -94,29 +106,17 @@ pub fn mbc(
     ram_banks_code: u8,
     rom_banks_code: u8,
     rom: Vec<u8>,
-    rom_fname: PathBuf,
 ) -> Box<dyn MemoryBankController + Send + Sync> {
     match mbc_code {
         0x0 => Box::new(NoMBC::new(rom)),
-        0x1 | 0x2 => Box::new(MBC1::new(
-            rom,
-            ram_banks_code,
-            rom_banks_code,
-            false,
-            rom_fname,
-        )),
-        0x3 => Box::new(MBC1::new(
-            rom,
-            ram_banks_code,
-            rom_banks_code,
-            true,
-            rom_fname,
-        )),
-        0xf | 0x10 => Box::new(MBC3::new(rom, ram_banks_code, true, true, rom_fname)),
-        0x11 | 0x12 => Box::new(MBC3::new(rom, ram_banks_code, false, false, rom_fname)),
-        0x13 => Box::new(MBC3::new(rom, ram_banks_code, true, false, rom_fname)),
-        0x19 | 0x1a | 0x1c | 0x1d => Box::new(MBC5::new(rom, ram_banks_code, false, rom_fname)),
-        0x1b | 0x1e => Box::new(MBC5::new(rom, ram_banks_code, true, rom_fname)),
-        _ => todo!(),
+        0x1 | 0x2 => Box::new(MBC1::new(rom, ram_banks_code, rom_banks_code, false)),
+        0x3 => Box::new(MBC1::new(rom, ram_banks_code, rom_banks_code, true)),
+        0xf | 0x10 => Box::new(MBC3::new(rom, ram_banks_code, true, true)),
+        0x11 | 0x12 => Box::new(MBC3::new(rom, ram_banks_code, false, false)),
+        0x13 => Box::new(MBC3::new(rom, ram_banks_code, true, false)),
+        0x19 | 0x1a | 0x1c | 0x1d => Box::new(MBC5::new(rom, ram_banks_code, false)),
+        0x1b | 0x1e => Box::new(MBC5::new(rom, ram_banks_code, true)),
+        // Checked by the cartridge
+        _ => unreachable!(),
     }
 }
@@ -1,6 +1,12 @@
+pub mod archive;
 pub mod boot;
 pub mod bus;
 pub mod cartridge;
+pub mod cheats;
 mod dma;
+pub mod header;
 mod mbc;
+pub mod patch;
 mod ram;
+pub mod rtc;
+pub mod save;
@@ -0,0 +1,265 @@
+use std::path::{Path, PathBuf};
+
+const PATCH_EXTENSIONS: [&str; 3] = ["ips", "ups", "bps"];
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: usize = 0x454f46;
+const UPS_MAGIC: &[u8] = b"UPS1";
+const BPS_MAGIC: &[u8] = b"BPS1";
+// Source, target and patch CRC32s
+const FOOTER_SIZE: usize = 12;
+// The largest MBC5 ROM
+const MAX_ROM_SIZE: usize = 0x800000;
+
+// Patches next to the ROM, sharing its name
+pub fn find(rom_path: &Path) -> Vec<PathBuf> {
+    PATCH_EXTENSIONS
+        .iter()
+        .map(|ext| rom_path.with_extension(ext))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+// Patches the ROM, the format is picked from the patch's magic
+pub fn apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    if patch.starts_with(IPS_MAGIC) {
+        let mut out = ips(rom, patch)?;
+        // IPS has no checksums, a patched header would fail the boot ROM's check
+        if out.get(0x134..0x14d) != rom.get(0x134..0x14d) {
+            fix_header_checksum(&mut out);
+        }
+        Ok(out)
+    } else if patch.starts_with(UPS_MAGIC) {
+        ups(rom, patch)
+    } else if patch.starts_with(BPS_MAGIC) {
+        bps(rom, patch)
+    } else {
+        Err("Unknown patch format".into())
+    }
+}
+
+fn fix_header_checksum(rom: &mut [u8]) {
+    if rom.len() < 0x150 {
+        return;
+    }
+    rom[0x14d] = rom[0x134..0x14d]
+        .iter()
+        .fold(0u8, |sum, b| sum.wrapping_sub(*b).wrapping_sub(1));
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).ok_or("Truncated patch")?;
+        let bytes = self.data.get(self.pos..end).ok_or("Truncated patch")?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn be(&mut self, len: usize) -> Result<usize, String> {
+        Ok(self
+            .bytes(len)?
+            .iter()
+            .fold(0, |value, b| value << 8 | *b as usize))
+    }
+
+    // Variable length integers of UPS and BPS
+    fn varint(&mut self) -> Result<usize, String> {
+        let (mut value, mut shift) = (0usize, 1usize);
+        loop {
+            let byte = self.byte()?;
+            value = (byte as usize & 0x7f)
+                .checked_mul(shift)
+                .and_then(|v| v.checked_add(value))
+                .ok_or("Invalid patch")?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_shl(7).ok_or("Invalid patch")?;
+            value += shift;
+        }
+    }
+
+    // Signed offsets of BPS copies
+    fn offset(&mut self, base: usize) -> Result<usize, String> {
+        let data = self.varint()?;
+        match data & 1 {
+            0 => base.checked_add(data >> 1),
+            _ => base.checked_sub(data >> 1),
+        }
+        .ok_or("Invalid patch".into())
+    }
+}
+
+fn ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = rom.to_vec();
+    let mut reader = Reader::new(patch, IPS_MAGIC.len());
+
+    loop {
+        let offset = reader.be(3)?;
+        if offset == IPS_EOF {
+            break;
+        }
+
+        let (len, rle) = match reader.be(2)? {
+            0 => (reader.be(2)?, Some(reader.byte()?)),
+            len => (len, None),
+        };
+        if offset + len > MAX_ROM_SIZE {
+            return Err("The patched ROM is too large".into());
+        }
+        if out.len() < offset + len {
+            out.resize(offset + len, 0);
+        }
+
+        match rle {
+            Some(value) => out[offset..offset + len].fill(value),
+            None => out[offset..offset + len].copy_from_slice(reader.bytes(len)?),
+        }
+    }
+
+    // Optional truncation
+    if let Ok(len) = reader.be(3) {
+        out.truncate(len);
+    }
+    Ok(out)
+}
+
+// Returns the source and target CRC32s, after checking the patch's own
+fn footer(patch: &[u8]) -> Result<(u32, u32), String> {
+    let footer = &patch[patch.len() - FOOTER_SIZE..];
+    let crc = |i: usize| u32::from_le_bytes(footer[i..i + 4].try_into().unwrap());
+
+    // Covers the whole patch but its own CRC
+    if crc32fast::hash(&patch[..patch.len() - 4]) != crc(8) {
+        return Err("Corrupted patch".into());
+    }
+    Ok((crc(0), crc(4)))
+}
+
+fn check(rom: &[u8], crc: u32, what: &str) -> Result<(), String> {
+    match crc32fast::hash(rom) == crc {
+        true => Ok(()),
+        false => Err(format!("The patch does not match the {what} ROM")),
+    }
+}
+
+fn ups(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    if patch.len() < UPS_MAGIC.len() + FOOTER_SIZE {
+        return Err("Truncated patch".into());
+    }
+    let (source_crc, target_crc) = footer(patch)?;
+    check(rom, source_crc, "source")?;
+
+    let mut reader = Reader::new(&patch[..patch.len() - FOOTER_SIZE], UPS_MAGIC.len());
+    let source_size = reader.varint()?;
+    let target_size = reader.varint()?;
+    if source_size != rom.len() {
+        return Err("The patch does not match the source ROM".into());
+    }
+    if target_size > MAX_ROM_SIZE {
+        return Err("The patched ROM is too large".into());
+    }
+
+    let mut out = rom.to_vec();
+    out.resize(target_size, 0);
+
+    // Runs of bytes XORed with the source, each ending with a 0. They never
+    // start past the end of both ROMs
+    let mut pos = 0usize;
+    while reader.pos < reader.data.len() {
+        pos = pos
+            .checked_add(reader.varint()?)
+            .filter(|pos| *pos <= source_size.max(target_size))
+            .ok_or("Invalid patch")?;
+        loop {
+            let value = reader.byte()?;
+            if value == 0 {
+                pos += 1;
+                break;
+            }
+            if let Some(byte) = out.get_mut(pos) {
+                *byte ^= value;
+            }
+            pos += 1;
+        }
+    }
+
+    check(&out, target_crc, "target")?;
+    Ok(out)
+}
+
+fn bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    if patch.len() < BPS_MAGIC.len() + FOOTER_SIZE {
+        return Err("Truncated patch".into());
+    }
+    let (source_crc, target_crc) = footer(patch)?;
+    check(rom, source_crc, "source")?;
+
+    let mut reader = Reader::new(&patch[..patch.len() - FOOTER_SIZE], BPS_MAGIC.len());
+    let source_size = reader.varint()?;
+    let target_size = reader.varint()?;
+    let metadata_size = reader.varint()?;
+    reader.bytes(metadata_size)?;
+    if source_size != rom.len() {
+        return Err("The patch does not match the source ROM".into());
+    }
+    if target_size > MAX_ROM_SIZE {
+        return Err("The patched ROM is too large".into());
+    }
+
+    let mut out = Vec::with_capacity(target_size);
+    let (mut source_pos, mut target_pos) = (0usize, 0);
+    while reader.pos < reader.data.len() {
+        let data = reader.varint()?;
+        let len = (data >> 2) + 1;
+        // Target copies could otherwise grow the ROM forever
+        if out.len() + len > target_size {
+            return Err("Invalid patch".into());
+        }
+
+        match data & 3 {
+            // Source read
+            0 => {
+                let pos = out.len();
+                out.extend_from_slice(rom.get(pos..pos + len).ok_or("Invalid patch")?);
+            }
+            // Target read
+            1 => out.extend_from_slice(reader.bytes(len)?),
+            // Source copy
+            2 => {
+                source_pos = reader.offset(source_pos)?;
+                let end = source_pos.checked_add(len).ok_or("Invalid patch")?;
+                out.extend_from_slice(rom.get(source_pos..end).ok_or("Invalid patch")?);
+                source_pos = end;
+            }
+            // Target copy, byte by byte as it may overlap what it writes
+            _ => {
+                target_pos = reader.offset(target_pos)?;
+                for _ in 0..len {
+                    let byte = *out.get(target_pos).ok_or("Invalid patch")?;
+                    out.push(byte);
+                    target_pos += 1;
+                }
+            }
+        }
+    }
+
+    if out.len() != target_size {
+        return Err("Invalid patch".into());
+    }
+    check(&out, target_crc, "target")?;
+    Ok(out)
+}
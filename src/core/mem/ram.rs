@@ -1,3 +1,4 @@
+use crate::save_state;
 use log::warn;
 
 #[allow(clippy::upper_case_acronyms)]
@@ -7,6 +8,12 @@ pub struct RAM {
     hram: [u8; 0x80],
 }
 
+save_state!(RAM {
+    wram_bank,
+    wram,
+    hram
+});
+
 impl RAM {
     pub fn new() -> Self {
         Self {
@@ -26,6 +33,19 @@ impl RAM {
         }
     }
 
+    // Writes to a WRAM bank, whichever is selected
+    pub fn write_bank(&mut self, bank: u8, addr: u16, value: u8) {
+        self.wram[(bank & 0b111).max(1) as usize][(addr - 0xd000) as usize] = value;
+    }
+
+    pub fn wram(&self) -> &[[u8; 0x1000]; 8] {
+        &self.wram
+    }
+
+    pub fn hram(&self) -> &[u8] {
+        &self.hram[..0x7f]
+    }
+
     pub fn read(&self, addr: u16) -> u8 {
         match addr {
             0xc000..=0xcfff => self.wram[0][(addr - 0xc000) as usize],
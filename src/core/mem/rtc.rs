@@ -0,0 +1,233 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use super::save::RtcFooter;
+use crate::core::save_state::{SaveState, StateError};
+
+// Day high register flags
+const DAY_HIGH: u8 = 0x01;
+const HALT: u8 = 0x40;
+const DAY_CARRY: u8 = 0x80;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum RtcClock {
+    // Counts the real time, even when the emulator is closed
+    #[default]
+    WALL,
+    // Counts the emulated cycles, for deterministic replays and tests
+    EMULATED,
+}
+
+// MBC3 real time clock
+#[derive(Default, Debug)]
+pub struct RTC {
+    clock: RtcClock,
+    // Wall time the counters were last brought up to date at
+    updated: DateTime<Utc>,
+    latch_reg: u8,
+
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    days: u16,
+    halt: bool,
+    carry: bool,
+
+    // Seconds, minutes, hours, day low and day high, as read by the game
+    latched: [u8; 5],
+    // Until the cartridge restarts the emulated second it counts
+    seconds_written: bool,
+}
+
+impl RTC {
+    pub fn new() -> Self {
+        Self {
+            updated: Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: RtcClock) {
+        self.update();
+        self.clock = clock;
+        self.updated = Utc::now();
+    }
+
+    // Brings the counters up to date with the wall time
+    fn update(&mut self) {
+        if self.clock != RtcClock::WALL {
+            return;
+        }
+
+        let now = Utc::now();
+        let elapsed = (now - self.updated).num_seconds();
+        if self.halt || elapsed < 0 {
+            self.updated = now;
+        } else if elapsed > 0 {
+            self.advance(elapsed as u64);
+            self.updated += TimeDelta::seconds(elapsed);
+        }
+    }
+
+    // Called every emulated second when driven by the emulated cycles
+    pub fn tick_second(&mut self) {
+        if self.clock == RtcClock::EMULATED && !self.halt {
+            self.advance(1);
+        }
+    }
+
+    fn advance(&mut self, mut seconds: u64) {
+        // Out of range values count up to their register's limit, one at a time
+        while seconds > 0 && (self.seconds >= 60 || self.minutes >= 60 || self.hours >= 24) {
+            self.increment();
+            seconds -= 1;
+        }
+        if seconds == 0 {
+            return;
+        }
+
+        let total =
+            seconds + self.seconds as u64 + self.minutes as u64 * 60 + self.hours as u64 * 3600;
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / 3600 % 24) as u8;
+
+        let days = self.days as u64 + total / 86400;
+        self.carry |= days >= 512;
+        self.days = (days % 512) as u16;
+    }
+
+    fn increment(&mut self) {
+        self.seconds = (self.seconds + 1) & 0x3f;
+        if self.seconds != 60 {
+            return;
+        }
+        self.seconds = 0;
+
+        self.minutes = (self.minutes + 1) & 0x3f;
+        if self.minutes != 60 {
+            return;
+        }
+        self.minutes = 0;
+
+        self.hours = (self.hours + 1) & 0x1f;
+        if self.hours != 24 {
+            return;
+        }
+        self.hours = 0;
+
+        self.days = (self.days + 1) & 0x1ff;
+        self.carry |= self.days == 0;
+    }
+
+    // Latched on a 0 then 1 write
+    pub fn write_latch(&mut self, value: u8) {
+        if self.latch_reg == 0 && value == 1 {
+            self.update();
+            self.latched = self.registers();
+        }
+        self.latch_reg = value;
+    }
+
+    // Whether the seconds were written since the last call
+    pub fn take_seconds_written(&mut self) -> bool {
+        std::mem::take(&mut self.seconds_written)
+    }
+
+    pub fn read(&self, register: usize) -> u8 {
+        match register {
+            0x8..=0xc => self.latched[register - 0x8],
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn write(&mut self, register: usize, value: u8) {
+        self.update();
+
+        match register {
+            0x8 => {
+                self.seconds = value & 0x3f;
+                // Resets the sub-second counter
+                self.updated = Utc::now();
+                self.seconds_written = true;
+            }
+            0x9 => self.minutes = value & 0x3f,
+            0xa => self.hours = value & 0x1f,
+            0xb => self.days = (self.days & 0x100) | value as u16,
+            0xc => {
+                self.days = (self.days & 0xff) | ((value & DAY_HIGH) as u16) << 8;
+                self.halt = value & HALT != 0;
+                self.carry = value & DAY_CARRY != 0;
+            }
+            _ => unreachable!(),
+        }
+        self.latched[register - 0x8] = self.registers()[register - 0x8];
+    }
+
+    fn registers(&self) -> [u8; 5] {
+        let mut day_high = (self.days >> 8) as u8 & DAY_HIGH;
+        if self.halt {
+            day_high |= HALT;
+        }
+        if self.carry {
+            day_high |= DAY_CARRY;
+        }
+        [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            (self.days & 0xff) as u8,
+            day_high,
+        ]
+    }
+
+    fn set_registers(&mut self, registers: [u8; 5]) {
+        self.seconds = registers[0] & 0x3f;
+        self.minutes = registers[1] & 0x3f;
+        self.hours = registers[2] & 0x1f;
+        self.days = registers[3] as u16 | ((registers[4] & DAY_HIGH) as u16) << 8;
+        self.halt = registers[4] & HALT != 0;
+        self.carry = registers[4] & DAY_CARRY != 0;
+    }
+
+    // The clock as saved in .sav files
+    pub fn footer(&mut self) -> RtcFooter {
+        self.update();
+        RtcFooter {
+            registers: self.registers(),
+            latched: self.latched,
+            timestamp: Utc::now().timestamp(),
+        }
+    }
+
+    // A wall clock keeps counting the time elapsed since the save
+    pub fn load_footer(&mut self, footer: &RtcFooter) {
+        self.set_registers(footer.registers);
+        self.latched = footer.latched;
+        self.updated = DateTime::from_timestamp(footer.timestamp, 0).unwrap_or_else(Utc::now);
+        self.update();
+    }
+}
+
+// The update time is saved as a timestamp, so that a wall clock keeps
+// counting the real time elapsed since
+impl SaveState for RTC {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        self.updated.timestamp().save_state(out);
+        self.latch_reg.save_state(out);
+        self.registers().save_state(out);
+        self.latched.save_state(out);
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        let mut updated = 0i64;
+        updated.load_state(data)?;
+        self.updated = DateTime::from_timestamp(updated, 0).ok_or(StateError::Invalid)?;
+
+        self.latch_reg.load_state(data)?;
+        let mut registers = [0u8; 5];
+        registers.load_state(data)?;
+        self.set_registers(registers);
+        self.latched.load_state(data)
+    }
+}
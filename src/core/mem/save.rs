@@ -0,0 +1,94 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// BGB's footer, VBA's older one has a 32 bits timestamp
+const RTC_FOOTER_SIZE: usize = 48;
+const RTC_FOOTER_SIZE_32: usize = 44;
+
+// SRAM sizes are multiples of MBC2's 512 bytes, anything past them is the footer
+const SRAM_ALIGN: usize = 0x200;
+
+// The MBC3 clock appended to .sav files, each register stored as a u32.
+// Registers are the seconds, minutes, hours, day low and day high ones
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RtcFooter {
+    pub registers: [u8; 5],
+    pub latched: [u8; 5],
+    // Unix time the save was made at
+    pub timestamp: i64,
+}
+
+impl RtcFooter {
+    fn parse(data: &[u8]) -> Self {
+        let u32_at = |i: usize| u32::from_le_bytes(data[i * 4..i * 4 + 4].try_into().unwrap());
+
+        let mut footer = Self::default();
+        for i in 0..5 {
+            footer.registers[i] = u32_at(i) as u8;
+            footer.latched[i] = u32_at(i + 5) as u8;
+        }
+        footer.timestamp = match data.len() {
+            RTC_FOOTER_SIZE => i64::from_le_bytes(data[40..48].try_into().unwrap()),
+            _ => u32_at(10) as i64,
+        };
+        footer
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        for register in self.registers.iter().chain(&self.latched) {
+            out.extend((*register as u32).to_le_bytes());
+        }
+        out.extend(self.timestamp.to_le_bytes());
+    }
+}
+
+// Saves are named after the ROM, in the saves directory or next to the ROM
+pub fn path(rom_path: &Path, save_dir: Option<&Path>) -> PathBuf {
+    let path = rom_path.with_extension("sav");
+    match (save_dir, path.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => path,
+    }
+}
+
+// Saves made by older versions, next to the ROM
+pub fn legacy_path(rom_path: &Path) -> PathBuf {
+    rom_path.with_extension("gbsave")
+}
+
+pub fn encode(sram: &[u8], rtc: Option<&RtcFooter>) -> Vec<u8> {
+    let mut out = sram.to_vec();
+    if let Some(rtc) = rtc {
+        rtc.write(&mut out);
+    }
+    out
+}
+
+// Splits a save into its SRAM and its clock, if it has one
+pub fn decode(data: &[u8]) -> (&[u8], Option<RtcFooter>) {
+    match data.len() % SRAM_ALIGN {
+        len @ (RTC_FOOTER_SIZE | RTC_FOOTER_SIZE_32) => {
+            let (sram, footer) = data.split_at(data.len() - len);
+            (sram, Some(RtcFooter::parse(footer)))
+        }
+        _ => (data, None),
+    }
+}
+
+// Written to a temporary file first, a crash never leaves a truncated save
+pub fn write(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&tmp_path, path).inspect_err(|_| _ = fs::remove_file(&tmp_path))
+}
@@ -5,4 +5,6 @@ mod macros;
 pub mod mem;
 pub mod playback;
 pub mod run_emu;
+pub mod save_state;
+pub mod stop_condition;
 mod utils;
@@ -33,7 +33,15 @@ impl Recorder {
 
     pub fn record(&mut self, event: &IOEvent, frame: u64, tick: u32) {
         if let Some(file) = &mut self.file {
-            if !matches!(event, &IOEvent::CLOSE) {
+            // Only record inputs
+            if !matches!(
+                event,
+                &IOEvent::CLOSE
+                    | &IOEvent::AUDIO_RECORD_START(_)
+                    | &IOEvent::AUDIO_RECORD_STOP
+                    | &IOEvent::VIDEO_RECORD_START(..)
+                    | &IOEvent::VIDEO_RECORD_STOP
+            ) {
                 file.write_all(format!("{frame} {tick} {event}\n").as_bytes())
                     .expect("Could not record input");
             }
@@ -1,82 +1,54 @@
-use super::cpu::instructions::CPURegisterId;
 use super::cpu::{CPUSpeed, LR35902CPU};
 use super::io::video::{lcd::Pixel, ppu::Vbuf};
 use super::io_event::{IOEvent, IOListener};
 use super::mem::bus::Bus;
 use super::playback::Playback;
+use super::stop_condition::{StopCondition, StopOutcome};
 use crate::core::io::video::ppu::{RESX, RESY};
-use crate::core::utils::{dump_regs, vbuf_snapshot};
-use crate::debugger::{init_metrics, Debugger, DebuggerCommand, EmuSnapshot};
+use crate::core::utils::{dump_regs, frame_screenshot, vbuf_snapshot};
+use crate::debugger::{Debugger, DebuggerCommand, EmuSnapshot};
 
 use std::backtrace::Backtrace;
 use std::panic;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
 use log::info;
 
-#[derive(Clone, Debug, Copy)]
-pub enum StopCondition {
-    LDBB,
-    TIMER(u32),
-}
-
-impl std::str::FromStr for StopCondition {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_ascii_uppercase().as_str() {
-            "LDBB" => Ok(Self::LDBB),
-            _ if s.to_ascii_uppercase().starts_with("TIMER") => {
-                let inner = s.trim_start_matches("TIMER(").trim_end_matches(')');
-                inner
-                    .parse::<u32>()
-                    .map(Self::TIMER)
-                    .map_err(|_| "Invalid TIMER value".into())
-            }
-            _ => Err("Invalid stop condition".into()),
-        }
-    }
-}
-
 pub fn run_headless(
     mut cpu: LR35902CPU,
     video_channel_rc: Receiver<Vbuf>,
-    sc: Option<StopCondition>,
+    mut sc: Option<StopCondition>,
     test_out_dir: Option<PathBuf>,
-) {
+    screenshot_at: Vec<u64>,
+) -> StopOutcome {
     let mut last_frame: Vbuf = [Pixel::default(); RESX * RESY];
     let start = Instant::now();
 
-    init_metrics(false);
-
     loop {
-        if let Some(condition) = sc {
-            match condition {
-                StopCondition::LDBB => {
-                    let instr = cpu.current_instruction;
-                    if instr.name == "LD"
-                        && matches!(instr.reg1, Some(CPURegisterId::B))
-                        && matches!(instr.reg2, Some(CPURegisterId::B))
-                    {
-                        dump_regs(&cpu, &test_out_dir);
-                        return vbuf_snapshot(last_frame, &test_out_dir);
-                    }
-                }
-                StopCondition::TIMER(secs) => {
-                    if start.elapsed() > Duration::from_secs(secs as u64) {
-                        dump_regs(&cpu, &test_out_dir);
-                        return vbuf_snapshot(last_frame, &test_out_dir);
-                    }
-                }
+        if let Some(outcome) = sc.as_mut().and_then(|c| c.check(&cpu, start)) {
+            info!("Stop condition met: {:?}", outcome);
+            dump_regs(&cpu, &test_out_dir);
+            vbuf_snapshot(last_frame, &test_out_dir);
+            if cpu.bus.cartridge.is_dirty() {
+                cpu.bus.cartridge.save();
             }
+            return outcome;
         }
 
+        let frames = cpu.bus.io.ppu.frames;
         cpu.step();
 
+        if cpu.bus.io.ppu.frames != frames {
+            cpu.bus.cartridge.autosave();
+            if screenshot_at.contains(&cpu.bus.io.ppu.frames) {
+                frame_screenshot(cpu.bus.io.ppu.vbuf(), cpu.bus.io.ppu.frames, &test_out_dir);
+            }
+        }
+
         if let Ok(frame) = video_channel_rc.try_recv() {
             last_frame = frame;
         }
@@ -96,7 +68,11 @@ fn run(
             io_listener.handle_events(cpu, &mut playback);
 
             debugger.executing_pc = cpu.pc();
+            let frames = cpu.bus.io.ppu.frames;
             cpu.step();
+            if cpu.bus.io.ppu.frames != frames {
+                cpu.bus.cartridge.autosave();
+            }
         }
 
         debugger.collect(cpu);
@@ -170,7 +146,7 @@ pub fn run_emu_thread(
     let crash_info = Arc::new(Mutex::new(None));
 
     let mut cpu = LR35902CPU::new(bus, serial, cpu_speed);
-    let mut dbg = Debugger::new(debug, dbg_cmd_rc, dbg_data_sd);
+    let mut dbg = Debugger::new(&mut cpu, debug, dbg_cmd_rc, dbg_data_sd);
 
     let thread = std::thread::spawn(move || {
         panic::set_hook(Box::new({
@@ -222,6 +198,9 @@ pub fn run_emu_thread(
                         });
 
                 crash_info.addr = dbg.executing_pc;
+                // The SRAM is most likely fine, whatever broke. Saved first, the
+                // debugger may not be listening anymore
+                _ = panic::catch_unwind(panic::AssertUnwindSafe(|| cpu.bus.cartridge.save()));
                 dbg.died(&cpu, crash_info.clone());
 
                 Err(crash_info)
@@ -0,0 +1,209 @@
+use std::fmt;
+
+use super::cpu::LR35902CPU;
+
+const MAGIC: &[u8; 4] = b"XGBS";
+// Bumped whenever the saved fields change
+const VERSION: u16 = 3;
+
+#[derive(Debug)]
+pub enum StateError {
+    // Not a save state, or one from another version
+    Invalid,
+    // Made with another ROM
+    WrongRom,
+    Truncated,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Invalid => write!(f, "Invalid save state"),
+            StateError::WrongRom => write!(f, "The save state was made with another ROM"),
+            StateError::Truncated => write!(f, "Truncated save state"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+// Binary snapshot of the emulation state. Fields are written in declaration
+// order and read back into an already built instance, so channels, recorders
+// and debug settings are left untouched
+pub trait SaveState {
+    fn save_state(&self, out: &mut Vec<u8>);
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError>;
+}
+
+// Snapshot of the whole emulator, to be loaded with the same ROM
+pub fn save(cpu: &LR35902CPU) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    VERSION.save_state(&mut out);
+    cpu.save_state(&mut out);
+    out
+}
+
+// The state is left untouched if the data is not valid
+pub fn load(cpu: &mut LR35902CPU, mut data: &[u8]) -> Result<(), StateError> {
+    let magic: [u8; 4] = take(&mut data)?;
+    let mut version = 0u16;
+    version.load_state(&mut data)?;
+    if &magic != MAGIC || version != VERSION {
+        return Err(StateError::Invalid);
+    }
+
+    // A partial load would corrupt the emulation, it is rolled back on error
+    let backup = save(cpu);
+    let mut result = cpu.load_state(&mut data);
+    if result.is_ok() && !data.is_empty() {
+        result = Err(StateError::Invalid);
+    }
+
+    if result.is_err() {
+        cpu.load_state(&mut &backup[MAGIC.len() + 2..])
+            .expect("Could not restore the emulation state");
+    }
+    result
+}
+
+fn take<const N: usize>(data: &mut &[u8]) -> Result<[u8; N], StateError> {
+    let (bytes, rest) = data.split_first_chunk::<N>().ok_or(StateError::Truncated)?;
+    *data = rest;
+    Ok(*bytes)
+}
+
+// Implements SaveState for a struct, from the list of its fields to save
+#[macro_export]
+macro_rules! save_state {
+    ( $type:ty { $( $field:ident ),* $(,)? } ) => {
+        impl $crate::core::save_state::SaveState for $type {
+            fn save_state(&self, out: &mut Vec<u8>) {
+                $( $crate::core::save_state::SaveState::save_state(&self.$field, out); )*
+            }
+
+            fn load_state(
+                &mut self,
+                data: &mut &[u8],
+            ) -> Result<(), $crate::core::save_state::StateError> {
+                $( $crate::core::save_state::SaveState::load_state(&mut self.$field, data)?; )*
+                Ok(())
+            }
+        }
+    };
+}
+
+macro_rules! save_state_number {
+    ( $( $type:ty ),* ) => {
+        $(
+            impl SaveState for $type {
+                fn save_state(&self, out: &mut Vec<u8>) {
+                    out.extend(self.to_le_bytes());
+                }
+
+                fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+                    *self = <$type>::from_le_bytes(take(data)?);
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+save_state_number!(u8, u16, u32, u64, i64, f32, f64);
+
+impl SaveState for usize {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        (*self as u64).save_state(out);
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        *self = u64::from_le_bytes(take(data)?) as usize;
+        Ok(())
+    }
+}
+
+impl SaveState for bool {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        *self = take::<1>(data)?[0] != 0;
+        Ok(())
+    }
+}
+
+impl<T: SaveState, const N: usize> SaveState for [T; N] {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        self.iter().for_each(|v| v.save_state(out));
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        self.iter_mut().try_for_each(|v| v.load_state(data))
+    }
+}
+
+// Fixed size, such as the cartridge RAM banks
+impl<T: SaveState> SaveState for [T] {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        self.iter().for_each(|v| v.save_state(out));
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        self.iter_mut().try_for_each(|v| v.load_state(data))
+    }
+}
+
+impl<T: SaveState + Default> SaveState for Vec<T> {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        self.len().save_state(out);
+        self.iter().for_each(|v| v.save_state(out));
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        let mut len = 0usize;
+        len.load_state(data)?;
+        // Every element takes at least a byte
+        if len > data.len() {
+            return Err(StateError::Truncated);
+        }
+
+        self.resize_with(len, T::default);
+        self.iter_mut().try_for_each(|v| v.load_state(data))
+    }
+}
+
+impl<T: SaveState + Default> SaveState for Option<T> {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        self.is_some().save_state(out);
+        if let Some(v) = self {
+            v.save_state(out);
+        }
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        let mut some = false;
+        some.load_state(data)?;
+
+        match some {
+            true => self.get_or_insert_with(T::default).load_state(data),
+            false => {
+                *self = None;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<A: SaveState, B: SaveState> SaveState for (A, B) {
+    fn save_state(&self, out: &mut Vec<u8>) {
+        self.0.save_state(out);
+        self.1.save_state(out);
+    }
+
+    fn load_state(&mut self, data: &mut &[u8]) -> Result<(), StateError> {
+        self.0.load_state(data)?;
+        self.1.load_state(data)
+    }
+}
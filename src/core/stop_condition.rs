@@ -0,0 +1,211 @@
+use super::cpu::instructions::CPURegisterId;
+use super::cpu::LR35902CPU;
+
+use std::time::{Duration, Instant};
+
+// Mooneye test ROMs signature, loaded in B, C, D, E, H, L before LD B, B
+const FIB_PASS: [u8; 6] = [3, 5, 8, 13, 21, 34];
+const FIB_FAIL: [u8; 6] = [0x42; 6];
+
+const SERIAL_PASS: &[u8] = b"Passed";
+const SERIAL_FAIL: &[u8] = b"Failed";
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StopOutcome {
+    // Ordered by priority, when combined with AND
+    DONE,
+    PASS,
+    TIMEOUT,
+    FAIL,
+}
+
+impl StopOutcome {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            StopOutcome::DONE | StopOutcome::PASS => 0,
+            StopOutcome::FAIL => 1,
+            StopOutcome::TIMEOUT => 2,
+        }
+    }
+}
+
+// Headless stop conditions, parsed from strings such as
+// "LDBB AND FIB OR FRAMES(600)". AND binds tighter than OR, & and | can be
+// used as well
+#[derive(Clone, Debug)]
+pub enum StopCondition {
+    // LD B, B executed, the mooneye and mattcurrie tests breakpoint
+    LDBB,
+    // Wall-clock seconds
+    TIMER(u32),
+    FRAMES(u64),
+    // M-cycles
+    CYCLES(u64),
+    PC(u16),
+    // Address, value
+    MEM(u16, u8),
+    // Serial output containing Passed or Failed, scanned incrementally
+    SERIAL {
+        scanned: usize,
+        outcome: Option<StopOutcome>,
+    },
+    // Mooneye registers signature, checked at LD B, B
+    FIB,
+    // Conditions stay met once they were, along with their outcome
+    AND(Vec<(StopCondition, Option<StopOutcome>)>),
+    OR(Vec<StopCondition>),
+}
+
+fn parse_number<T: TryFrom<u64>>(s: &str) -> Result<T, String> {
+    let value = match s.strip_prefix("0X") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse::<u64>(),
+    }
+    .map_err(|_| format!("Invalid number {s}"))?;
+
+    T::try_from(value).map_err(|_| format!("Number out of range {s}"))
+}
+
+// Returns the inner argument of NAME(arg)
+fn argument<'a>(s: &'a str, name: &str) -> Option<&'a str> {
+    s.strip_prefix(name)?.strip_prefix('(')?.strip_suffix(')')
+}
+
+impl StopCondition {
+    fn parse_atom(s: &str) -> Result<Self, String> {
+        match s {
+            "LDBB" => return Ok(Self::LDBB),
+            "FIB" => return Ok(Self::FIB),
+            "SERIAL" => {
+                return Ok(Self::SERIAL {
+                    scanned: 0,
+                    outcome: None,
+                })
+            }
+            _ => (),
+        }
+
+        if let Some(arg) = argument(s, "TIMER") {
+            return parse_number(arg).map(Self::TIMER);
+        }
+        if let Some(arg) = argument(s, "FRAMES") {
+            return parse_number(arg).map(Self::FRAMES);
+        }
+        if let Some(arg) = argument(s, "CYCLES") {
+            return parse_number(arg).map(Self::CYCLES);
+        }
+        if let Some(arg) = argument(s, "PC") {
+            return parse_number(arg).map(Self::PC);
+        }
+        if let Some(arg) = argument(s, "MEM") {
+            let (addr, value) = arg.split_once('=').ok_or("Expected MEM(addr=value)")?;
+            return Ok(Self::MEM(parse_number(addr)?, parse_number(value)?));
+        }
+
+        Err(format!("Invalid stop condition {s}"))
+    }
+
+    fn is_ldbb(cpu: &LR35902CPU) -> bool {
+        let instr = cpu.current_instruction;
+        instr.name == "LD"
+            && matches!(instr.reg1, Some(CPURegisterId::B))
+            && matches!(instr.reg2, Some(CPURegisterId::B))
+    }
+
+    // Returns the outcome once the condition is met
+    pub fn check(&mut self, cpu: &LR35902CPU, start: Instant) -> Option<StopOutcome> {
+        match self {
+            Self::LDBB => Self::is_ldbb(cpu).then_some(StopOutcome::DONE),
+            Self::TIMER(secs) => (start.elapsed() > Duration::from_secs(*secs as u64))
+                .then_some(StopOutcome::TIMEOUT),
+            Self::FRAMES(frames) => {
+                (cpu.bus.io.ppu.frames >= *frames).then_some(StopOutcome::TIMEOUT)
+            }
+            Self::CYCLES(cycles) => (cpu.total_cycles >= *cycles).then_some(StopOutcome::TIMEOUT),
+            Self::PC(pc) => (cpu.pc() == *pc).then_some(StopOutcome::DONE),
+            Self::MEM(addr, value) => (cpu.bus.read(*addr) == *value).then_some(StopOutcome::DONE),
+            Self::SERIAL { scanned, outcome } => {
+                let output = cpu.bus.io.serial.output();
+                // The serial output was trimmed, start over
+                if output.len() < *scanned {
+                    *scanned = 0;
+                }
+                if outcome.is_none() && output.len() > *scanned {
+                    // Rescan the tail, in case a word was cut
+                    let from = scanned.saturating_sub(SERIAL_PASS.len());
+                    for window in output[from..].windows(SERIAL_PASS.len()) {
+                        if window == SERIAL_PASS {
+                            *outcome = Some(StopOutcome::PASS);
+                        } else if window == SERIAL_FAIL {
+                            *outcome = Some(StopOutcome::FAIL);
+                        }
+                    }
+                    *scanned = output.len();
+                }
+                *outcome
+            }
+            Self::FIB => {
+                if !Self::is_ldbb(cpu) {
+                    return None;
+                }
+
+                let r = &cpu.registers;
+                match [r.b, r.c, r.d, r.e, r.h, r.l] {
+                    FIB_PASS => Some(StopOutcome::PASS),
+                    FIB_FAIL => Some(StopOutcome::FAIL),
+                    _ => None,
+                }
+            }
+            Self::AND(conditions) => {
+                let mut result = Some(StopOutcome::DONE);
+                for (condition, met) in conditions.iter_mut() {
+                    if met.is_none() {
+                        *met = condition.check(cpu, start);
+                    }
+                    result = match (result, *met) {
+                        (Some(a), Some(b)) => Some(a.max(b)),
+                        _ => None,
+                    };
+                }
+                result
+            }
+            Self::OR(conditions) => {
+                let mut result = None;
+                for condition in conditions.iter_mut() {
+                    result = result.or(condition.check(cpu, start));
+                }
+                result
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for StopCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s
+            .to_ascii_uppercase()
+            .replace('|', " OR ")
+            .replace('&', " AND ");
+
+        let mut or = Vec::new();
+        for term in s.split(" OR ") {
+            let mut and = term
+                .split(" AND ")
+                .map(|atom| Self::parse_atom(atom.trim()))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            or.push(match and.len() {
+                1 => and.remove(0),
+                _ => Self::AND(and.into_iter().map(|c| (c, None)).collect()),
+            });
+        }
+
+        Ok(match or.len() {
+            1 => or.remove(0),
+            _ => Self::OR(or),
+        })
+    }
+}
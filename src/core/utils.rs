@@ -1,4 +1,5 @@
 use crate::core::cpu::LR35902CPU;
+use crate::core::io::video::capture::save_png;
 use crate::core::io::video::ppu::Vbuf;
 use crate::core::io::video::ppu::{RESX, RESY};
 use std::env::temp_dir;
@@ -45,6 +46,19 @@ pub fn dump_regs(cpu: &LR35902CPU, test_out_dir: &Option<PathBuf>) {
     .unwrap();
 }
 
+pub fn frame_screenshot(frame: &Vbuf, frame_number: u64, test_out_dir: &Option<PathBuf>) {
+    let fpath = match test_out_dir {
+        None => temp_dir().join(format!("frame_{frame_number}.png")),
+        Some(test_out_dir) => {
+            create_dir_all(test_out_dir).expect("Could not create test dir");
+            test_out_dir.join(format!("frame_{frame_number}.png"))
+        }
+    };
+
+    save_png(frame, &fpath).expect("Could not save screenshot");
+    info!("Frame {} saved to {}", frame_number, fpath.display());
+}
+
 pub fn vbuf_snapshot(frame: Vbuf, test_out_dir: &Option<PathBuf>) {
     // Output the current video buffer to a PPM formatted file
 
@@ -1,10 +1,13 @@
-use super::{cpu_metrics, ppu_metrics, Debugger};
+use super::Debugger;
 use crate::core::{
     cpu::{instructions::CPURegisterId, LR35902CPU},
     io::video::ppu::PPU_LAYER,
+    mem::cheats::Cheat,
 };
 
+use clap::{error::ErrorKind, Error};
 use log::info;
+use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct DynAddr {
@@ -32,6 +35,30 @@ impl DynAddr {
     }
 }
 
+impl FromStr for DynAddr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(reg) = s.strip_prefix("$") {
+            let reg =
+                CPURegisterId::from_str(reg).map_err(|_| Error::new(ErrorKind::ValueValidation))?;
+            return Ok(Self::new(None, Some(reg)));
+        }
+
+        if let Some(s) = s.strip_prefix("0x") {
+            return Ok(Self::new(
+                Some(u16::from_str_radix(s, 16).map_err(|_| Error::new(ErrorKind::InvalidValue))?),
+                None,
+            ));
+        }
+
+        Ok(Self::new(
+            Some(str::parse(s).map_err(|_| Error::new(ErrorKind::InvalidValue))?),
+            None,
+        ))
+    }
+}
+
 #[allow(nonstandard_style)]
 #[derive(Debug)]
 pub enum DebuggerCommand {
@@ -50,6 +77,14 @@ pub enum DebuggerCommand {
     //PPU
     PPU_HIDE_LAYER(PPU_LAYER),
 
+    // Cheats
+    CHEAT_ADD(Cheat),
+    CHEAT_ENABLED((usize, bool)),
+    CHEAT_REMOVE(usize),
+
+    // RAM search, memory snapshots are only taken while enabled
+    MEM_SNAPSHOTS(bool),
+
     // REPL
     STEP,
     CONTINUE,
@@ -66,11 +101,11 @@ impl Debugger {
         }
     }
 
-    pub fn set_enabled(&mut self, enabled: bool) {
+    pub fn set_enabled(&mut self, cpu: &mut LR35902CPU, enabled: bool) {
         info!("Debugger is now enabled:{enabled}");
         self.enabled = enabled;
 
-        cpu_metrics().set_enabled(enabled);
-        ppu_metrics().set_enabled(enabled);
+        cpu.metrics.set_enabled(enabled);
+        cpu.bus.io.ppu.metrics.set_enabled(enabled);
     }
 }
@@ -1,42 +1,13 @@
-use super::metrics::{CpuMetrics, MetricsHandler, PpuMetrics};
 use super::state::EmuSnapshot;
 use super::{
     commands::DebuggerCommand,
+    search::MemState,
     state::{ApuState, CpuState, PpuState},
 };
 use crate::core::cpu::cpu::LR35902CPU;
 use crate::core::run_emu::EmuCrash;
 use crossbeam_channel::{Receiver, Sender};
 
-use std::time::Duration;
-
-static mut CPU_METRICS: Option<MetricsHandler<CpuMetrics>> = None;
-static mut PPU_METRICS: Option<MetricsHandler<PpuMetrics>> = None;
-
-pub fn init_metrics(enabled: bool) {
-    unsafe {
-        CPU_METRICS = Some(MetricsHandler::new(enabled, Duration::from_millis(1000)));
-        PPU_METRICS = Some(MetricsHandler::new(enabled, Duration::from_millis(1000)));
-    }
-}
-
-#[inline(always)]
-#[allow(static_mut_refs)]
-pub fn cpu_metrics() -> &'static mut MetricsHandler<CpuMetrics> {
-    unsafe { CPU_METRICS.as_mut().unwrap() }
-}
-
-#[inline(always)]
-#[allow(static_mut_refs)]
-pub fn ppu_metrics() -> &'static mut MetricsHandler<PpuMetrics> {
-    unsafe { PPU_METRICS.as_mut().unwrap() }
-}
-
-// thread_local! {
-//     pub static CPU_METRICS: RefCell<MetricsHandler<CpuMetrics>> = RefCell::new(MetricsHandler::<CpuMetrics>::new(Duration::from_millis(1000)));
-//     pub static PPU_METRICS: RefCell<MetricsHandler<PpuMetrics>> = RefCell::new(MetricsHandler::<PpuMetrics>::new(Duration::from_millis(1000)));
-// }
-
 pub struct Debugger {
     pub enabled: bool,
 
@@ -46,6 +17,7 @@ pub struct Debugger {
     resume: bool,
 
     pub executing_pc: u16,
+    mem_snapshots: bool,
 
     ui_commands_rc: Receiver<DebuggerCommand>,
     dbg_data_sd: Sender<EmuSnapshot>,
@@ -53,11 +25,13 @@ pub struct Debugger {
 
 impl Debugger {
     pub fn new(
+        cpu: &mut LR35902CPU,
         enabled: bool,
         ui_commands_rc: Receiver<DebuggerCommand>,
         dbg_data_sd: Sender<EmuSnapshot>,
     ) -> Self {
-        init_metrics(enabled);
+        cpu.metrics.set_enabled(enabled);
+        cpu.bus.io.ppu.metrics.set_enabled(enabled);
 
         Self {
             enabled,
@@ -66,6 +40,7 @@ impl Debugger {
             resume: false,
             breakpoints: vec![],
             executing_pc: 0,
+            mem_snapshots: false,
             ui_commands_rc,
             dbg_data_sd,
         }
@@ -74,9 +49,15 @@ impl Debugger {
     pub fn handle_events(&mut self, cpu: &mut LR35902CPU) {
         if let Ok(event) = self.ui_commands_rc.try_recv() {
             match event {
-                DebuggerCommand::ENABLED(enabled) => self.set_enabled(enabled),
+                DebuggerCommand::ENABLED(enabled) => self.set_enabled(cpu, enabled),
                 DebuggerCommand::CPU_CLOCK(clock_speed) => cpu.clock.set_speed(clock_speed),
                 DebuggerCommand::PPU_HIDE_LAYER(layer) => cpu.bus.io.ppu.hide_layer(layer),
+                DebuggerCommand::CHEAT_ADD(cheat) => cpu.bus.cartridge.cheats.add(cheat),
+                DebuggerCommand::CHEAT_ENABLED((idx, enabled)) => {
+                    cpu.bus.cartridge.cheats.set_enabled(idx, enabled)
+                }
+                DebuggerCommand::CHEAT_REMOVE(idx) => cpu.bus.cartridge.cheats.remove(idx),
+                DebuggerCommand::MEM_SNAPSHOTS(enabled) => self.mem_snapshots = enabled,
                 DebuggerCommand::APU_VOLUME(vol) => cpu.bus.io.apu.dbg_volume(vol),
                 DebuggerCommand::APU_VOLUME_LEFT(vol) => cpu.bus.io.apu.dbg_volume_left(vol),
                 DebuggerCommand::APU_VOLUME_RIGHT(vol) => cpu.bus.io.apu.dbg_volume_right(vol),
@@ -105,13 +86,13 @@ impl Debugger {
         }
     }
 
-    pub fn collect(&self, cpu: &LR35902CPU) {
+    pub fn collect(&self, cpu: &mut LR35902CPU) {
         if !self.enabled {
             return;
         }
 
-        cpu_metrics().update();
-        ppu_metrics().update();
+        cpu.metrics.update();
+        cpu.bus.io.ppu.metrics.update();
 
         if self.dbg_data_sd.is_full() {
             return;
@@ -121,6 +102,10 @@ impl Debugger {
             ppu: PpuState::new(&cpu.bus.io.ppu),
             cpu: CpuState::new(cpu, self.executing_pc),
             apu: ApuState::new(&cpu.bus.io.apu),
+            mem: match self.mem_snapshots {
+                true => MemState::new(&mut cpu.bus),
+                false => MemState::default(),
+            },
             breakpoints: self.breakpoints.clone(),
             ..Default::default()
         };
@@ -169,6 +154,7 @@ impl Debugger {
                 apu: ApuState::new(&cpu.bus.io.apu),
                 breakpoints: self.breakpoints.clone(),
                 crash: Some(emu_crash),
+                ..Default::default()
             })
             .expect("Failed to send emulation state");
     }
@@ -41,6 +41,13 @@ pub struct MetricsHandler<T> {
     metrics: T,
 }
 
+// Disabled until the debugger is enabled
+impl<T: Metrics + Default + Copy> Default for MetricsHandler<T> {
+    fn default() -> Self {
+        Self::new(false, Duration::from_millis(1000))
+    }
+}
+
 impl<T: Metrics + Default + Copy> MetricsHandler<T> {
     pub fn new(enabled: bool, update_interval: Duration) -> Self {
         Self {
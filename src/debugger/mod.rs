@@ -2,10 +2,15 @@ mod commands;
 mod debugger;
 mod disas;
 mod metrics;
+mod search;
 mod state;
 
 pub use commands::{DebuggerCommand, DynAddr};
-pub use debugger::{cpu_metrics, init_metrics, ppu_metrics, Debugger};
+pub use debugger::Debugger;
 pub use disas::GbAsm;
-pub use metrics::{CpuMetricFields, CpuMetrics, MetricType, MetricsExport, PpuMetricFields};
+pub use metrics::{
+    CpuMetricFields, CpuMetrics, MetricType, MetricsExport, MetricsHandler, PpuMetricFields,
+    PpuMetrics,
+};
+pub use search::{MemState, RamSearch, SearchFilter, SearchSize};
 pub use state::{ApuState, EmuSnapshot, InterruptState};
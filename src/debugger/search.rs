@@ -0,0 +1,176 @@
+use crate::core::mem::bus::Bus;
+
+// WRAM banks, HRAM and the battery backed SRAM, laid end to end
+#[derive(Default, Clone)]
+pub struct MemState {
+    data: Vec<u8>,
+    wram_len: usize,
+    hram_len: usize,
+}
+
+impl MemState {
+    pub fn new(bus: &mut Bus) -> Self {
+        // Only the first two banks exist on DMG
+        let wram_banks = match bus.cartridge.is_cgb() {
+            true => 8,
+            false => 2,
+        };
+        let wram = bus.ram().wram()[..wram_banks].as_flattened();
+        let hram = bus.ram().hram();
+        let (wram_len, hram_len) = (wram.len(), hram.len());
+
+        let mut data = [wram, hram].concat();
+        data.extend_from_slice(bus.cartridge.mbc.sram().unwrap_or_default());
+
+        Self {
+            data,
+            wram_len,
+            hram_len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn location(&self, idx: usize) -> MemLocation {
+        let hram = self.wram_len;
+        let sram = hram + self.hram_len;
+
+        match idx {
+            0..0x1000 => MemLocation::new(None, 0xc000 + idx as u16),
+            _ if idx < hram => MemLocation::new(Some(idx / 0x1000), 0xd000 + (idx % 0x1000) as u16),
+            _ if idx < sram => MemLocation::new(None, 0xff80 + (idx - hram) as u16),
+            _ => MemLocation::new(
+                Some((idx - sram) / 0x2000),
+                0xa000 + ((idx - sram) % 0x2000) as u16,
+            ),
+        }
+    }
+
+    // Whether the byte after idx comes next in the address space, so that
+    // words never span two banks or regions
+    fn is_contiguous(&self, idx: usize) -> bool {
+        if idx + 1 >= self.len() {
+            return false;
+        }
+        let (loc, next) = (self.location(idx), self.location(idx + 1));
+        next.addr == loc.addr.wrapping_add(1) && (next.bank == loc.bank || loc.addr == 0xcfff)
+    }
+
+    // Little endian for 16 bits values, like the CPU stores them
+    pub fn value(&self, idx: usize, size: SearchSize, signed: bool) -> i32 {
+        let byte = |i: usize| self.data.get(i).copied().unwrap_or_default();
+        match (size, signed) {
+            (SearchSize::BYTE, false) => byte(idx) as i32,
+            (SearchSize::BYTE, true) => byte(idx) as i8 as i32,
+            (SearchSize::WORD, false) => u16::from_le_bytes([byte(idx), byte(idx + 1)]) as i32,
+            (SearchSize::WORD, true) => i16::from_le_bytes([byte(idx), byte(idx + 1)]) as i32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemLocation {
+    // Switchable WRAM or SRAM bank
+    pub bank: Option<usize>,
+    pub addr: u16,
+}
+
+impl MemLocation {
+    fn new(bank: Option<usize>, addr: u16) -> Self {
+        Self { bank, addr }
+    }
+
+    // GameShark code writing value at this location, in its bank
+    pub fn gameshark(&self, value: u8) -> String {
+        let code_type = match (self.bank, self.addr) {
+            (Some(bank), 0xd000..=0xdfff) => 0x90 | bank as u8,
+            (Some(bank), 0xa000..=0xbfff) => 0x80 | bank as u8,
+            _ => 0x01,
+        };
+        let [lo, hi] = self.addr.to_le_bytes();
+        format!("{code_type:02X}{value:02X}{lo:02X}{hi:02X}")
+    }
+}
+
+impl std::fmt::Display for MemLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.bank {
+            Some(bank) => write!(f, "{bank:X}:{:04X}", self.addr),
+            None => write!(f, "{:04X}", self.addr),
+        }
+    }
+}
+
+#[allow(nonstandard_style)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SearchSize {
+    #[default]
+    BYTE,
+    WORD,
+}
+
+#[allow(nonstandard_style)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFilter {
+    EQUAL,
+    CHANGED,
+    INCREASED,
+    DECREASED,
+    VALUE(i32),
+}
+
+// Narrows down candidate addresses, comparing each snapshot with the last one
+pub struct RamSearch {
+    pub size: SearchSize,
+    pub signed: bool,
+    last: MemState,
+    results: Vec<usize>,
+}
+
+impl RamSearch {
+    pub fn new(mem: MemState, size: SearchSize, signed: bool) -> Self {
+        let results = (0..mem.len()).collect();
+        Self {
+            size,
+            signed,
+            last: mem,
+            results,
+        }
+    }
+
+    pub fn filter(&mut self, mem: MemState, filter: SearchFilter) {
+        let (size, signed) = (self.size, self.signed);
+        let last = &self.last;
+
+        self.results.retain(|&idx| {
+            if size == SearchSize::WORD && !mem.is_contiguous(idx) {
+                return false;
+            }
+
+            let (before, now) = (last.value(idx, size, signed), mem.value(idx, size, signed));
+            match filter {
+                SearchFilter::EQUAL => now == before,
+                SearchFilter::CHANGED => now != before,
+                SearchFilter::INCREASED => now > before,
+                SearchFilter::DECREASED => now < before,
+                SearchFilter::VALUE(value) => now == value,
+            }
+        });
+        self.last = mem;
+    }
+
+    pub fn results(&self) -> &[usize] {
+        &self.results
+    }
+
+    // Memory the results were last filtered against
+    pub fn last(&self) -> &MemState {
+        &self.last
+    }
+}
@@ -2,9 +2,8 @@ use std::time::{Duration, Instant};
 
 use super::disas::{disas, GbAsm};
 use super::metrics::{CpuMetrics, MetricsExport, PpuMetrics};
-use super::{cpu_metrics, ppu_metrics};
+use super::search::MemState;
 use crate::core::cpu::cpu::{CPURegisters, LR35902CPU};
-use crate::core::cpu::interrupts::{INTERRUPT_ENABLE, INTERRUPT_FLAGS};
 use crate::core::cpu::CPUSpeed;
 use crate::core::io::audio::apu::APU;
 use crate::core::io::video::ppu::PPU;
@@ -16,6 +15,7 @@ pub struct EmuSnapshot {
     pub cpu: CpuState,
     pub breakpoints: Vec<u16>,
     pub apu: ApuState,
+    pub mem: MemState,
     pub crash: Option<EmuCrash>,
 }
 
@@ -51,10 +51,10 @@ impl CpuState {
             halt: cpu.halt,
             interrupts: InterruptState {
                 int_master: cpu.int_master,
-                int_enable: INTERRUPT_ENABLE.get(),
-                interrupts: INTERRUPT_FLAGS.get(),
+                int_enable: cpu.bus.interrupts.enable,
+                interrupts: cpu.bus.interrupts.flags,
             },
-            metrics: cpu_metrics().export(),
+            metrics: cpu.metrics.export(),
             disas: disas(cpu, last_pc, 30),
             clock: ClockState {
                 mode: cpu.clock.speed_mode,
@@ -161,7 +161,7 @@ impl PpuState {
     pub fn new(ppu: &PPU) -> Self {
         Self {
             vram: ppu.vram,
-            metrics: ppu_metrics().export(),
+            metrics: ppu.metrics.export(),
         }
     }
 }
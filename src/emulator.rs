@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use crossbeam_channel::{bounded, unbounded, Receiver};
+
+use crate::core::cpu::{CPUSpeed, LR35902CPU};
+use crate::core::io::video::ppu::{Vbuf, TICKS_PER_FRAME};
+use crate::core::mem::{
+    boot::BootRom, bus::Bus, cartridge::Cartridge, cheats::Cheats, rtc::RtcClock,
+};
+use crate::core::save_state::{self, StateError};
+
+// Emulator driven from the caller's thread, for frontends, bots and test
+// runners embedding xenogb. It runs unthrottled, pacing is left to the caller.
+// Instances share no state, any number of them can run side by side
+pub struct Emulator {
+    cpu: LR35902CPU,
+    audio: Receiver<[f32; 2]>,
+}
+
+impl Emulator {
+    pub fn load_rom(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let cartridge = Cartridge::load(path.as_ref().to_path_buf())?;
+
+        // Frames are read from the PPU, nothing is ever sent on a zero sized channel
+        let (video_sd, _) = bounded(0);
+        let (audio_sd, audio) = unbounded();
+
+        let bus = Bus::new(cartridge, BootRom::NONE, video_sd, audio_sd);
+        Ok(Self {
+            cpu: LR35902CPU::new(bus, false, CPUSpeed::CUSTOM),
+            audio,
+        })
+    }
+
+    // Power cycle, the cartridge is kept with its RAM, clock and cheats
+    pub fn reset(&mut self) {
+        let (video_sd, _) = bounded(0);
+        let (audio_sd, audio) = unbounded();
+        let sample_rate = self.cpu.bus.io.apu.sample_rate();
+
+        self.cpu.bus.reset(BootRom::NONE, video_sd, audio_sd);
+        self.cpu.bus.io.apu.set_sample_rate(sample_rate);
+        self.cpu.reset(CPUSpeed::CUSTOM);
+        self.audio = audio;
+    }
+
+    // Runs a single instruction
+    pub fn step(&mut self) {
+        self.cpu.step();
+    }
+
+    // Runs until the PPU completes a frame, or for as long as a frame lasts
+    // when the LCD is off
+    pub fn run_frame(&mut self) {
+        let frames = self.cpu.bus.io.ppu.frames;
+        let cycles = match self.cpu.clock.speed_mode {
+            CPUSpeed::DOUBLE => TICKS_PER_FRAME / 2,
+            _ => TICKS_PER_FRAME / 4,
+        };
+        let end = self.cpu.total_cycles + cycles as u64;
+
+        while self.cpu.bus.io.ppu.frames == frames && self.cpu.total_cycles < end {
+            self.cpu.step();
+        }
+    }
+
+    // Last frame, complete right after run_frame
+    pub fn frame_buffer(&self) -> &Vbuf {
+        self.cpu.bus.io.ppu.vbuf()
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.cpu.bus.io.ppu.frames
+    }
+
+    // Stereo samples produced since the last call, they pile up until drained
+    pub fn drain_audio(&mut self) -> Vec<[f32; 2]> {
+        self.audio.try_iter().collect()
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.cpu.bus.io.apu.set_sample_rate(sample_rate);
+    }
+
+    // Buttons held down, as a JOYPAD_INPUT mask
+    pub fn set_buttons(&mut self, buttons: u8) {
+        self.cpu.bus.io.joypad.release(!buttons);
+        self.cpu
+            .bus
+            .io
+            .joypad
+            .press(buttons, &mut self.cpu.bus.interrupts);
+    }
+
+    // Battery backed cartridge RAM, None when the cartridge has no battery
+    pub fn sram(&mut self) -> Option<&mut [u8]> {
+        self.cpu.bus.cartridge.sram()
+    }
+
+    // MBC3 clocks count the real time unless driven by the emulation, to be
+    // set before running
+    pub fn set_rtc_clock(&mut self, clock: RtcClock) {
+        self.cpu.bus.cartridge.set_rtc_clock(clock);
+    }
+
+    // Empty at load, cheat files are left to the caller
+    pub fn cheats(&mut self) -> &mut Cheats {
+        &mut self.cpu.bus.cartridge.cheats
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        save_state::save(&self.cpu)
+    }
+
+    // The emulation is left untouched on error
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        save_state::load(&mut self.cpu, state)
+    }
+
+    pub fn cpu(&self) -> &LR35902CPU {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut LR35902CPU {
+        &mut self.cpu
+    }
+}
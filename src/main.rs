@@ -1,37 +1,63 @@
 #![allow(clippy::upper_case_acronyms)]
 #![allow(clippy::module_inception)]
 
-mod audio;
-mod core;
-mod dbg;
-mod debugger;
 mod ui;
 
+use xenogb::{audio, core, debugger};
+
+use audio::cpal_sink::output_sample_rate;
+use audio::run_audio::{buffer_size, run_audio_thread, AudioBackend, AudioConfig};
 use core::cpu::{CPUSpeed, LR35902CPU};
+use core::io::audio::apu::DEFAULT_SAMPLE_RATE;
+use core::io::audio::rate_control::RateControl;
+use core::io::video::capture::VideoFormat;
+use core::mem::archive;
 use core::mem::boot::BootRom;
 use core::mem::bus::Bus;
 use core::mem::cartridge::Cartridge;
-use core::run_emu::{run_headless, StopCondition};
+use core::mem::cheats::Cheats;
+use core::mem::header::CartridgeHeader;
+use core::mem::rtc::RtcClock;
+use core::run_emu::run_headless;
+use core::stop_condition::StopCondition;
 use ui::run_ui;
 
 use chrono::Local;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use crossbeam_channel::{bounded, unbounded};
+use log::warn;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 struct XenoGBError;
 
 #[derive(Parser, Debug)]
-#[command(version)]
+#[command(version, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(short, long)]
-    cartridge: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(short, long, required = true)]
+    cartridge: Option<PathBuf>,
+
+    /// IPS, UPS or BPS patches applied in order, instead of the ones next to the ROM
+    #[arg(long)]
+    patch: Vec<PathBuf>,
+
+    /// Directory of the .sav and .cht files, instead of next to the ROM
+    #[arg(long, default_value = None)]
+    save_dir: Option<PathBuf>,
+
+    /// Drive MBC3 clocks with the real time, or with the emulated cycles for deterministic runs
+    #[arg(long, value_enum, default_value_t = RtcClock::WALL)]
+    rtc_clock: RtcClock,
 
     #[arg(long, default_value_t = false)]
     headless: bool,
 
+    /// Stop the emulation on specific conditions, e.g. "LDBB AND FIB OR FRAMES(600)".
+    /// Exits with 0 on success, 1 on failure and 2 on timeout
     #[arg(long, default_value = None)]
     stop_condition: Option<StopCondition>,
 
@@ -44,7 +70,7 @@ struct Args {
     #[arg(short, long, default_value_t = false)]
     debug: bool,
 
-    #[arg(short, long, value_enum, default_value_t = CPUSpeed::NORMAL)]
+    #[arg(long, value_enum, default_value_t = CPUSpeed::NORMAL)]
     cpu_speed: CPUSpeed,
 
     #[arg(long, default_value_t = false)]
@@ -58,6 +84,55 @@ struct Args {
 
     #[arg(long, default_value = None)]
     test_out_dir: Option<PathBuf>,
+
+    /// Audio output, defaults to cpal, or null when headless
+    #[arg(long, value_enum, env = "XENOGB_AUDIO_SINK", default_value = None)]
+    audio_sink: Option<AudioBackend>,
+
+    /// Output of the file audio sink
+    #[arg(long, env = "XENOGB_AUDIO_FILE", default_value = None)]
+    audio_file: Option<PathBuf>,
+
+    /// Audio output sample rate, defaults to the output device's
+    #[arg(long, default_value = None)]
+    sample_rate: Option<u32>,
+
+    /// Pace the emulation with the audio output instead of the clock
+    #[arg(long, default_value_t = false)]
+    audio_sync: bool,
+
+    /// Record the audio output to a WAV file
+    #[arg(long, default_value = None)]
+    record_audio: Option<PathBuf>,
+
+    /// Also record each channel to its own WAV file, next to the output
+    #[arg(long, default_value_t = false)]
+    audio_stems: bool,
+
+    /// Record every frame to a GIF, or to a directory of PNGs
+    #[arg(long, default_value = None)]
+    record_video: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = VideoFormat::GIF)]
+    video_format: VideoFormat,
+
+    /// Also record the audio to a WAV file next to the video
+    #[arg(long, default_value_t = false)]
+    video_audio: bool,
+
+    /// Save the frames with these numbers to PNGs, when headless. The first frame drawn is 1
+    #[arg(long, value_delimiter = ',', value_parser = clap::value_parser!(u64).range(1..))]
+    screenshot_at: Vec<u64>,
+
+    /// Directory of the screenshots taken with F12, instead of next to the ROM
+    #[arg(long, default_value = None)]
+    screenshot_dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the cartridge header of a ROM and validate it
+    Info { rom: PathBuf },
 }
 
 fn setup_logger() -> String {
@@ -73,40 +148,157 @@ fn setup_logger() -> String {
     file_path
 }
 
+// Fails on ROMs xenogb cannot run, or that do not pass the validation
+fn info(rom: &Path) -> Result<(), XenoGBError> {
+    let contents = match archive::read_rom(rom) {
+        Ok((_, contents)) => contents,
+        Err(e) => {
+            eprintln!("Unable to read {}: {e}", rom.display());
+            return Err(XenoGBError);
+        }
+    };
+    if contents.len() < 0x150 {
+        eprintln!("Missing cartridge header");
+        return Err(XenoGBError);
+    }
+
+    let header = CartridgeHeader::new(&contents);
+    print!("{header}");
+    match header.is_supported() && header.checks().iter().all(|(_, ok, _)| *ok) {
+        true => Ok(()),
+        false => Err(XenoGBError),
+    }
+}
+
 fn main() -> Result<(), XenoGBError> {
     setup_logger();
 
     let args = Args::parse();
+    if let Some(Command::Info { rom }) = &args.command {
+        return info(rom);
+    }
+    let rom_path = args.cartridge.expect("A cartridge is required");
+
+    let mut backend = args.audio_sink.unwrap_or(match args.headless {
+        true => AudioBackend::NULL,
+        false => AudioBackend::CPAL,
+    });
+
+    let mut sample_rate = args.sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE);
+    if backend == AudioBackend::CPAL {
+        match output_sample_rate() {
+            Some(rate) => sample_rate = args.sample_rate.unwrap_or(rate),
+            None => {
+                warn!("No audio output device, audio is disabled");
+                backend = AudioBackend::NULL;
+            }
+        }
+    }
+
+    let mut audio_sync = args.audio_sync && backend == AudioBackend::CPAL;
+    if args.audio_sync && !audio_sync {
+        warn!("Audio sync needs an audio output device, ignoring it");
+    }
 
-    let (audio_channel_sd, audio_channel_rc) = unbounded();
+    // When synced to the audio, the emulation blocks on a full samples channel
+    let (audio_channel_sd, audio_channel_rc) = match audio_sync {
+        true => bounded(buffer_size(sample_rate)),
+        false => unbounded(),
+    };
     let (video_channel_sd, video_channel_rc) = bounded(1);
-    let bus = Bus::new(
-        Cartridge::new(args.cartridge),
-        args.boot_rom,
-        video_channel_sd,
-        audio_channel_sd,
-    );
+    let cartridge = match args.patch.is_empty() {
+        true => Cartridge::new(rom_path),
+        false => {
+            Cartridge::load_patched(rom_path, &args.patch).expect("Unable to patch the cartridge")
+        }
+    };
+    let mut bus = Bus::new(cartridge, args.boot_rom, video_channel_sd, audio_channel_sd);
+
+    bus.cartridge.cheats = Cheats::load(Cheats::path(
+        bus.cartridge.rom_path(),
+        args.save_dir.as_deref(),
+    ));
+    if let Some(save_dir) = &args.save_dir {
+        bus.cartridge.set_save_dir(save_dir);
+    }
+    if args.rtc_clock != RtcClock::WALL {
+        bus.cartridge.set_rtc_clock(args.rtc_clock);
+    }
+    bus.io.apu.set_sample_rate(sample_rate);
+    if let Some(path) = &args.record_audio {
+        bus.io
+            .apu
+            .start_recording(path, args.audio_stems)
+            .expect("Could not start audio recording");
+    }
+    if let Some(path) = &args.record_video {
+        bus.io
+            .start_video_recording(path, args.video_format, args.video_audio)
+            .expect("Could not start video recording");
+    }
+
+    let rate_control = RateControl::new();
+    bus.io.apu.set_rate_control(rate_control.clone());
+
+    let audio_thread = match backend {
+        // Closing the channel makes the APU drop its samples right away
+        AudioBackend::NULL => {
+            drop(audio_channel_rc);
+            None
+        }
+        _ => {
+            let (thread, opened) = run_audio_thread(
+                audio_channel_rc,
+                AudioConfig {
+                    backend,
+                    sample_rate,
+                    audio_sync,
+                    file: args.audio_file,
+                },
+                rate_control,
+            );
+
+            // Without the audio output pacing it, the emulation keeps its own speed
+            if audio_sync && opened != AudioBackend::CPAL {
+                warn!("Audio sync needs an audio output device, ignoring it");
+                audio_sync = false;
+            }
+            Some(thread)
+        }
+    };
+
+    // Custom as initializer means no throttle
+    let cpu_speed = match audio_sync {
+        true => CPUSpeed::CUSTOM,
+        false => args.cpu_speed,
+    };
 
-    #[allow(clippy::unit_arg)]
     if args.headless {
-        return Ok(run_headless(
-            LR35902CPU::new(bus, args.serial, args.cpu_speed),
+        let outcome = run_headless(
+            LR35902CPU::new(bus, args.serial, cpu_speed),
             video_channel_rc,
             args.stop_condition,
             args.test_out_dir,
-        ));
+            args.screenshot_at,
+        );
+
+        // Let the audio sink flush the remaining samples
+        if let Some(thread) = audio_thread {
+            _ = thread.join();
+        }
+        std::process::exit(outcome.exit_code());
     }
 
     #[allow(clippy::unit_arg)]
     Ok(run_ui(
         bus,
         video_channel_rc,
-        audio_channel_rc,
         args.debug,
         args.serial,
-        args.cpu_speed,
+        cpu_speed,
         args.record,
         args.record_path,
         args.replay_path,
+        args.screenshot_dir,
     ))
 }
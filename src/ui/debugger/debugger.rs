@@ -1,4 +1,4 @@
-use super::views::{apu::ApuUi, cpu::CpuUi, ppu::PpuUi, repl::ReplUi};
+use super::views::{apu::ApuUi, cpu::CpuUi, ppu::PpuUi, repl::ReplUi, search::SearchUi};
 use crate::debugger::{DebuggerCommand, EmuSnapshot};
 use crossbeam_channel::{Receiver, Sender};
 use eframe::egui;
@@ -9,6 +9,7 @@ pub enum Tabs {
     Cpu,
     Vram,
     Apu,
+    Search,
 }
 
 pub struct DebuggerUi {
@@ -20,6 +21,7 @@ pub struct DebuggerUi {
     pub cpu: CpuUi,
     pub repl: ReplUi,
     pub apu: ApuUi,
+    pub search: SearchUi,
 }
 
 impl DebuggerUi {
@@ -29,11 +31,12 @@ impl DebuggerUi {
         dbg_commands_sd: Sender<DebuggerCommand>,
         dbg_data_rc: Receiver<EmuSnapshot>,
     ) -> Self {
-        let tabs = vec![Tabs::Apu, Tabs::ReplUi, Tabs::Vram, Tabs::Cpu];
+        let tabs = vec![Tabs::Apu, Tabs::ReplUi, Tabs::Vram, Tabs::Cpu, Tabs::Search];
         let ppu = PpuUi::new(ctx, dbg_data_rc.clone(), dbg_commands_sd.clone());
         let cpu = CpuUi::new(dbg_data_rc.clone(), dbg_commands_sd.clone());
         let repl = ReplUi::new(dbg_data_rc.clone(), dbg_commands_sd.clone());
         let apu = ApuUi::new(dbg_data_rc.clone(), dbg_commands_sd.clone());
+        let search = SearchUi::new(dbg_data_rc.clone(), dbg_commands_sd.clone());
 
         Self {
             enabled,
@@ -42,6 +45,7 @@ impl DebuggerUi {
             cpu,
             repl,
             apu,
+            search,
         }
     }
 }
@@ -51,6 +55,7 @@ pub struct Behavior<'a> {
     pub cpu: &'a mut CpuUi,
     pub repl: &'a mut ReplUi,
     pub apu: &'a mut ApuUi,
+    pub search: &'a mut SearchUi,
 }
 
 #[allow(clippy::needless_lifetimes)]
@@ -61,6 +66,7 @@ impl<'a> egui_tiles::Behavior<Tabs> for Behavior<'a> {
             Tabs::Vram => "VRAM".into(),
             Tabs::Cpu => "CPU".into(),
             Tabs::Apu => "APU".into(),
+            Tabs::Search => "RAM Search".into(),
         }
     }
 
@@ -75,6 +81,7 @@ impl<'a> egui_tiles::Behavior<Tabs> for Behavior<'a> {
             Tabs::Vram => self.ppu.ui(ui),
             Tabs::Cpu => self.cpu.ui(ui),
             Tabs::Apu => self.apu.ui(ui),
+            Tabs::Search => self.search.ui(ui),
         }
 
         Default::default()
@@ -1,57 +1,8 @@
-use std::str::FromStr;
-
 use clap::{error::ErrorKind, Error, Parser};
 use crossbeam_channel::Sender;
 
-use crate::core::cpu::instructions::CPURegisterId;
 use crate::debugger::{DebuggerCommand, DynAddr};
 
-impl FromStr for CPURegisterId {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_uppercase().as_str() {
-            "A" => Ok(Self::A),
-            "F" => Ok(Self::F),
-            "B" => Ok(Self::B),
-            "C" => Ok(Self::C),
-            "D" => Ok(Self::D),
-            "E" => Ok(Self::E),
-            "H" => Ok(Self::H),
-            "L" => Ok(Self::L),
-            "AF" => Ok(Self::AF),
-            "BC" => Ok(Self::BC),
-            "DE" => Ok(Self::DE),
-            "HL" => Ok(Self::HL),
-            "SP" => Ok(Self::SP),
-            "PC" => Ok(Self::PC),
-            _ => Err(Error::new(ErrorKind::ValueValidation)),
-        }
-    }
-}
-
-impl FromStr for DynAddr {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some(reg) = s.strip_prefix("$") {
-            return Ok(Self::new(None, Some(CPURegisterId::from_str(reg)?)));
-        }
-
-        if let Some(s) = s.strip_prefix("0x") {
-            return Ok(Self::new(
-                Some(u16::from_str_radix(s, 16).map_err(|_| Error::new(ErrorKind::InvalidValue))?),
-                None,
-            ));
-        }
-
-        Ok(Self::new(
-            Some(str::parse(s).map_err(|_| Error::new(ErrorKind::InvalidValue))?),
-            None,
-        ))
-    }
-}
-
 struct ReplHistory {
     history: Vec<String>,
     cursor: usize,
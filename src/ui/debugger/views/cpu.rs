@@ -11,7 +11,7 @@ use crate::core::cpu::{
 use crate::debugger::{
     CpuMetrics, DebuggerCommand, EmuSnapshot, InterruptState, MetricType, MetricsExport,
 };
-use crate::flag_set;
+use xenogb::flag_set;
 
 const HISTORY_SIZE: usize = 60;
 
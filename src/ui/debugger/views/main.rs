@@ -17,6 +17,7 @@ impl DebuggerUi {
                         ppu: &mut self.ppu,
                         cpu: &mut self.cpu,
                         apu: &mut self.apu,
+                        search: &mut self.search,
                     };
                     self.tree.ui(&mut behavior, ui);
                 });
@@ -3,3 +3,4 @@ pub mod cpu;
 pub mod main;
 pub mod ppu;
 pub mod repl;
+pub mod search;
@@ -0,0 +1,247 @@
+use crossbeam_channel::{Receiver, Sender};
+use eframe::egui::{Button, ComboBox, DragValue, Ui};
+use egui_extras::{Column, TableBuilder};
+
+use super::super::utils::Cache;
+use crate::core::mem::cheats::Cheat;
+use crate::debugger::{
+    DebuggerCommand, EmuSnapshot, MemState, RamSearch, SearchFilter, SearchSize,
+};
+use log::error;
+
+const ROW_HEIGHT: f32 = 18.0;
+
+#[derive(Clone, Copy)]
+struct Watch {
+    idx: usize,
+    size: SearchSize,
+    signed: bool,
+}
+
+pub struct SearchUi {
+    search: Option<RamSearch>,
+    // Waiting for a memory snapshot to start a search
+    new_search: bool,
+    // Whether the emulation is sending memory snapshots
+    snapshots: bool,
+    size: SearchSize,
+    signed: bool,
+    value: i32,
+    watches: Vec<Watch>,
+    // Picked up by the cheat settings
+    cheats: Vec<Cheat>,
+
+    dbg_data_rc: Cache,
+    dbg_commands_sd: Sender<DebuggerCommand>,
+}
+
+impl SearchUi {
+    pub fn new(
+        dbg_data_rc: Receiver<EmuSnapshot>,
+        dbg_commands_sd: Sender<DebuggerCommand>,
+    ) -> Self {
+        Self {
+            search: None,
+            new_search: false,
+            snapshots: false,
+            size: SearchSize::default(),
+            signed: false,
+            value: 0,
+            watches: vec![],
+            cheats: vec![],
+            dbg_data_rc: Cache::new(dbg_data_rc),
+            dbg_commands_sd,
+        }
+    }
+
+    pub fn take_cheats(&mut self) -> Vec<Cheat> {
+        std::mem::take(&mut self.cheats)
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui) {
+        let mem = self.dbg_data_rc.get().mem;
+        if self.new_search && !mem.is_empty() {
+            self.new_search = false;
+            self.search = Some(RamSearch::new(mem.clone(), self.size, self.signed));
+        }
+
+        self.filters_ui(ui, &mem);
+        ui.separator();
+        self.results_ui(ui, &mem);
+        ui.separator();
+        self.watches_ui(ui, &mem);
+
+        // Snapshotting the memory every frame is only worth it while searching
+        let snapshots = self.new_search || self.search.is_some() || !self.watches.is_empty();
+        if snapshots != self.snapshots {
+            self.snapshots = snapshots;
+            _ = self
+                .dbg_commands_sd
+                .send(DebuggerCommand::MEM_SNAPSHOTS(snapshots));
+        }
+    }
+
+    fn filters_ui(&mut self, ui: &mut Ui, mem: &MemState) {
+        ui.horizontal(|ui| {
+            ComboBox::from_label("Size")
+                .selected_text(match self.size {
+                    SearchSize::BYTE => "8 bits",
+                    SearchSize::WORD => "16 bits",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.size, SearchSize::BYTE, "8 bits");
+                    ui.selectable_value(&mut self.size, SearchSize::WORD, "16 bits");
+                });
+            ui.checkbox(&mut self.signed, "Signed");
+
+            if let Some(search) = &mut self.search {
+                search.size = self.size;
+                search.signed = self.signed;
+            }
+
+            if ui
+                .add_enabled(!self.new_search, Button::new("New search"))
+                .clicked()
+            {
+                self.new_search = true;
+            }
+        });
+
+        let Some(search) = &mut self.search else {
+            ui.label("Start a new search to snapshot WRAM, HRAM and SRAM");
+            return;
+        };
+
+        ui.add_enabled_ui(!mem.is_empty(), |ui| {
+            ui.horizontal(|ui| {
+                let filters = [
+                    ("Equal", SearchFilter::EQUAL),
+                    ("Changed", SearchFilter::CHANGED),
+                    ("Increased", SearchFilter::INCREASED),
+                    ("Decreased", SearchFilter::DECREASED),
+                ];
+                for (label, filter) in filters {
+                    if ui.button(label).clicked() {
+                        search.filter(mem.clone(), filter);
+                    }
+                }
+
+                ui.separator();
+                ui.add(DragValue::new(&mut self.value));
+                if ui.button("Value").clicked() {
+                    search.filter(mem.clone(), SearchFilter::VALUE(self.value));
+                }
+            });
+        });
+    }
+
+    fn results_ui(&mut self, ui: &mut Ui, mem: &MemState) {
+        let Some(search) = &self.search else { return };
+        ui.label(format!("{} results", search.results().len()));
+
+        let mut watched = None;
+        let mut cheat = None;
+        TableBuilder::new(ui)
+            .id_salt("ram-search-results")
+            .striped(true)
+            .max_scroll_height(250.0)
+            .column(Column::exact(80.0))
+            .column(Column::exact(80.0))
+            .column(Column::exact(80.0))
+            .column(Column::remainder())
+            .header(ROW_HEIGHT, |mut header| {
+                header.col(|ui| _ = ui.strong("Address"));
+                header.col(|ui| _ = ui.strong("Previous"));
+                header.col(|ui| _ = ui.strong("Current"));
+                header.col(|_| ());
+            })
+            .body(|body| {
+                body.rows(ROW_HEIGHT, search.results().len(), |mut row| {
+                    let idx = search.results()[row.index()];
+                    row.col(|ui| _ = ui.monospace(mem.location(idx).to_string()));
+                    row.col(|ui| {
+                        _ = ui.monospace(
+                            search
+                                .last()
+                                .value(idx, search.size, search.signed)
+                                .to_string(),
+                        )
+                    });
+                    row.col(|ui| {
+                        _ = ui.monospace(mem.value(idx, search.size, search.signed).to_string())
+                    });
+                    row.col(|ui| {
+                        if ui.small_button("Watch").clicked() {
+                            watched = Some(Watch {
+                                idx,
+                                size: search.size,
+                                signed: search.signed,
+                            });
+                        }
+                        if ui.small_button("Cheat").clicked() {
+                            cheat = Some(Watch {
+                                idx,
+                                size: search.size,
+                                signed: search.signed,
+                            });
+                        }
+                    });
+                });
+            });
+
+        if let Some(watch) = watched {
+            self.watches.push(watch);
+        }
+        if let Some(watch) = cheat {
+            self.add_cheat(mem, watch);
+        }
+    }
+
+    fn watches_ui(&mut self, ui: &mut Ui, mem: &MemState) {
+        ui.label("Watch list");
+
+        let mut removed = None;
+        let mut cheat = None;
+        for (i, watch) in self.watches.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.monospace(format!(
+                    "{}: {}",
+                    mem.location(watch.idx),
+                    mem.value(watch.idx, watch.size, watch.signed)
+                ));
+                if ui.small_button("Cheat").clicked() {
+                    cheat = Some(*watch);
+                }
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+
+        if let Some(watch) = cheat {
+            self.add_cheat(mem, watch);
+        }
+        if let Some(i) = removed {
+            self.watches.remove(i);
+        }
+    }
+
+    // Freezes the watched value with GameShark codes, one per byte
+    fn add_cheat(&mut self, mem: &MemState, watch: Watch) {
+        let value = mem.value(watch.idx, watch.size, watch.signed).to_le_bytes();
+        let code = match watch.size {
+            SearchSize::BYTE => mem.location(watch.idx).gameshark(value[0]),
+            SearchSize::WORD => format!(
+                "{}+{}",
+                mem.location(watch.idx).gameshark(value[0]),
+                mem.location(watch.idx + 1).gameshark(value[1])
+            ),
+        };
+
+        let name = format!("RAM {}", mem.location(watch.idx));
+        match Cheat::new(&name, &code, true) {
+            Ok(cheat) => self.cheats.push(cheat),
+            Err(e) => error!("{e}"),
+        }
+    }
+}
@@ -1,12 +1,11 @@
 use super::ui::{XenoGBUI, WINDOW_SIZE};
-use crate::audio::run_audio::run_audio_thread;
 use crate::core::io::video::ppu::Vbuf;
 use crate::core::mem::bus::Bus;
 use crate::core::run_emu::run_emu_thread;
 
+use crate::core::cpu::CPUSpeed;
 use crossbeam_channel::Receiver;
 use eframe::egui::ViewportBuilder;
-use crate::core::cpu::CPUSpeed;
 
 use std::path::PathBuf;
 
@@ -14,14 +13,17 @@ use std::path::PathBuf;
 pub fn run_ui(
     bus: Bus,
     video_channel_rc: Receiver<Vbuf>,
-    audio_channel_rc: Receiver<[f32; 2]>,
     debug: bool,
     serial: bool,
     cpu_speed: CPUSpeed,
     record_enabled: bool,
     record_path: Option<PathBuf>,
     replay_path: Option<PathBuf>,
+    screenshot_dir: Option<PathBuf>,
 ) {
+    let cheats = bus.cartridge.cheats.list().to_vec();
+    let header = bus.cartridge.header().clone();
+    let rom_path = bus.cartridge.rom_path().to_path_buf();
     let _ = eframe::run_native(
         "xenogb",
         eframe::NativeOptions {
@@ -31,8 +33,6 @@ pub fn run_ui(
             ..Default::default()
         },
         Box::new(move |ctx| {
-            run_audio_thread(audio_channel_rc);
-
             let (emu_state, channels) = run_emu_thread(
                 bus,
                 debug,
@@ -51,6 +51,10 @@ pub fn run_ui(
                 channels.1,
                 channels.2,
                 debug,
+                cheats,
+                header,
+                rom_path,
+                screenshot_dir,
             )))
         }),
     );
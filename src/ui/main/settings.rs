@@ -3,13 +3,18 @@ use std::fmt::Display;
 use crossbeam_channel::Sender;
 use eframe::egui::{
     widgets::color_picker::{color_picker_color32, Alpha},
-    Color32, ComboBox, Key, Order, Slider, TopBottomPanel, Ui, Window,
+    Checkbox, Color32, ComboBox, Grid, Key, Order, Slider, TextEdit, TopBottomPanel, Ui, Window,
 };
 use egui_extras::{Column, TableBuilder};
 use indexmap::IndexMap;
 use itertools::Itertools;
 
-use crate::core::{io::joypad::JOYPAD_INPUT, io_event::IOEvent};
+use crate::core::{
+    io::{joypad::JOYPAD_INPUT, video::capture::VideoFormat},
+    io_event::IOEvent,
+    mem::{cheats::Cheat, header::CartridgeHeader},
+};
+use crate::debugger::DebuggerCommand;
 
 #[allow(nonstandard_style)]
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
@@ -80,6 +85,8 @@ impl GraphicsSettings {
 struct SoundSettings {
     volume: f32,
     mute: bool,
+    recording: bool,
+    stems: bool,
 }
 
 impl Default for SoundSettings {
@@ -87,6 +94,8 @@ impl Default for SoundSettings {
         Self {
             volume: 0.5,
             mute: false,
+            recording: false,
+            stems: false,
         }
     }
 }
@@ -108,6 +117,51 @@ impl SoundSettings {
                 .send(IOEvent::SOUND_MUTE(self.mute))
                 .expect("Could not send io event");
         }
+
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut self.recording, "Record Audio").changed() {
+                let event = match self.recording {
+                    true => IOEvent::AUDIO_RECORD_START(self.stems),
+                    false => IOEvent::AUDIO_RECORD_STOP,
+                };
+                sender.send(event).expect("Could not send io event");
+            }
+            ui.add_enabled(
+                !self.recording,
+                Checkbox::new(&mut self.stems, "Channel stems"),
+            );
+        });
+    }
+}
+
+#[derive(Default)]
+struct CaptureSettings {
+    recording: bool,
+    format: VideoFormat,
+    audio: bool,
+}
+
+impl CaptureSettings {
+    pub fn ui(&mut self, ui: &mut Ui, sender: &Sender<IOEvent>) {
+        ui.horizontal(|ui| {
+            if ui.checkbox(&mut self.recording, "Record Video").changed() {
+                let event = match self.recording {
+                    true => IOEvent::VIDEO_RECORD_START(self.format, self.audio),
+                    false => IOEvent::VIDEO_RECORD_STOP,
+                };
+                sender.send(event).expect("Could not send io event");
+            }
+
+            ui.add_enabled_ui(!self.recording, |ui| {
+                ComboBox::from_label("Video format")
+                    .selected_text(format!("{:?}", self.format))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.format, VideoFormat::GIF, "GIF");
+                        ui.selectable_value(&mut self.format, VideoFormat::PNG, "PNG sequence");
+                    });
+                ui.checkbox(&mut self.audio, "With audio");
+            });
+        });
     }
 }
 
@@ -203,27 +257,178 @@ impl KeymapSettings {
     }
 }
 
+// Mirrors the cheats of the emulator, changes are sent as debugger commands
+#[derive(Default)]
+struct CheatSettings {
+    cheats: Vec<Cheat>,
+    window_open: bool,
+    name: String,
+    code: String,
+    error: Option<String>,
+}
+
+impl CheatSettings {
+    fn add(&mut self, cheat: Cheat, sender: &Sender<DebuggerCommand>) {
+        sender
+            .send(DebuggerCommand::CHEAT_ADD(cheat.clone()))
+            .expect("Could not send debugger command");
+        self.cheats.push(cheat);
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, sender: &Sender<DebuggerCommand>) {
+        if ui.button("Cheats").clicked() {
+            self.window_open = !self.window_open;
+        }
+
+        let mut added = None;
+        Window::new("Cheats")
+            .open(&mut self.window_open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ui.ctx(), |ui| {
+                let mut removed = None;
+                for (i, cheat) in self.cheats.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.checkbox(&mut cheat.enabled, &cheat.name).changed() {
+                            sender
+                                .send(DebuggerCommand::CHEAT_ENABLED((i, cheat.enabled)))
+                                .expect("Could not send debugger command");
+                        }
+                        ui.monospace(&cheat.code);
+                        if ui.small_button("Remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+
+                if let Some(i) = removed {
+                    self.cheats.remove(i);
+                    sender
+                        .send(DebuggerCommand::CHEAT_REMOVE(i))
+                        .expect("Could not send debugger command");
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        TextEdit::singleline(&mut self.name)
+                            .hint_text("Name")
+                            .desired_width(120.0),
+                    );
+                    ui.add(
+                        TextEdit::singleline(&mut self.code)
+                            .hint_text("ABC-DEF-GHI or ABCDEFGH")
+                            .desired_width(160.0),
+                    );
+                    if ui.button("Add").clicked() {
+                        match Cheat::new(&self.name, &self.code, true) {
+                            Ok(cheat) => {
+                                added = Some(cheat);
+                                self.name.clear();
+                                self.code.clear();
+                                self.error = None;
+                            }
+                            Err(e) => self.error = Some(e),
+                        }
+                    }
+                });
+
+                if let Some(e) = &self.error {
+                    ui.colored_label(Color32::RED, e);
+                }
+            });
+
+        if let Some(cheat) = added {
+            self.add(cheat, sender);
+        }
+    }
+}
+
+struct CartridgeInfo {
+    header: CartridgeHeader,
+    window_open: bool,
+}
+
+impl CartridgeInfo {
+    pub fn ui(&mut self, ui: &mut Ui) {
+        if ui.button("Cartridge info").clicked() {
+            self.window_open = !self.window_open;
+        }
+
+        Window::new(format!("Cartridge info - {}", self.header.title()))
+            .id("cartridge-info".into())
+            .open(&mut self.window_open)
+            .resizable(false)
+            .show(ui.ctx(), |ui| {
+                Grid::new("cartridge-header")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (name, value) in self.header.rows() {
+                            ui.strong(name);
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                        for (name, ok, value) in self.header.checks() {
+                            ui.strong(name);
+                            match ok {
+                                true => ui.colored_label(Color32::GREEN, value),
+                                false => ui.colored_label(Color32::RED, value),
+                            };
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
+
 pub struct Settings {
     sound: SoundSettings,
+    capture: CaptureSettings,
     pub graphics: GraphicsSettings,
+    // Apply the graphics mode to screenshots
+    pub filtered_screenshots: bool,
     pub keymap: KeymapSettings,
+    cheats: CheatSettings,
+    cartridge: CartridgeInfo,
 
     io_event_sd: Sender<IOEvent>,
+    dbg_commands_sd: Sender<DebuggerCommand>,
 }
 
 impl Settings {
-    pub fn new(sender: Sender<IOEvent>) -> Self {
+    pub fn new(
+        sender: Sender<IOEvent>,
+        dbg_commands_sd: Sender<DebuggerCommand>,
+        cheats: Vec<Cheat>,
+        header: CartridgeHeader,
+    ) -> Self {
         let sound_settings = SoundSettings::default();
         // Synchronize emulation's volume with settings volume
         _ = sender.send(IOEvent::SOUND_VOLUME(sound_settings.volume));
         Settings {
             sound: sound_settings,
+            capture: CaptureSettings::default(),
             graphics: GraphicsSettings::default(),
+            filtered_screenshots: false,
             keymap: KeymapSettings::default(),
+            cheats: CheatSettings {
+                cheats,
+                ..Default::default()
+            },
+            cartridge: CartridgeInfo {
+                header,
+                window_open: false,
+            },
             io_event_sd: sender,
+            dbg_commands_sd,
         }
     }
 
+    pub fn add_cheat(&mut self, cheat: Cheat) {
+        self.cheats.add(cheat, &self.dbg_commands_sd);
+    }
+
     fn _from_file(_sender: Sender<IOEvent>) -> Result<Self, ()> {
         todo!()
     }
@@ -233,8 +438,17 @@ impl Settings {
             ui.label("Settings");
 
             self.sound.ui(ui, &self.io_event_sd);
+            self.capture.ui(ui, &self.io_event_sd);
             self.graphics.ui(ui);
-            self.keymap.ui(ui);
+            ui.checkbox(
+                &mut self.filtered_screenshots,
+                "Apply graphics mode to screenshots (F12)",
+            );
+            ui.horizontal(|ui| {
+                self.keymap.ui(ui);
+                self.cheats.ui(ui, &self.dbg_commands_sd);
+                self.cartridge.ui(ui);
+            });
         });
     }
 }
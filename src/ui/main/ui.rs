@@ -1,5 +1,9 @@
+use crate::core::io::video::capture::{save_png, save_rgb_png};
+use crate::core::io::video::lcd::Pixel;
 use crate::core::io::video::ppu::{Vbuf, RESX, RESY};
 use crate::core::io_event::IOEvent;
+use crate::core::mem::cheats::Cheat;
+use crate::core::mem::header::CartridgeHeader;
 use crate::core::run_emu::EmuState;
 use crate::debugger::{DebuggerCommand, EmuSnapshot};
 use crate::ui::{
@@ -15,13 +19,20 @@ use egui::{
 };
 use egui_extras::install_image_loaders;
 
+use chrono::Local;
+use log::{error, info};
+use std::path::PathBuf;
+
 const DEBUGGER_KEY: Key = Key::D;
+const SCREENSHOT_KEY: Key = Key::F12;
 
 const SCALE: usize = 4;
 
 pub const WINDOW_SIZE: [f32; 2] = [(RESX * SCALE) as f32, (RESY * SCALE) as f32];
 
 pub struct XenoGBUI {
+    // Last frame received, before any graphics setting is applied
+    vbuf: Vbuf,
     screen_buffer: [u8; RESX * RESY * 3],
     screen_texture: egui::TextureHandle,
 
@@ -33,12 +44,16 @@ pub struct XenoGBUI {
 
     settings: Settings,
 
+    rom_path: PathBuf,
+    screenshot_dir: Option<PathBuf>,
+
     frame: u64,
 
     pub emu_state: EmuState,
 }
 
 impl XenoGBUI {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         ctx: &eframe::CreationContext<'_>,
         emu_state: EmuState,
@@ -47,6 +62,10 @@ impl XenoGBUI {
         dbg_commands_sd: Sender<DebuggerCommand>,
         dbg_data_rc: Receiver<EmuSnapshot>,
         debug: bool,
+        cheats: Vec<Cheat>,
+        header: CartridgeHeader,
+        rom_path: PathBuf,
+        screenshot_dir: Option<PathBuf>,
     ) -> Self {
         let screen_buffer = [0xff; RESX * RESY * 3];
         let screen_texture = ctx.egui_ctx.load_texture(
@@ -60,13 +79,16 @@ impl XenoGBUI {
         install_image_loaders(&ctx.egui_ctx);
 
         Self {
+            vbuf: [Pixel::default(); RESX * RESY],
             screen_buffer,
             screen_texture,
             debugger,
             video_channel_rc,
             events_sd: events_sd.clone(),
-            dbg_commands_sd,
-            settings: Settings::new(events_sd),
+            dbg_commands_sd: dbg_commands_sd.clone(),
+            settings: Settings::new(events_sd, dbg_commands_sd, cheats, header),
+            rom_path,
+            screenshot_dir,
             emu_state,
             frame: 0,
         }
@@ -74,6 +96,7 @@ impl XenoGBUI {
 
     fn render_vbuf(&mut self, ctx: &Context) {
         if let Ok(vbuf) = self.video_channel_rc.try_recv() {
+            self.vbuf = vbuf;
             for (i, pixel) in vbuf.iter().enumerate() {
                 self.screen_buffer[i * 3] = pixel.r;
                 self.screen_buffer[i * 3 + 1] = pixel.g;
@@ -97,6 +120,33 @@ impl XenoGBUI {
         }
     }
 
+    // Named after the ROM, next to it unless a screenshot directory is given
+    fn screenshot(&self) {
+        let stem = self.rom_path.file_stem().unwrap_or_default();
+        let name = format!(
+            "{}_{}.png",
+            stem.to_string_lossy(),
+            Local::now().format("%Y-%m-%d_%H-%M-%S%.3f")
+        );
+        let (path, dir) = match &self.screenshot_dir {
+            Some(dir) => (dir.join(name), Some(dir)),
+            None => (self.rom_path.with_file_name(name), None),
+        };
+
+        let result = dir.map_or(Ok(()), std::fs::create_dir_all).and_then(|_| {
+            if self.settings.filtered_screenshots {
+                save_rgb_png(self.screen_buffer.to_vec(), &path)
+            } else {
+                save_png(&self.vbuf, &path)
+            }
+        });
+
+        match result {
+            Ok(_) => info!("Screenshot saved to {path:?}"),
+            Err(e) => error!("Could not save screenshot: {e}"),
+        }
+    }
+
     fn apply_tint(&mut self, tint: Color32, to_white: bool) {
         if to_white {
             for pixel in self.screen_buffer.chunks_exact_mut(3) {
@@ -129,6 +179,9 @@ impl eframe::App for XenoGBUI {
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
         if self.debugger.enabled {
             self.debugger.ui(ctx);
+            for cheat in self.debugger.search.take_cheats() {
+                self.settings.add_cheat(cheat);
+            }
         }
 
         if self.emu_state.is_dead() {
@@ -161,6 +214,10 @@ impl eframe::App for XenoGBUI {
                     .send(DebuggerCommand::ENABLED(self.debugger.enabled))
                     .expect("Could not send dbg command");
             }
+
+            if inp.key_pressed(SCREENSHOT_KEY) {
+                self.screenshot();
+            }
         });
 
         self.render_vbuf(ctx);
@@ -0,0 +1,80 @@
+mod common;
+
+use xenogb::core::cpu::LR35902CPU;
+
+const NR50: u16 = 0xff24;
+const NR51: u16 = 0xff25;
+const NR52: u16 = 0xff26;
+const PULSE2_LEFT: u8 = 0x20;
+const PULSE2_RIGHT: u8 = 0x02;
+const STEPS: usize = 200_000;
+
+// Plays a tone on the pulse 2 channel panned following NR51, returns whether
+// the left and right outputs carry any sound
+fn tone(name: &str, panning: u8) -> [bool; 2] {
+    let code = [
+        0x3e, 0x80, 0xe0, 0x26, // NR52: APU on
+        0x3e, panning, 0xe0, 0x25, // NR51
+        0x3e, 0x77, 0xe0, 0x24, // NR50: max volume
+        0x3e, 0x80, 0xe0, 0x16, // NR21: 50% duty
+        0x3e, 0xf0, 0xe0, 0x17, // NR22: max volume, no envelope
+        0x3e, 0x00, 0xe0, 0x18, // NR23
+        0x3e, 0x87, 0xe0, 0x19, // NR24: trigger
+        0x18, 0xfe, // JR -2
+    ];
+    let dir = common::temp_dir(&format!("apu_{name}"));
+    let (mut cpu, samples) =
+        common::setup_cpu_with_audio(common::write_rom(&dir, &common::build_rom(&code)));
+    for _ in 0..STEPS {
+        cpu.step();
+    }
+
+    let samples: Vec<[f32; 2]> = samples.try_iter().collect();
+    assert!(!samples.is_empty());
+    [0, 1].map(|side| samples.iter().any(|s| s[side].abs() > 1e-3))
+}
+
+fn setup_cpu(name: &str) -> LR35902CPU {
+    let dir = common::temp_dir(&format!("apu_{name}"));
+    common::setup_cpu(common::write_rom(&dir, &common::build_rom(&common::LOOP)))
+}
+
+#[test]
+fn registers() {
+    let mut cpu = setup_cpu("registers");
+    let bus = &mut cpu.bus;
+
+    // Ignored while the APU is off
+    bus.write(NR50, 0x77);
+    assert_eq!(bus.read(NR50), 0x00);
+    assert_eq!(bus.read(NR52), 0x70);
+
+    // Every NR50 and NR51 bit reads back, VIN ones included
+    bus.write(NR52, 0x80);
+    for value in [0xff, 0x00, 0x88, 0x5a, 0xa5] {
+        bus.write(NR50, value);
+        bus.write(NR51, value);
+        assert_eq!(bus.read(NR50), value);
+        assert_eq!(bus.read(NR51), value);
+    }
+    assert_eq!(bus.read(NR52), 0xf0);
+
+    // Cleared when the APU is turned off
+    bus.write(NR52, 0x00);
+    assert_eq!(bus.read(NR50), 0x00);
+    assert_eq!(bus.read(NR51), 0x00);
+}
+
+#[test]
+fn panning() {
+    assert_eq!(tone("left", PULSE2_LEFT), [true, false]);
+    assert_eq!(tone("right", PULSE2_RIGHT), [false, true]);
+    assert_eq!(tone("both", PULSE2_LEFT | PULSE2_RIGHT), [true, true]);
+
+    // Channels panned on neither side, or other channels' bits
+    assert_eq!(tone("none", 0x00), [false, false]);
+    assert_eq!(
+        tone("others", !(PULSE2_LEFT | PULSE2_RIGHT)),
+        [false, false]
+    );
+}
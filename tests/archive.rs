@@ -0,0 +1,82 @@
+mod common;
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use flate2::{write::GzEncoder, Compression};
+use xenogb::core::mem::cartridge::Cartridge;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+fn build_rom(title: &[u8]) -> Vec<u8> {
+    let mut rom = common::build_rom(&[]);
+    rom[0x134..0x134 + title.len()].copy_from_slice(title);
+    rom
+}
+
+fn dir(name: &str) -> PathBuf {
+    common::temp_dir(&format!("archive_{name}"))
+}
+
+fn title(cartridge: &Cartridge) -> Vec<u8> {
+    (0x134..0x138).map(|addr| cartridge.read(addr)).collect()
+}
+
+#[test]
+fn zip() {
+    let path = dir("zip").join("game.zip");
+    let mut zip = ZipWriter::new(std::fs::File::create(&path).unwrap());
+    for (name, contents) in [
+        ("readme.txt", b"Not a ROM".to_vec()),
+        ("roms/game.GBC", build_rom(b"GAME")),
+        ("other.gb", build_rom(b"OTHR")),
+    ] {
+        zip.start_file(name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(&contents).unwrap();
+    }
+    zip.finish().unwrap();
+
+    // The first ROM, saved next to the archive under its own name
+    let cartridge = Cartridge::load(path.clone()).unwrap();
+    assert_eq!(title(&cartridge), b"GAME");
+    assert_eq!(cartridge.rom_path(), path.with_file_name("game.GBC"));
+
+    let named = PathBuf::from(format!("{}#other.gb", path.display()));
+    let cartridge = Cartridge::load(named).unwrap();
+    assert_eq!(title(&cartridge), b"OTHR");
+    assert_eq!(cartridge.rom_path(), path.with_file_name("other.gb"));
+
+    let named = PathBuf::from(format!("{}#roms/game.GBC", path.display()));
+    let cartridge = Cartridge::load(named).unwrap();
+    assert_eq!(cartridge.rom_path(), path.with_file_name("game.GBC"));
+
+    let missing = PathBuf::from(format!("{}#missing.gb", path.display()));
+    let err = Cartridge::load(missing).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn gzip() {
+    let dir = dir("gzip");
+    let path = dir.join("game.gb.gz");
+    let mut gz = GzEncoder::new(
+        std::fs::File::create(&path).unwrap(),
+        Compression::default(),
+    );
+    gz.write_all(&build_rom(b"GZIP")).unwrap();
+    gz.finish().unwrap();
+
+    let cartridge = Cartridge::load(path).unwrap();
+    assert_eq!(title(&cartridge), b"GZIP");
+    assert_eq!(cartridge.rom_path(), dir.join("game.gb"));
+}
+
+#[test]
+fn no_archive() {
+    let dir = dir("none");
+    let path = dir.join("game.gb");
+    std::fs::write(&path, build_rom(b"NONE")).unwrap();
+
+    assert_eq!(title(&Cartridge::load(path.clone()).unwrap()), b"NONE");
+    let named = PathBuf::from(format!("{}#game.gb", path.display()));
+    assert!(Cartridge::load(named).is_err());
+}
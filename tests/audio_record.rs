@@ -0,0 +1,77 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+const SAMPLE_RATE: u32 = 48000;
+const STEPS: usize = 200_000;
+
+// Plays a tone on the pulse 2 channel, panned on both sides
+const TONE: [u8; 30] = [
+    0x3e, 0x80, 0xe0, 0x26, // NR52: APU on
+    0x3e, 0xff, 0xe0, 0x25, // NR51: all channels on both sides
+    0x3e, 0x77, 0xe0, 0x24, // NR50: max volume
+    0x3e, 0x80, 0xe0, 0x16, // NR21: 50% duty
+    0x3e, 0xf0, 0xe0, 0x17, // NR22: max volume, no envelope
+    0x3e, 0x00, 0xe0, 0x18, // NR23
+    0x3e, 0x87, 0xe0, 0x19, // NR24: trigger
+    0x18, 0xfe, // JR -2
+];
+
+fn record(name: &str, stems: bool) -> PathBuf {
+    let dir = common::temp_dir(&format!("record_{name}"));
+    let mut cpu = common::setup_cpu(common::write_rom(&dir, &common::build_rom(&TONE)));
+
+    let wav_path = dir.join("record.wav");
+    cpu.bus.io.apu.set_sample_rate(SAMPLE_RATE);
+    cpu.bus.io.apu.start_recording(&wav_path, stems).unwrap();
+    for _ in 0..STEPS {
+        cpu.step();
+    }
+    cpu.bus.io.apu.stop_recording();
+
+    wav_path
+}
+
+// Returns the channel count and the data chunk of a WAV file
+fn read_wav(path: &Path) -> (u16, Vec<u8>) {
+    let wav = std::fs::read(path).unwrap();
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(
+        u32::from_le_bytes(wav[24..28].try_into().unwrap()),
+        SAMPLE_RATE
+    );
+    assert_eq!(&wav[36..40], b"data");
+
+    let size = u32::from_le_bytes(wav[40..44].try_into().unwrap()) as usize;
+    assert_eq!(size, wav.len() - 44);
+    (
+        u16::from_le_bytes(wav[22..24].try_into().unwrap()),
+        wav[44..].to_vec(),
+    )
+}
+
+#[test]
+fn record_is_deterministic() {
+    let (channels, first) = read_wav(&record("first", false));
+    let (_, second) = read_wav(&record("second", false));
+
+    assert_eq!(channels, 2);
+    assert!(first.iter().any(|b| *b != 0));
+    assert_eq!(first, second);
+}
+
+#[test]
+fn record_stems() {
+    let path = record("stems", true);
+    let (_, mix) = read_wav(&path);
+
+    for stem in ["pulse1", "pulse2", "wave", "noise"] {
+        let (channels, data) = read_wav(&path.with_file_name(format!("record_{stem}.wav")));
+        assert_eq!(channels, 1);
+        assert_eq!(data.len(), mix.len() / 2);
+
+        // Only the pulse 2 channel is playing
+        assert_eq!(stem == "pulse2", data.iter().any(|b| *b != 0));
+    }
+}
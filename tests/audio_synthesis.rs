@@ -0,0 +1,100 @@
+use xenogb::core::cpu::CLOCK_SPEED;
+use xenogb::core::io::audio::blip::BlipBuf;
+use xenogb::core::io::audio::high_pass::HighPass;
+
+const SAMPLE_RATE: u32 = 48000;
+
+fn read_all(blip: &mut BlipBuf<2>) -> Vec<[f32; 2]> {
+    std::iter::from_fn(|| blip.read()).collect()
+}
+
+#[test]
+fn blip_sample_rate() {
+    let mut blip = BlipBuf::<2>::new(CLOCK_SPEED, SAMPLE_RATE);
+
+    // Not a full output sample yet
+    blip.advance(CLOCK_SPEED / SAMPLE_RATE - 1);
+    assert_eq!(blip.read(), None);
+
+    // A second of clocks, in M-cycles
+    for _ in 0..CLOCK_SPEED / 4 {
+        blip.advance(4);
+    }
+    assert!(read_all(&mut blip).len().abs_diff(SAMPLE_RATE as usize) <= 1);
+
+    // Dynamic rate control stretches the output
+    blip.adjust_rate(1.005);
+    for _ in 0..CLOCK_SPEED / 4 {
+        blip.advance(4);
+    }
+    assert!(read_all(&mut blip).len().abs_diff(48240) <= 1);
+}
+
+#[test]
+fn blip_steps() {
+    let mut blip = BlipBuf::<2>::new(CLOCK_SPEED, SAMPLE_RATE);
+    blip.advance(1000);
+    blip.add_delta(0, 1.0);
+    blip.advance(CLOCK_SPEED / 100);
+    let samples = read_all(&mut blip);
+
+    // Band-limited: the edge lands between two samples, with some ringing
+    // around it, then the output settles on the exact delta
+    let left: Vec<f32> = samples.iter().map(|s| s[0]).collect();
+    assert!(left[..8].iter().all(|s| *s == 0.0));
+    assert!(left.iter().any(|s| *s > 0.1 && *s < 0.9));
+    assert!(left.iter().all(|s| *s > -0.15 && *s < 1.15));
+    assert!((left.last().unwrap() - 1.0).abs() < 1e-5);
+
+    // Channels are independent
+    assert!(samples.iter().all(|s| s[1] == 0.0));
+
+    // Steps at fractional positions still add up to their delta
+    blip.advance(7);
+    blip.add_delta(0, -0.5);
+    blip.add_delta(1, 0.25);
+    blip.advance(CLOCK_SPEED / 100);
+    let last = *read_all(&mut blip).last().unwrap();
+    assert!((last[0] - 0.5).abs() < 1e-5);
+    assert!((last[1] - 0.25).abs() < 1e-5);
+}
+
+#[test]
+fn high_pass_dc() {
+    let mut dmg = HighPass::<1>::new(false, SAMPLE_RATE);
+    let mut cgb = HighPass::<1>::new(true, SAMPLE_RATE);
+
+    // A DC offset goes through at first, then the capacitor charges
+    assert_eq!(dmg.apply([1.0]), [1.0]);
+    assert_eq!(cgb.apply([1.0]), [1.0]);
+    for _ in 0..100 {
+        dmg.apply([1.0]);
+        cgb.apply([1.0]);
+    }
+
+    // The CGB capacitor charges faster
+    let [dmg_out] = dmg.apply([1.0]);
+    let [cgb_out] = cgb.apply([1.0]);
+    assert!(dmg_out > 0.5 && dmg_out < 0.9);
+    assert!(cgb_out < 0.01);
+
+    // Fully blocked after a second
+    for _ in 0..SAMPLE_RATE {
+        dmg.apply([1.0]);
+    }
+    assert!(dmg.apply([1.0])[0].abs() < 1e-3);
+}
+
+#[test]
+fn high_pass_signal() {
+    let mut hpf = HighPass::<2>::new(false, SAMPLE_RATE);
+
+    // A square wave around an offset loses the offset, not its amplitude
+    let mut out = [0.0; 2];
+    for i in 0..SAMPLE_RATE {
+        let sample = if (i / 24) % 2 == 0 { 1.0 } else { 0.0 };
+        out = hpf.apply([sample, 0.0]);
+    }
+    assert!((out[0].abs() - 0.5).abs() < 0.05);
+    assert_eq!(out[1], 0.0);
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use std::path::PathBuf;
+
+use xenogb::core::mem::cheats::{Cheat, CheatCode, Cheats};
+use xenogb::emulator::Emulator;
+
+const DATA: usize = 0x200;
+const ROM_COPY: u16 = 0xc000;
+const RAM_COPY: u16 = 0xc002;
+
+// Copies a ROM byte and a WRAM byte, so patched reads end up in WRAM
+const PROGRAM: [u8; 14] = [
+    0xfa, 0x00, 0x02, // LD A, (0x200)
+    0xea, 0x00, 0xc0, // LD (0xc000), A
+    0xfa, 0x01, 0xc0, // LD A, (0xc001)
+    0xea, 0x02, 0xc0, // LD (0xc002), A
+    0x18, 0xf2, // JR -14
+];
+
+fn build_rom(name: &str) -> PathBuf {
+    let mut rom = common::build_rom(&PROGRAM);
+    rom[DATA] = 0x42;
+    common::write_rom(&common::temp_dir(&format!("cheats_{name}")), &rom)
+}
+
+fn run(name: &str, cheats: &[&str]) -> Emulator {
+    let mut emu = Emulator::load_rom(build_rom(name)).unwrap();
+    for code in cheats {
+        emu.cheats().add(Cheat::new("", code, true).unwrap());
+    }
+    for _ in 0..3 {
+        emu.run_frame();
+    }
+    emu
+}
+
+#[test]
+fn codes() {
+    assert_eq!(
+        "992-00F".parse(),
+        Ok(CheatCode::GAME_GENIE {
+            addr: 0x0200,
+            value: 0x99,
+            compare: None
+        })
+    );
+    assert_eq!(
+        "992-00F-EA3".parse(),
+        Ok(CheatCode::GAME_GENIE {
+            addr: 0x0200,
+            value: 0x99,
+            compare: Some(0x42)
+        })
+    );
+    assert_eq!(
+        "017701C0".parse(),
+        Ok(CheatCode::GAMESHARK {
+            bank: 0x01,
+            addr: 0xc001,
+            value: 0x77
+        })
+    );
+    assert!("0177".parse::<CheatCode>().is_err());
+    // Outside of the cartridge RAM, WRAM and HRAM
+    assert!("017701FF".parse::<CheatCode>().is_err());
+    assert!("01770080".parse::<CheatCode>().is_err());
+    assert!("0177FFFF".parse::<CheatCode>().is_err());
+    assert!("017780FF".parse::<CheatCode>().is_ok());
+    assert!("992-00G".parse::<CheatCode>().is_err());
+
+    let cheat = Cheat::new("Both", "992-00f + 017701c0", true).unwrap();
+    assert_eq!(cheat.code, "992-00F + 017701C0");
+    assert_eq!(cheat.codes().len(), 2);
+}
+
+#[test]
+fn game_genie() {
+    let emu = run("genie", &[]);
+    assert_eq!(emu.cpu().bus.read(ROM_COPY), 0x42);
+
+    let emu = run("genie", &["992-00F"]);
+    assert_eq!(emu.cpu().bus.read(ROM_COPY), 0x99);
+
+    // Only patched when the compare value matches
+    let emu = run("genie", &["992-00F-EA3"]);
+    assert_eq!(emu.cpu().bus.read(ROM_COPY), 0x99);
+    let emu = run("genie", &["992-00F-FA3"]);
+    assert_eq!(emu.cpu().bus.read(ROM_COPY), 0x42);
+}
+
+#[test]
+fn gameshark() {
+    let emu = run("shark", &["017701C0"]);
+    assert_eq!(emu.cpu().bus.read(RAM_COPY), 0x77);
+
+    // WRAM bank 1 is the one mapped on DMG
+    let emu = run("shark", &["915501D0"]);
+    assert_eq!(emu.cpu().bus.read(0xd001), 0x55);
+
+    // Disabled cheats are not applied
+    let mut emu = Emulator::load_rom(build_rom("disabled")).unwrap();
+    emu.cheats().add(Cheat::new("", "017701C0", false).unwrap());
+    emu.run_frame();
+    emu.run_frame();
+    assert_eq!(emu.cpu().bus.read(RAM_COPY), 0x00);
+
+    emu.cheats().set_enabled(0, true);
+    emu.run_frame();
+    emu.run_frame();
+    assert_eq!(emu.cpu().bus.read(RAM_COPY), 0x77);
+}
+
+#[test]
+fn gameshark_cartridge_ram() {
+    // MBC1+RAM, no battery
+    let mut rom = common::build_rom(&common::LOOP);
+    rom[0x147] = 0x02;
+    rom[0x149] = 0x03;
+    let dir = common::temp_dir("cheats_cartridge_ram");
+    let mut emu = Emulator::load_rom(common::write_rom(&dir, &rom)).unwrap();
+    assert!(emu.sram().is_none());
+
+    // Written to the selected bank, even with the RAM disabled
+    emu.cheats().add(Cheat::new("", "826610A0", true).unwrap());
+    emu.run_frame();
+    emu.run_frame();
+    let bus = &mut emu.cpu_mut().bus;
+    bus.write(0x0000, 0x0a);
+    bus.write(0x4000, 0x02);
+    assert_eq!(bus.read(0xa010), 0x66);
+    bus.write(0x4000, 0x00);
+    assert_eq!(bus.read(0xa010), 0x00);
+}
+
+#[test]
+fn cheat_files() {
+    let path = common::temp_dir("cheat_files").join("files.cht");
+
+    let mut cheats = Cheats::load(path.clone());
+    assert!(cheats.list().is_empty());
+    cheats.add(Cheat::new("Infinite lives", "017701C0", true).unwrap());
+    cheats.add(Cheat::new("Patch", "992-00F-EA3", true).unwrap());
+    cheats.add(Cheat::new("Removed", "992-00F", true).unwrap());
+    cheats.set_enabled(1, false);
+    cheats.remove(2);
+
+    let cheats = Cheats::load(path);
+    let list = cheats.list();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].name, "Infinite lives");
+    assert_eq!(list[0].code, "017701C0");
+    assert!(list[0].enabled);
+    assert_eq!(list[1].name, "Patch");
+    assert!(!list[1].enabled);
+
+    let rom = PathBuf::from("roms/tetris.gb");
+    assert_eq!(Cheats::path(&rom, None), PathBuf::from("roms/tetris.cht"));
+    assert_eq!(
+        Cheats::path(&rom, Some(&PathBuf::from("saves"))),
+        PathBuf::from("saves/tetris.cht")
+    );
+}
@@ -0,0 +1,65 @@
+// Helpers shared by the integration tests, not all of them use every one
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use crossbeam_channel::{unbounded, Receiver};
+use xenogb::core::cpu::{CPUSpeed, LR35902CPU};
+use xenogb::core::mem::{boot::BootRom, bus::Bus, cartridge::Cartridge};
+
+pub const ROM_SIZE: usize = 0x8000;
+pub const START: usize = 0x150;
+pub const JP_START: [u8; 3] = [0xc3, 0x50, 0x01]; // JP 0x150
+pub const LD_B_B: u8 = 0x40;
+pub const LOOP: [u8; 2] = [0x18, 0xfe]; // JR -2
+
+// A 32 KiB ROM only cartridge, jumping to the code at 0x150
+pub fn build_rom(code: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0x00; ROM_SIZE];
+    rom[0x100..0x103].copy_from_slice(&JP_START);
+    rom[START..START + code.len()].copy_from_slice(code);
+    rom
+}
+
+// An empty directory, unique to the test and the process running it
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("xenogb_{name}_{}", std::process::id()));
+    _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+pub fn write_rom(dir: &Path, rom: &[u8]) -> PathBuf {
+    let path = dir.join("game.gb");
+    std::fs::write(&path, rom).unwrap();
+    path
+}
+
+// The frames and samples are dropped, as no one listens to the channels
+pub fn setup_cpu(rom_path: PathBuf) -> LR35902CPU {
+    setup_cpu_with_audio(rom_path).0
+}
+
+// Only the frames are dropped, the samples are received by the test
+pub fn setup_cpu_with_audio(rom_path: PathBuf) -> (LR35902CPU, Receiver<[f32; 2]>) {
+    let (vcs, _) = unbounded();
+    let (acs, acr) = unbounded();
+
+    let cpu = LR35902CPU::new(
+        Bus::new(Cartridge::new(rom_path), BootRom::NONE, vcs, acs),
+        false,
+        CPUSpeed::CUSTOM,
+    );
+    (cpu, acr)
+}
+
+// Steps the CPU until it reaches LD B, B, returns false if it never does
+pub fn run_until_ldbb(cpu: &mut LR35902CPU, max_steps: usize) -> bool {
+    for _ in 0..max_steps {
+        if cpu.bus.read(cpu.pc()) == LD_B_B {
+            return true;
+        }
+        cpu.step();
+    }
+    false
+}
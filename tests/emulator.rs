@@ -0,0 +1,198 @@
+mod common;
+
+use std::path::PathBuf;
+
+use xenogb::core::io::joypad::JOYPAD_INPUT;
+use xenogb::core::io::video::capture::to_rgb;
+use xenogb::core::save_state::StateError;
+use xenogb::emulator::Emulator;
+
+const SAMPLE_RATE: u32 = 48000;
+const PRESSED: u16 = 0xc000;
+
+// Plays a tone on the pulse 2 channel, then copies the action buttons to WRAM
+const PROGRAM: [u8; 39] = [
+    0x3e, 0x80, 0xe0, 0x26, // NR52: APU on
+    0x3e, 0xff, 0xe0, 0x25, // NR51: all channels on both sides
+    0x3e, 0x77, 0xe0, 0x24, // NR50: max volume
+    0x3e, 0x80, 0xe0, 0x16, // NR21: 50% duty
+    0x3e, 0xf0, 0xe0, 0x17, // NR22: max volume, no envelope
+    0x3e, 0x00, 0xe0, 0x18, // NR23
+    0x3e, 0x87, 0xe0, 0x19, // NR24: trigger
+    0x3e, 0x10, 0xe0, 0x00, // P1: select the action buttons
+    0xf0, 0x00, // LDH A, (P1)
+    0xea, 0x00, 0xc0, // LD (0xc000), A
+    0x18, 0xf5, // JR -11
+];
+
+fn build_rom(name: &str, header_checksum: u8) -> PathBuf {
+    let mut rom = common::build_rom(&PROGRAM);
+    rom[0x14d] = header_checksum;
+    common::write_rom(&common::temp_dir(&format!("emulator_{name}")), &rom)
+}
+
+#[test]
+fn run_frames() {
+    let mut emu = Emulator::load_rom(build_rom("frames", 0)).unwrap();
+    emu.set_sample_rate(SAMPLE_RATE);
+
+    for _ in 0..60 {
+        emu.run_frame();
+    }
+    assert_eq!(emu.frames(), 60);
+    assert_eq!(to_rgb(emu.frame_buffer()).len(), 160 * 144 * 3);
+
+    // About a second of audio, none left after draining
+    let samples = emu.drain_audio();
+    assert!(samples.len().abs_diff(SAMPLE_RATE as usize) < SAMPLE_RATE as usize / 10);
+    assert!(samples.iter().any(|s| s[0] != 0.0 && s[1] != 0.0));
+    assert!(emu.drain_audio().is_empty());
+}
+
+#[test]
+fn buttons() {
+    let mut emu = Emulator::load_rom(build_rom("buttons", 0)).unwrap();
+
+    emu.set_buttons(JOYPAD_INPUT::A | JOYPAD_INPUT::START);
+    emu.run_frame();
+    assert_eq!(emu.cpu().bus.read(PRESSED) & 0xf, 0b1010);
+
+    emu.set_buttons(JOYPAD_INPUT::B);
+    emu.run_frame();
+    assert_eq!(emu.cpu().bus.read(PRESSED) & 0xf, 0b1101);
+
+    emu.set_buttons(0);
+    emu.run_frame();
+    assert_eq!(emu.cpu().bus.read(PRESSED) & 0xf, 0b1111);
+}
+
+#[test]
+fn save_states() {
+    let mut emu = Emulator::load_rom(build_rom("states", 0)).unwrap();
+
+    for _ in 0..10 {
+        emu.run_frame();
+    }
+    let state = emu.save_state();
+
+    emu.set_buttons(JOYPAD_INPUT::SELECT);
+    for _ in 0..10 {
+        emu.run_frame();
+    }
+    let after = emu.save_state();
+    let frame = to_rgb(emu.frame_buffer());
+    assert_ne!(state, after);
+
+    // Replaying from the state ends up in the exact same place
+    emu.load_state(&state).unwrap();
+    assert_eq!(emu.save_state(), state);
+    assert_eq!(emu.frames(), 10);
+
+    emu.set_buttons(JOYPAD_INPUT::SELECT);
+    for _ in 0..10 {
+        emu.run_frame();
+    }
+    assert_eq!(emu.save_state(), after);
+    assert_eq!(to_rgb(emu.frame_buffer()), frame);
+}
+
+#[test]
+fn invalid_states() {
+    let mut emu = Emulator::load_rom(build_rom("invalid", 0)).unwrap();
+    let mut other = Emulator::load_rom(build_rom("other", 1)).unwrap();
+    emu.run_frame();
+    other.run_frame();
+
+    let state = emu.save_state();
+
+    assert!(matches!(
+        other.load_state(&emu.save_state()),
+        Err(StateError::WrongRom)
+    ));
+    assert!(matches!(
+        emu.load_state(b"XGBS"),
+        Err(StateError::Truncated)
+    ));
+    assert!(matches!(
+        emu.load_state(&state[..state.len() - 1]),
+        Err(StateError::Truncated)
+    ));
+    assert!(matches!(
+        emu.load_state(&[state.as_slice(), &[0]].concat()),
+        Err(StateError::Invalid)
+    ));
+
+    let mut bad = state.clone();
+    bad[0] = b'_';
+    assert!(matches!(emu.load_state(&bad), Err(StateError::Invalid)));
+
+    // Failed loads leave the emulation untouched
+    assert_eq!(emu.save_state(), state);
+}
+
+#[test]
+fn reset() {
+    let mut emu = Emulator::load_rom(build_rom("reset", 0)).unwrap();
+    let mut fresh = Emulator::load_rom(build_rom("reset", 0)).unwrap();
+    emu.set_buttons(JOYPAD_INPUT::A);
+    for _ in 0..10 {
+        emu.run_frame();
+    }
+
+    // Back to power on, the sample rate is kept
+    emu.set_sample_rate(SAMPLE_RATE);
+    fresh.set_sample_rate(SAMPLE_RATE);
+    emu.reset();
+    assert!(emu.drain_audio().is_empty());
+    assert_eq!(emu.save_state(), fresh.save_state());
+    emu.run_frame();
+    fresh.run_frame();
+    assert_eq!(emu.drain_audio(), fresh.drain_audio());
+}
+
+#[test]
+fn unsupported_cartridges() {
+    let dir = common::temp_dir("emulator_unsupported");
+    let mut rom = common::build_rom(&PROGRAM);
+
+    // MBC2
+    rom[0x147] = 0x05;
+    let err = Emulator::load_rom(common::write_rom(&dir, &rom))
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+
+    // Unknown ROM and RAM sizes
+    rom[0x147] = 0x00;
+    rom[0x148] = 0x09;
+    let err = Emulator::load_rom(common::write_rom(&dir, &rom))
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    rom[0x148] = 0x00;
+    rom[0x149] = 0x06;
+    let err = Emulator::load_rom(common::write_rom(&dir, &rom))
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn side_by_side() {
+    let mut alone = Emulator::load_rom(build_rom("alone", 0)).unwrap();
+    for _ in 0..10 {
+        alone.run_frame();
+    }
+
+    // Inputs given to one instance never reach the other
+    let mut emu = Emulator::load_rom(build_rom("alone", 0)).unwrap();
+    let mut other = Emulator::load_rom(build_rom("side", 0)).unwrap();
+    for i in 0..10 {
+        other.set_buttons(if i % 2 == 0 { JOYPAD_INPUT::A } else { 0 });
+        other.run_frame();
+        emu.run_frame();
+    }
+
+    assert_eq!(emu.save_state(), alone.save_state());
+    assert_ne!(other.save_state(), alone.save_state());
+}
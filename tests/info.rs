@@ -0,0 +1,155 @@
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use xenogb::core::mem::header::CartridgeHeader;
+
+// 64 KiB, as declared in the header
+const ROM_SIZE: usize = common::ROM_SIZE * 2;
+
+const NINTENDO_LOGO: [u8; 0x30] = [
+    0xce, 0xed, 0x66, 0x66, 0xcc, 0x0d, 0x00, 0x0b, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0c, 0x00, 0x0d,
+    0x00, 0x08, 0x11, 0x1f, 0x88, 0x89, 0x00, 0x0e, 0xdc, 0xcc, 0x6e, 0xe6, 0xdd, 0xdd, 0xd9, 0x99,
+    0xbb, 0xbb, 0x67, 0x63, 0x6e, 0x0e, 0xec, 0xcc, 0xdd, 0xdc, 0x99, 0x9f, 0xbb, 0xb9, 0x33, 0x3e,
+];
+
+fn build_rom() -> Vec<u8> {
+    let mut rom: Vec<u8> = (0..ROM_SIZE).map(|i| (i * 3) as u8).collect();
+    rom[0x100..0x150].fill(0x00);
+    rom[0x104..0x134].copy_from_slice(&NINTENDO_LOGO);
+    rom[0x134..0x13d].copy_from_slice(b"INFO TEST");
+    rom[0x13f..0x143].copy_from_slice(b"AXYE");
+    // CGB enhanced, new licensee Nintendo, SGB, MBC5+RAM+BATTERY, 64 KiB ROM, 32 KiB RAM
+    rom[0x143] = 0x80;
+    rom[0x144..0x146].copy_from_slice(b"01");
+    rom[0x146] = 0x03;
+    rom[0x147] = 0x1b;
+    rom[0x148] = 0x01;
+    rom[0x149] = 0x03;
+    rom[0x14a] = 0x01;
+    rom[0x14b] = 0x33;
+    rom[0x14c] = 0x02;
+
+    rom[0x14d] = rom[0x134..0x14d]
+        .iter()
+        .fold(0u8, |sum, b| sum.wrapping_sub(*b).wrapping_sub(1));
+    let global = rom
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !(0x14e..=0x14f).contains(i))
+        .fold(0u16, |sum, (_, b)| sum.wrapping_add(*b as u16));
+    rom[0x14e..0x150].copy_from_slice(&global.to_be_bytes());
+    rom
+}
+
+fn row(header: &CartridgeHeader, name: &str) -> String {
+    header
+        .rows()
+        .into_iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, value)| value)
+        .unwrap_or_else(|| panic!("Missing {name}"))
+}
+
+#[test]
+fn decoded() {
+    let header = CartridgeHeader::new(&build_rom());
+
+    assert_eq!(header.title(), "INFO TEST");
+    assert_eq!(row(&header, "Manufacturer"), "AXYE");
+    assert_eq!(header.licensee(), "Nintendo Research & Development 1 (01)");
+    assert_eq!(header.cartridge_type(), "MBC5+RAM+BATTERY");
+    assert!(header.is_supported());
+    assert_eq!(header.rom_size(), Some(0x10000));
+    assert_eq!(header.ram_size(), Some(0x8000));
+    assert_eq!(header.destination(), "Overseas");
+    assert!(header.is_cgb());
+
+    assert_eq!(row(&header, "ROM size"), "64 KiB");
+    assert_eq!(row(&header, "RAM size"), "32 KiB");
+    assert_eq!(row(&header, "CGB"), "CGB enhanced");
+    assert_eq!(row(&header, "SGB"), "Yes");
+    assert_eq!(row(&header, "Version"), "2");
+
+    // Old licensee codes, and controllers xenogb does not emulate
+    let mut rom = build_rom();
+    rom[0x14b] = 0xa4;
+    rom[0x147] = 0x22;
+    let header = CartridgeHeader::new(&rom);
+    assert_eq!(header.licensee(), "Konami (A4)");
+    assert!(!header.is_supported());
+    assert!(row(&header, "Cartridge type").ends_with("unsupported"));
+}
+
+#[test]
+fn validation() {
+    let rom = build_rom();
+    let header = CartridgeHeader::new(&rom);
+    assert!(header.logo_ok());
+    assert!(header.header_checksum_ok());
+    assert!(header.global_checksum_ok());
+    assert!(header.checks().iter().all(|(_, ok, _)| *ok));
+
+    // A bad dump, with a corrupted logo and a patched title
+    let mut bad = rom.clone();
+    bad[0x110] ^= 0xff;
+    bad[0x134] = b'X';
+    let header = CartridgeHeader::new(&bad);
+    assert!(!header.logo_ok());
+    assert!(!header.header_checksum_ok());
+    assert!(!header.global_checksum_ok());
+    assert!(header.to_string().contains("Header checksum: 0x"));
+
+    // A truncated dump
+    let header = CartridgeHeader::new(&rom[..0x8000]);
+    assert!(header.header_checksum_ok());
+    assert_eq!(
+        row(&header, "ROM size"),
+        "64 KiB, but the file holds 32 KiB"
+    );
+}
+
+fn xenogb_info(dir: &Path, rom_path: &Path) -> Output {
+    // Run from the test directory, which gets the logs
+    Command::new(env!("CARGO_BIN_EXE_xenogb"))
+        .current_dir(dir)
+        .arg("info")
+        .arg(rom_path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn subcommand() {
+    let dir = common::temp_dir("info_subcommand");
+    let rom = build_rom();
+
+    let output = xenogb_info(&dir, &common::write_rom(&dir, &rom));
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("INFO TEST"));
+    assert!(stdout.contains("Global checksum: 0x"));
+
+    // Still printed, but failing, for ROMs xenogb cannot run
+    let mut unsupported = rom.clone();
+    unsupported[0x147] = 0x22;
+    let output = xenogb_info(&dir, &common::write_rom(&dir, &unsupported));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("unsupported"));
+
+    // And bad dumps
+    let mut bad = rom.clone();
+    bad[0x110] ^= 0xff;
+    let output = xenogb_info(&dir, &common::write_rom(&dir, &bad));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("BAD"));
+
+    let output = xenogb_info(&dir, &common::write_rom(&dir, &rom[..0x100]));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Missing cartridge header"));
+
+    let output = xenogb_info(&dir, &dir.join("missing.gb"));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unable to read"));
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use std::path::PathBuf;
+
+use xenogb::core::mem::cartridge::Cartridge;
+use xenogb::core::mem::patch;
+
+use common::ROM_SIZE;
+
+fn build_rom() -> Vec<u8> {
+    let mut rom: Vec<u8> = (0..ROM_SIZE).map(|i| (i * 7) as u8).collect();
+    rom[0x100..0x150].fill(0x00);
+    rom[0x134..0x13e].copy_from_slice(b"PATCH TEST");
+    rom
+}
+
+fn header_checksum(rom: &[u8]) -> u8 {
+    rom[0x134..0x14d]
+        .iter()
+        .fold(0u8, |sum, b| sum.wrapping_sub(*b).wrapping_sub(1))
+}
+
+fn varint(mut value: usize, out: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(0x80 | byte);
+            return;
+        }
+        out.push(byte);
+        value -= 1;
+    }
+}
+
+fn footer(source: &[u8], target: &[u8], mut patch: Vec<u8>) -> Vec<u8> {
+    patch.extend(crc32fast::hash(source).to_le_bytes());
+    patch.extend(crc32fast::hash(target).to_le_bytes());
+    patch.extend(crc32fast::hash(&patch).to_le_bytes());
+    patch
+}
+
+fn ips() -> Vec<u8> {
+    let mut patch = b"PATCH".to_vec();
+    // Title
+    patch.extend([0x00, 0x01, 0x34, 0x00, 0x04]);
+    patch.extend(b"HACK");
+    // RLE run, past the end of the ROM
+    patch.extend([0x00, 0x7f, 0xfe, 0x00, 0x00, 0x00, 0x04, 0xaa]);
+    patch.extend(b"EOF");
+    patch
+}
+
+fn ups(source: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut patch = b"UPS1".to_vec();
+    varint(source.len(), &mut patch);
+    varint(target.len(), &mut patch);
+
+    let mut last = 0;
+    let mut pos = 0;
+    while pos < target.len() {
+        let byte = |i: usize| source.get(i).copied().unwrap_or(0) ^ target[i];
+        if byte(pos) == 0 {
+            pos += 1;
+            continue;
+        }
+        varint(pos - last, &mut patch);
+        while pos < target.len() && byte(pos) != 0 {
+            patch.push(byte(pos));
+            pos += 1;
+        }
+        patch.push(0);
+        pos += 1;
+        last = pos;
+    }
+    footer(source, target, patch)
+}
+
+#[test]
+fn ips_patches() {
+    let rom = build_rom();
+    let patched = patch::apply(&rom, &ips()).unwrap();
+
+    assert_eq!(patched.len(), ROM_SIZE + 2);
+    assert_eq!(&patched[0x134..0x138], b"HACK");
+    assert_eq!(&patched[0x7ffe..], [0xaa; 4]);
+    assert_eq!(patched[0x150..0x7ffe], rom[0x150..0x7ffe]);
+    // The title changed, so did the header checksum
+    assert_eq!(patched[0x14d], header_checksum(&patched));
+
+    // Truncation
+    let mut patch = b"PATCH".to_vec();
+    patch.extend(b"EOF");
+    patch.extend([0x00, 0x40, 0x00]);
+    assert_eq!(patch::apply(&rom, &patch).unwrap(), rom[..0x4000]);
+
+    assert!(patch::apply(&rom, b"PATCH\x00\x01").is_err());
+    assert!(patch::apply(&rom, b"NOT A PATCH").is_err());
+}
+
+#[test]
+fn ups_patches() {
+    let rom = build_rom();
+    let mut target = rom.clone();
+    target[0x200..0x210].fill(0x42);
+    target[0x7fff] = 0x00;
+    target.extend([0x01, 0x02, 0x03]);
+
+    let patch = ups(&rom, &target);
+    assert_eq!(patch::apply(&rom, &patch).unwrap(), target);
+
+    // Wrong source ROM
+    let mut other = rom.clone();
+    other[0] ^= 0xff;
+    assert!(patch::apply(&other, &patch).is_err());
+
+    // Corrupted patch
+    let mut corrupted = patch.clone();
+    corrupted[8] ^= 0xff;
+    assert!(patch::apply(&rom, &corrupted).is_err());
+}
+
+#[test]
+fn bps_patches() {
+    let rom = build_rom();
+
+    // Every action: source read, target read, target copy and source copy
+    let mut target = rom[..0x100].to_vec();
+    target.extend([0x11, 0x22, 0x11, 0x22, 0x11, 0x22, 0x11, 0x22]);
+    target.extend_from_slice(&rom[0x400..0x410]);
+    target.extend_from_slice(&rom[target.len()..]);
+
+    let mut patch = b"BPS1".to_vec();
+    varint(rom.len(), &mut patch);
+    varint(target.len(), &mut patch);
+    varint(3, &mut patch);
+    patch.extend(b"xml");
+    varint((0x100 - 1) << 2, &mut patch);
+    varint((2 - 1) << 2 | 1, &mut patch);
+    patch.extend([0x11, 0x22]);
+    // Overlapping the bytes it writes
+    varint((6 - 1) << 2 | 3, &mut patch);
+    varint(0x100 << 1, &mut patch);
+    varint((0x10 - 1) << 2 | 2, &mut patch);
+    varint(0x400 << 1, &mut patch);
+    varint((ROM_SIZE - 0x118 - 1) << 2, &mut patch);
+    let patch = footer(&rom, &target, patch);
+
+    assert_eq!(patch::apply(&rom, &patch).unwrap(), target);
+    assert!(patch::apply(&rom[..0x4000], &patch).is_err());
+}
+
+#[test]
+fn malformed_patches() {
+    let rom = build_rom();
+    let bps = |target_size: usize, actions: &[usize]| {
+        let mut patch = b"BPS1".to_vec();
+        varint(rom.len(), &mut patch);
+        varint(target_size, &mut patch);
+        for action in actions {
+            varint(*action, &mut patch);
+        }
+        footer(&rom, &rom, patch)
+    };
+
+    // Larger than any cartridge
+    assert!(patch::apply(&rom, &bps(0x800001, &[0])).is_err());
+    // Metadata running past the end
+    assert!(patch::apply(&rom, &bps(rom.len(), &[usize::MAX >> 8])).is_err());
+    // Target copy growing the ROM past its size
+    assert!(patch::apply(&rom, &bps(rom.len(), &[0, 0, (1 << 40) << 2 | 3, 0])).is_err());
+    // Source copy running past the end of the ROM
+    let copy = ((1 << 40) - 1) << 2 | 2;
+    assert!(patch::apply(&rom, &bps(rom.len(), &[0, copy, 0])).is_err());
+
+    // UPS runs starting way past the end of the ROM
+    let mut patch = b"UPS1".to_vec();
+    varint(rom.len(), &mut patch);
+    varint(rom.len(), &mut patch);
+    varint(usize::MAX >> 8, &mut patch);
+    patch.extend([0x01, 0x00]);
+    varint(usize::MAX >> 8, &mut patch);
+    patch.extend([0x01, 0x00]);
+    assert!(patch::apply(&rom, &footer(&rom, &rom, patch)).is_err());
+
+    // IPS record written past any cartridge size
+    let mut patch = b"PATCH".to_vec();
+    patch.extend([0xff, 0xff, 0xfe, 0x00, 0x00, 0xff, 0xff, 0x00]);
+    patch.extend(b"EOF");
+    assert!(patch::apply(&rom, &patch).is_err());
+}
+
+#[test]
+fn cartridges() {
+    let dir = common::temp_dir("patch_cartridges");
+    let rom = build_rom();
+    let rom_path = common::write_rom(&dir, &rom);
+
+    let mut target = rom.clone();
+    target[0x150] = 0x99;
+    let ups_path = dir.join("translation.ups");
+    std::fs::write(&ups_path, ups(&rom, &target)).unwrap();
+
+    // Patches passed explicitly, chained
+    let ips_path: PathBuf = dir.join("hack.ips");
+    std::fs::write(&ips_path, ips()).unwrap();
+    let cartridge = Cartridge::load_patched(rom_path.clone(), &[ups_path, ips_path]).unwrap();
+    assert_eq!(cartridge.read(0x150), 0x99);
+    assert_eq!(cartridge.read(0x134), b'H');
+
+    // Picked up next to the ROM
+    assert_eq!(Cartridge::load(rom_path.clone()).unwrap().read(0x134), b'P');
+    std::fs::write(dir.join("game.ips"), ips()).unwrap();
+    assert_eq!(Cartridge::load(rom_path.clone()).unwrap().read(0x134), b'H');
+    assert_eq!(std::fs::read(&rom_path).unwrap(), rom);
+}
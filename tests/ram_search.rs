@@ -0,0 +1,109 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::LOOP;
+use xenogb::core::mem::cheats::Cheat;
+use xenogb::debugger::{MemState, RamSearch, SearchFilter, SearchSize};
+use xenogb::emulator::Emulator;
+
+const COUNTER: u16 = 0xc123;
+
+fn build_rom(name: &str) -> PathBuf {
+    let dir = common::temp_dir(&format!("ram_search_{name}"));
+    common::write_rom(&dir, &common::build_rom(&LOOP))
+}
+
+fn snapshot(emu: &mut Emulator) -> MemState {
+    MemState::new(&mut emu.cpu_mut().bus)
+}
+
+fn set(emu: &mut Emulator, addr: u16, value: u8) {
+    emu.cpu_mut().bus.write(addr, value);
+    emu.run_frame();
+}
+
+#[test]
+fn search() {
+    let mut emu = Emulator::load_rom(build_rom("search")).unwrap();
+    set(&mut emu, COUNTER, 10);
+
+    // WRAM banks 0 and 1, then HRAM
+    let mem = snapshot(&mut emu);
+    assert_eq!(mem.len(), 0x2000 + 0x7f);
+
+    let mut search = RamSearch::new(mem, SearchSize::BYTE, false);
+    set(&mut emu, COUNTER, 11);
+    search.filter(snapshot(&mut emu), SearchFilter::INCREASED);
+    set(&mut emu, COUNTER, 11);
+    search.filter(snapshot(&mut emu), SearchFilter::EQUAL);
+    set(&mut emu, COUNTER, 7);
+    search.filter(snapshot(&mut emu), SearchFilter::DECREASED);
+    search.filter(snapshot(&mut emu), SearchFilter::VALUE(7));
+
+    let mem = snapshot(&mut emu);
+    let results: Vec<_> = search
+        .results()
+        .iter()
+        .map(|&idx| mem.location(idx).to_string())
+        .collect();
+    assert_eq!(results, ["C123"]);
+
+    // Signed 16 bits values, spread over two bytes
+    set(&mut emu, COUNTER, 0xff);
+    set(&mut emu, COUNTER + 1, 0xff);
+    let mut search = RamSearch::new(snapshot(&mut emu), SearchSize::WORD, true);
+    search.filter(snapshot(&mut emu), SearchFilter::VALUE(-1));
+    let idx = search.results()[0];
+    assert_eq!(mem.location(idx).to_string(), "C123");
+
+    set(&mut emu, COUNTER + 1, 0x01);
+    search.filter(snapshot(&mut emu), SearchFilter::CHANGED);
+    assert_eq!(search.results(), [idx]);
+    assert_eq!(snapshot(&mut emu).value(idx, SearchSize::WORD, true), 0x1ff);
+}
+
+#[test]
+fn locations() {
+    let mut emu = Emulator::load_rom(build_rom("locations")).unwrap();
+    let mem = snapshot(&mut emu);
+
+    assert_eq!(mem.location(0x0010).to_string(), "C010");
+    assert_eq!(mem.location(0x1010).to_string(), "1:D010");
+    assert_eq!(mem.location(0x2001).to_string(), "FF81");
+
+    // Cheats freeze the location, in its bank
+    assert_eq!(mem.location(0x0123).gameshark(0x63), "016323C1");
+    assert_eq!(mem.location(0x1123).gameshark(0x63), "916323D1");
+
+    let code = mem.location(0x0123).gameshark(0x63);
+    emu.cheats().add(Cheat::new("", &code, true).unwrap());
+    emu.run_frame();
+    assert_eq!(emu.cpu().bus.read(COUNTER), 0x63);
+}
+
+#[test]
+fn words_within_regions() {
+    let mut emu = Emulator::load_rom(build_rom("words")).unwrap();
+    // WRAM banks 0 and 1 follow each other
+    set(&mut emu, 0xcfff, 0x34);
+    set(&mut emu, 0xd000, 0x12);
+    // The end of WRAM and the start of HRAM do not
+    set(&mut emu, 0xdfff, 0x78);
+    set(&mut emu, 0xff80, 0x56);
+
+    let mem = snapshot(&mut emu);
+    let mut search = RamSearch::new(mem.clone(), SearchSize::WORD, false);
+    search.filter(mem.clone(), SearchFilter::VALUE(0x1234));
+    assert_eq!(search.results(), [0x0fff]);
+
+    let mut search = RamSearch::new(mem.clone(), SearchSize::WORD, false);
+    search.filter(mem.clone(), SearchFilter::VALUE(0x5678));
+    assert!(search.results().is_empty());
+
+    // Nor does the end of HRAM
+    let mut search = RamSearch::new(mem.clone(), SearchSize::WORD, false);
+    search.filter(mem.clone(), SearchFilter::EQUAL);
+    assert!(!search.results().contains(&(mem.len() - 1)));
+    assert!(!search.results().contains(&0x1fff));
+}
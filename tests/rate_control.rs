@@ -0,0 +1,49 @@
+use xenogb::core::io::audio::rate_control::{RateControl, MAX_RATE_DELTA};
+
+fn assert_ratio(rate_control: &RateControl, ratio: f32) {
+    assert!(
+        (rate_control.ratio() - ratio).abs() < 1e-6,
+        "{} != {ratio}",
+        rate_control.ratio()
+    );
+}
+
+#[test]
+fn ratio() {
+    let rate_control = RateControl::new();
+    assert_eq!(rate_control.ratio(), 1.0);
+
+    // Half full is the target, faster output when draining, slower when filling up
+    rate_control.update(0.5);
+    assert_ratio(&rate_control, 1.0);
+    rate_control.update(0.25);
+    assert_ratio(&rate_control, 1.0 + MAX_RATE_DELTA / 2.0);
+    rate_control.update(0.0);
+    assert_ratio(&rate_control, 1.0 + MAX_RATE_DELTA);
+    rate_control.update(1.0);
+    assert_ratio(&rate_control, 1.0 - MAX_RATE_DELTA);
+}
+
+#[test]
+fn clamped() {
+    let rate_control = RateControl::new();
+
+    // Out of range fill levels never push the ratio past its maximum deviation
+    rate_control.update(-3.0);
+    assert_ratio(&rate_control, 1.0 + MAX_RATE_DELTA);
+    rate_control.update(42.0);
+    assert_ratio(&rate_control, 1.0 - MAX_RATE_DELTA);
+    rate_control.update(f32::INFINITY);
+    assert_ratio(&rate_control, 1.0 - MAX_RATE_DELTA);
+}
+
+#[test]
+fn shared() {
+    // The audio thread updates the ratio the APU reads
+    let apu = RateControl::new();
+    let audio = apu.clone();
+    std::thread::spawn(move || audio.update(0.0))
+        .join()
+        .unwrap();
+    assert_ratio(&apu, 1.0 + MAX_RATE_DELTA);
+}
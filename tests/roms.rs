@@ -0,0 +1,497 @@
+// ROM test suites, run in-process on every available core
+//
+//   cargo test --test roms [-- <filters>...]
+//
+// Only the ROMs whose path contains one of the filters are run. Entries of
+// tests/skip.list are reported as skipped. JUnit and JSON reports are written
+// to target/rom-tests, or XENOGB_REPORT_DIR
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{unbounded, Receiver};
+use serde_json::json;
+use xenogb::core::cpu::{CPUSpeed, LR35902CPU};
+use xenogb::core::io::video::capture::to_rgb;
+use xenogb::core::io::video::ppu::Vbuf;
+use xenogb::core::mem::{boot::BootRom, bus::Bus, cartridge::Cartridge};
+use xenogb::core::stop_condition::{StopCondition, StopOutcome};
+
+// M-cycles per emulated second, at normal speed
+const CYCLES_PER_SEC: u64 = 1 << 20;
+// The emulator state lives on the stack, which overflows in debug builds
+const STACK_SIZE: usize = 64 << 20;
+
+#[derive(Clone, Copy)]
+enum Check {
+    // Passed or Failed over the serial port
+    Serial,
+    // Mooneye registers signature
+    Fibonacci,
+    // Screen at LD B, B compared to the .png next to the ROM
+    Snapshot,
+}
+
+struct Suite {
+    name: &'static str,
+    check: Check,
+    // Emulated seconds
+    timeout: u64,
+}
+
+// Mirrors the config.sh of each suite
+const SUITES: [Suite; 3] = [
+    Suite {
+        name: "blarggs",
+        check: Check::Serial,
+        timeout: 60,
+    },
+    Suite {
+        name: "mattcurrie",
+        check: Check::Snapshot,
+        timeout: 10,
+    },
+    Suite {
+        name: "mooneye",
+        check: Check::Fibonacci,
+        timeout: 20,
+    },
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Pass,
+    Fail,
+    Skip,
+}
+
+struct Job {
+    suite: &'static Suite,
+    // Relative to the suite roms directory
+    name: String,
+    path: PathBuf,
+    skipped: bool,
+}
+
+struct TestResult {
+    suite: &'static str,
+    name: String,
+    status: Status,
+    message: String,
+    elapsed: Duration,
+}
+
+fn tests_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+// Shell-like glob, * and ? also match /
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        Some((b'?', rest)) => !text.is_empty() && glob_match(rest, &text[1..]),
+        Some((b'[', rest)) => {
+            let Some(end) = rest.iter().skip(1).position(|&c| c == b']').map(|i| i + 1) else {
+                return text.first() == Some(&b'[') && glob_match(rest, &text[1..]);
+            };
+            let Some((&c, text)) = text.split_first() else {
+                return false;
+            };
+            let (negate, set) = match rest[0] {
+                b'!' | b'^' => (true, &rest[1..end]),
+                _ => (false, &rest[..end]),
+            };
+
+            let mut matched = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == b'-' {
+                    matched |= (set[i]..=set[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    matched |= set[i] == c;
+                    i += 1;
+                }
+            }
+            matched != negate && glob_match(&rest[end + 1..], text)
+        }
+        Some((c, rest)) => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+// Same format as the one used by run_tests.sh
+fn load_skip_list() -> Vec<String> {
+    let Ok(contents) = std::fs::read_to_string(tests_dir().join("skip.list")) else {
+        return Vec::new();
+    };
+
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap().replace(' ', ""))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn is_skipped(skip_list: &[String], suite: &str, name: &str) -> bool {
+    let path = format!("{suite}/{name}");
+    let group = match path.rsplit_once('/') {
+        Some((group, _)) => group,
+        None => suite,
+    };
+
+    skip_list.iter().any(|skip| {
+        [suite, group, path.as_str()]
+            .iter()
+            .any(|key| glob_match(skip.as_bytes(), key.as_bytes()))
+    })
+}
+
+fn discover_roms(dir: &Path, roms: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.is_dir() {
+            discover_roms(&path, roms);
+        } else if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("gb") | Some("gbc")
+        ) {
+            roms.push(path);
+        }
+    }
+}
+
+fn discover_jobs(filters: &[String]) -> Vec<Job> {
+    let skip_list = load_skip_list();
+    let mut jobs = Vec::new();
+
+    for suite in SUITES.iter() {
+        let roms_dir = tests_dir().join(suite.name).join("roms");
+        let mut roms = Vec::new();
+        discover_roms(&roms_dir, &mut roms);
+        roms.sort();
+
+        for path in roms {
+            let name = path
+                .strip_prefix(&roms_dir)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/");
+            let full_name = format!("{}/{name}", suite.name);
+
+            if !filters.is_empty() && !filters.iter().any(|f| full_name.contains(f.as_str())) {
+                continue;
+            }
+
+            jobs.push(Job {
+                suite,
+                skipped: is_skipped(&skip_list, suite.name, &name),
+                name,
+                path,
+            });
+        }
+    }
+    jobs
+}
+
+fn compare_snapshot(frame: &Vbuf, reference: &Path) -> Result<(), String> {
+    let expected = image::open(reference)
+        .map_err(|e| format!("Could not open {}: {e}", reference.display()))?
+        .to_rgb8();
+
+    let differences = to_rgb(frame)
+        .chunks(3)
+        .zip(expected.pixels())
+        .filter(|(actual, expected)| *actual != expected.0)
+        .count();
+
+    match differences {
+        0 => Ok(()),
+        n => Err(format!("{n} pixels differ from the reference")),
+    }
+}
+
+fn run_rom(job: &Job) -> Result<(), String> {
+    let (video_sender, video_receiver): (_, Receiver<Vbuf>) = unbounded();
+    let (audio_sender, _) = unbounded();
+
+    let mut cpu = LR35902CPU::new(
+        Bus::new(
+            Cartridge::new(job.path.clone()),
+            BootRom::NONE,
+            video_sender,
+            audio_sender,
+        ),
+        false,
+        CPUSpeed::CUSTOM,
+    );
+
+    let condition = match job.suite.check {
+        Check::Serial => "SERIAL",
+        Check::Fibonacci => "FIB",
+        Check::Snapshot => "LDBB",
+    };
+    let mut condition: StopCondition = format!(
+        "{condition} OR CYCLES({})",
+        job.suite.timeout * CYCLES_PER_SEC
+    )
+    .parse()
+    .unwrap();
+
+    let start = Instant::now();
+    let mut last_frame = None;
+
+    loop {
+        cpu.step();
+
+        // Only the last frame is kept, not to run out of memory
+        while let Ok(frame) = video_receiver.try_recv() {
+            last_frame = Some(frame);
+        }
+
+        let Some(outcome) = condition.check(&cpu, start) else {
+            continue;
+        };
+
+        return match (outcome, job.suite.check) {
+            (StopOutcome::PASS, _) => Ok(()),
+            (StopOutcome::DONE, Check::Snapshot) => {
+                let frame = last_frame.ok_or("No frame was rendered")?;
+                compare_snapshot(&frame, &job.path.with_extension("png"))
+            }
+            (StopOutcome::TIMEOUT, _) => Err(format!(
+                "Timed out after {} emulated seconds",
+                job.suite.timeout
+            )),
+            (outcome, _) => {
+                let r = &cpu.registers;
+                let serial = String::from_utf8_lossy(cpu.bus.io.serial.output());
+                Err(format!(
+                    "{outcome:?}, B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X}\n{serial}",
+                    r.b, r.c, r.d, r.e, r.h, r.l
+                ))
+            }
+        };
+    }
+}
+
+fn run_job(job: Job) -> TestResult {
+    let start = Instant::now();
+
+    let (status, message) = match job.skipped {
+        true => (Status::Skip, String::new()),
+        false => {
+            // A thread per ROM, to catch panics
+            let path = job.path.clone();
+            let suite = job.suite;
+            let name = job.name.clone();
+            let result = thread::Builder::new()
+                .stack_size(STACK_SIZE)
+                .spawn(move || {
+                    run_rom(&Job {
+                        suite,
+                        name,
+                        path,
+                        skipped: false,
+                    })
+                })
+                .unwrap()
+                .join();
+
+            match result {
+                Ok(Ok(())) => (Status::Pass, String::new()),
+                Ok(Err(message)) => (Status::Fail, message),
+                Err(_) => (Status::Fail, "The emulator panicked".into()),
+            }
+        }
+    };
+
+    TestResult {
+        suite: job.suite.name,
+        name: job.name,
+        status,
+        message,
+        elapsed: start.elapsed(),
+    }
+}
+
+fn run_jobs(jobs: Vec<Job>) -> Vec<TestResult> {
+    let queue = Arc::new(Mutex::new(VecDeque::from(jobs)));
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+
+    let handles: Vec<_> = (0..workers)
+        .map(|_| {
+            let queue = queue.clone();
+            let results = results.clone();
+            thread::spawn(move || loop {
+                let Some(job) = queue.lock().unwrap().pop_front() else {
+                    break;
+                };
+
+                let result = run_job(job);
+                print_result(&result);
+                results.lock().unwrap().push(result);
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let mut results = Arc::into_inner(results).unwrap().into_inner().unwrap();
+    results.sort_by(|a, b| (a.suite, &a.name).cmp(&(b.suite, &b.name)));
+    results
+}
+
+fn print_result(result: &TestResult) {
+    let status = match result.status {
+        Status::Pass => "\x1b[0;32mok\x1b[0m",
+        Status::Fail => "\x1b[0;31mFAILED\x1b[0m",
+        Status::Skip => "\x1b[0;33mskipped\x1b[0m",
+    };
+    println!(
+        "rom {}/{} ... {status} ({:.2}s)",
+        result.suite,
+        result.name,
+        result.elapsed.as_secs_f32()
+    );
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn junit_report(results: &[TestResult]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+
+    for suite in SUITES.iter().map(|s| s.name) {
+        let cases: Vec<_> = results.iter().filter(|r| r.suite == suite).collect();
+        let count = |status| cases.iter().filter(|r| r.status == status).count();
+        let time: f32 = cases.iter().map(|r| r.elapsed.as_secs_f32()).sum();
+
+        _ = writeln!(
+            xml,
+            "  <testsuite name=\"{suite}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{time:.3}\">",
+            cases.len(),
+            count(Status::Fail),
+            count(Status::Skip),
+        );
+        for case in cases {
+            _ = write!(
+                xml,
+                "    <testcase classname=\"{suite}\" name=\"{}\" time=\"{:.3}\"",
+                escape_xml(&case.name),
+                case.elapsed.as_secs_f32()
+            );
+            match case.status {
+                Status::Pass => xml.push_str("/>\n"),
+                Status::Skip => xml.push_str(">\n      <skipped/>\n    </testcase>\n"),
+                Status::Fail => {
+                    _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{}\"/>\n    </testcase>",
+                        escape_xml(&case.message)
+                    );
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn json_report(results: &[TestResult]) -> String {
+    let tests: Vec<_> = results
+        .iter()
+        .map(|r| {
+            json!({
+                "suite": r.suite,
+                "name": r.name,
+                "status": match r.status {
+                    Status::Pass => "pass",
+                    Status::Fail => "fail",
+                    Status::Skip => "skip",
+                },
+                "message": r.message,
+                "elapsed": r.elapsed.as_secs_f64(),
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json!({ "tests": tests })).unwrap()
+}
+
+fn write_reports(results: &[TestResult]) {
+    let dir = match std::env::var_os("XENOGB_REPORT_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => Path::new(env!("CARGO_MANIFEST_DIR")).join("target/rom-tests"),
+    };
+
+    std::fs::create_dir_all(&dir).expect("Could not create the report dir");
+    std::fs::write(dir.join("report.xml"), junit_report(results))
+        .expect("Could not write the JUnit report");
+    std::fs::write(dir.join("report.json"), json_report(results))
+        .expect("Could not write the JSON report");
+    println!("\nReports written to {}", dir.display());
+}
+
+fn main() {
+    // Flags passed by cargo to every test binary are ignored
+    let filters: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .collect();
+
+    let jobs = discover_jobs(&filters);
+    println!("\nrunning {} ROMs", jobs.len());
+
+    let start = Instant::now();
+    let results = run_jobs(jobs);
+    write_reports(&results);
+
+    let failed: Vec<_> = results
+        .iter()
+        .filter(|r| r.status == Status::Fail)
+        .collect();
+    if !failed.is_empty() {
+        println!("\nfailures:");
+        for result in failed.iter() {
+            println!(
+                "    {}/{}: {}",
+                result.suite,
+                result.name,
+                result.message.trim_end()
+            );
+        }
+    }
+
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    println!(
+        "\nrom test result: {}. {} passed; {} failed; {} skipped; finished in {:.2}s\n",
+        match failed.is_empty() {
+            true => "ok",
+            false => "FAILED",
+        },
+        count(Status::Pass),
+        count(Status::Fail),
+        count(Status::Skip),
+        start.elapsed().as_secs_f32()
+    );
+
+    if !failed.is_empty() {
+        std::process::exit(1);
+    }
+}
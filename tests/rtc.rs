@@ -0,0 +1,173 @@
+mod common;
+
+use xenogb::core::cpu::{CPUSpeed, CLOCK_SPEED};
+use xenogb::core::mem::cartridge::Cartridge;
+use xenogb::core::mem::rtc::RtcClock;
+use xenogb::core::mem::save;
+
+const SECONDS: usize = 0x8;
+const MINUTES: usize = 0x9;
+const HOURS: usize = 0xa;
+const DAY_LOW: usize = 0xb;
+const DAY_HIGH: usize = 0xc;
+
+// MBC3+TIMER+RAM+BATTERY, its clock driven by the emulation
+fn cartridge(name: &str) -> Cartridge {
+    let dir = common::temp_dir(&format!("rtc_{name}"));
+    let mut rom = common::build_rom(&[]);
+    rom[0x147] = 0x10;
+    rom[0x149] = 0x02;
+
+    let mut cartridge = Cartridge::load(common::write_rom(&dir, &rom)).unwrap();
+    cartridge.set_rtc_clock(RtcClock::EMULATED);
+    cartridge.write(0x0000, 0x0a);
+    cartridge
+}
+
+fn write(cartridge: &mut Cartridge, register: usize, value: u8) {
+    cartridge.write(0x4000, register as u8);
+    cartridge.write(0xa000, value);
+}
+
+fn latch(cartridge: &mut Cartridge) {
+    cartridge.write(0x6000, 0);
+    cartridge.write(0x6000, 1);
+}
+
+fn read(cartridge: &mut Cartridge, register: usize) -> u8 {
+    cartridge.write(0x4000, register as u8);
+    cartridge.read(0xa000)
+}
+
+fn run_seconds(cartridge: &mut Cartridge, seconds: u32, speed: CPUSpeed) {
+    let per_cycle = match speed {
+        CPUSpeed::DOUBLE => 2,
+        _ => 4,
+    };
+    for _ in 0..seconds * CLOCK_SPEED / per_cycle {
+        cartridge.tick(speed);
+    }
+}
+
+#[test]
+fn emulated_clock() {
+    let mut cartridge = cartridge("emulated");
+    run_seconds(&mut cartridge, 1, CPUSpeed::NORMAL);
+    // Read from the latched registers
+    assert_eq!(read(&mut cartridge, SECONDS), 0);
+    latch(&mut cartridge);
+    assert_eq!(read(&mut cartridge, SECONDS), 1);
+
+    // Twice as many cycles in double speed
+    run_seconds(&mut cartridge, 2, CPUSpeed::DOUBLE);
+    latch(&mut cartridge);
+    assert_eq!(read(&mut cartridge, SECONDS), 3);
+}
+
+#[test]
+fn seconds_write() {
+    // Writing the seconds restarts the current second
+    let mut cartridge = cartridge("seconds_write");
+    for _ in 0..CLOCK_SPEED / 4 / 2 {
+        cartridge.tick(CPUSpeed::NORMAL);
+    }
+    write(&mut cartridge, SECONDS, 10);
+    let ticks = CLOCK_SPEED / 4 * 3 / 5;
+    for _ in 0..ticks {
+        cartridge.tick(CPUSpeed::NORMAL);
+    }
+    latch(&mut cartridge);
+    assert_eq!(read(&mut cartridge, SECONDS), 10);
+
+    for _ in ticks..CLOCK_SPEED / 4 {
+        cartridge.tick(CPUSpeed::NORMAL);
+    }
+    latch(&mut cartridge);
+    assert_eq!(read(&mut cartridge, SECONDS), 11);
+}
+
+#[test]
+fn reset() {
+    // The clock and its registers survive a reset
+    let mut cartridge = cartridge("reset");
+    write(&mut cartridge, MINUTES, 5);
+    cartridge.reset();
+    cartridge.write(0x0000, 0x0a);
+    run_seconds(&mut cartridge, 3, CPUSpeed::NORMAL);
+    latch(&mut cartridge);
+    assert_eq!(read(&mut cartridge, SECONDS), 3);
+    assert_eq!(read(&mut cartridge, MINUTES), 5);
+}
+
+#[test]
+fn halt() {
+    let mut cartridge = cartridge("halt");
+    write(&mut cartridge, DAY_HIGH, 0x40);
+    run_seconds(&mut cartridge, 2, CPUSpeed::NORMAL);
+    latch(&mut cartridge);
+    assert_eq!(read(&mut cartridge, SECONDS), 0);
+    assert_eq!(read(&mut cartridge, DAY_HIGH), 0x40);
+
+    write(&mut cartridge, DAY_HIGH, 0x00);
+    run_seconds(&mut cartridge, 1, CPUSpeed::NORMAL);
+    latch(&mut cartridge);
+    assert_eq!(read(&mut cartridge, SECONDS), 1);
+}
+
+#[test]
+fn day_carry() {
+    let mut cartridge = cartridge("carry");
+    write(&mut cartridge, SECONDS, 59);
+    write(&mut cartridge, MINUTES, 59);
+    write(&mut cartridge, HOURS, 23);
+    write(&mut cartridge, DAY_LOW, 0xff);
+    write(&mut cartridge, DAY_HIGH, 0x01);
+
+    run_seconds(&mut cartridge, 1, CPUSpeed::NORMAL);
+    latch(&mut cartridge);
+    let registers: Vec<u8> = (SECONDS..=DAY_HIGH)
+        .map(|r| read(&mut cartridge, r))
+        .collect();
+    assert_eq!(registers, [0, 0, 0, 0, 0x80]);
+
+    // The carry is kept until cleared
+    run_seconds(&mut cartridge, 1, CPUSpeed::NORMAL);
+    latch(&mut cartridge);
+    assert_eq!(read(&mut cartridge, DAY_HIGH), 0x80);
+    write(&mut cartridge, DAY_HIGH, 0x00);
+    assert_eq!(read(&mut cartridge, DAY_HIGH), 0x00);
+}
+
+#[test]
+fn out_of_range() {
+    // Counts up to 63 then wraps, without carrying into the minutes
+    let mut cartridge = cartridge("range");
+    write(&mut cartridge, SECONDS, 62);
+    run_seconds(&mut cartridge, 2, CPUSpeed::NORMAL);
+    latch(&mut cartridge);
+    assert_eq!(read(&mut cartridge, SECONDS), 0);
+    assert_eq!(read(&mut cartridge, MINUTES), 0);
+}
+
+#[test]
+fn persisted() {
+    let mut cartridge = cartridge("persisted");
+    write(&mut cartridge, HOURS, 5);
+    write(&mut cartridge, DAY_HIGH, 0x41);
+    latch(&mut cartridge);
+    let data = cartridge.export_save().unwrap();
+
+    let (_, footer) = save::decode(&data);
+    let footer = footer.unwrap();
+    assert_eq!(footer.registers, [0, 0, 5, 0, 0x41]);
+    assert_eq!(footer.latched, footer.registers);
+
+    // An emulated clock does not count the time elapsed since the save
+    let mut other = self::cartridge("restored");
+    let mut old = footer;
+    old.timestamp -= 3600;
+    other.import_save(&save::encode(&data[..0x2000], Some(&old)));
+    latch(&mut other);
+    assert_eq!(read(&mut other, HOURS), 5);
+    assert_eq!(read(&mut other, DAY_HIGH), 0x41);
+}
@@ -0,0 +1,239 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use xenogb::core::mem::cartridge::Cartridge;
+use xenogb::core::mem::save::{self, RtcFooter};
+
+const SRAM_SIZE: usize = 0x8000;
+
+// MBC3+TIMER+RAM+BATTERY with 32 KiB of RAM, or MBC5+RAM+BATTERY
+fn build_rom(cartridge_type: u8) -> Vec<u8> {
+    let mut rom = common::build_rom(&[]);
+    rom[0x134..0x138].copy_from_slice(b"SAVE");
+    rom[0x147] = cartridge_type;
+    rom[0x149] = 0x03;
+    rom
+}
+
+fn dir(name: &str) -> PathBuf {
+    common::temp_dir(&format!("saves_{name}"))
+}
+
+fn write_rom(dir: &Path, cartridge_type: u8) -> PathBuf {
+    common::write_rom(dir, &build_rom(cartridge_type))
+}
+
+// Selects an SRAM bank, or an RTC register from 0x8
+fn select(cartridge: &mut Cartridge, bank: u8) {
+    cartridge.write(0x0000, 0x0a);
+    cartridge.write(0x4000, bank);
+}
+
+#[test]
+fn footers() {
+    let footer = RtcFooter {
+        registers: [1, 2, 3, 4, 1],
+        latched: [5, 6, 7, 8, 0],
+        timestamp: 1_700_000_000,
+    };
+    let data = save::encode(&[0x42; SRAM_SIZE], Some(&footer));
+    assert_eq!(data.len(), SRAM_SIZE + 48);
+    assert_eq!(save::decode(&data), (&[0x42; SRAM_SIZE][..], Some(footer)));
+
+    // VBA's 32 bits timestamps
+    let data = &data[..SRAM_SIZE + 44];
+    assert_eq!(save::decode(data).1, Some(footer));
+
+    // Timer only cartridges, and no footer at all
+    assert_eq!(save::decode(&data[SRAM_SIZE..]).0, b"");
+    assert_eq!(save::decode(&[0x42; SRAM_SIZE]).1, None);
+}
+
+#[test]
+fn paths() {
+    let rom = Path::new("roms/game.gb");
+    assert_eq!(save::path(rom, None), Path::new("roms/game.sav"));
+    assert_eq!(
+        save::path(rom, Some(Path::new("saves"))),
+        Path::new("saves/game.sav")
+    );
+    assert_eq!(save::legacy_path(rom), Path::new("roms/game.gbsave"));
+}
+
+#[test]
+fn save_dir() {
+    let dir = dir("save_dir");
+    let rom_path = write_rom(&dir, 0x1b);
+    let save_dir = dir.join("saves");
+
+    let mut cartridge = Cartridge::load(rom_path.clone()).unwrap();
+    cartridge.set_save_dir(&save_dir);
+    select(&mut cartridge, 1);
+    cartridge.write(0xa000, 0x99);
+    cartridge.save();
+
+    let save_path = save_dir.join("game.sav");
+    assert_eq!(cartridge.save_path(), save_path);
+    let data = std::fs::read(&save_path).unwrap();
+    assert_eq!(data.len(), SRAM_SIZE);
+    assert_eq!(data[0x2000], 0x99);
+    assert!(!dir.join("game.sav").exists());
+
+    let mut cartridge = Cartridge::load(rom_path.clone()).unwrap();
+    cartridge.set_save_dir(&save_dir);
+    select(&mut cartridge, 1);
+    assert_eq!(cartridge.read(0xa000), 0x99);
+
+    // Saves that cannot be written are reported, not fatal
+    std::fs::write(dir.join("file"), b"").unwrap();
+    cartridge.set_save_dir(&dir.join("file"));
+    cartridge.save();
+}
+
+#[test]
+fn legacy_saves() {
+    let dir = dir("legacy");
+    let rom_path = write_rom(&dir, 0x1b);
+    let mut sram = vec![0x00; SRAM_SIZE];
+    sram[0] = 0x77;
+    std::fs::write(dir.join("game.gbsave"), &sram).unwrap();
+
+    let mut cartridge = Cartridge::load(rom_path).unwrap();
+    select(&mut cartridge, 0);
+    assert_eq!(cartridge.read(0xa000), 0x77);
+
+    // Written back as a .sav
+    cartridge.save();
+    assert_eq!(std::fs::read(dir.join("game.sav")).unwrap(), sram);
+}
+
+#[test]
+fn rtc_footer() {
+    let dir = dir("rtc");
+    let rom_path = write_rom(&dir, 0x10);
+
+    // A save from another emulator, made a day ago
+    let footer = RtcFooter {
+        registers: [10, 20, 3, 0, 0],
+        latched: [10, 20, 3, 0, 0],
+        timestamp: chrono::Utc::now().timestamp() - 24 * 3600,
+    };
+    let mut sram = vec![0x00; SRAM_SIZE];
+    sram[0x10] = 0x55;
+    std::fs::write(dir.join("game.sav"), save::encode(&sram, Some(&footer))).unwrap();
+
+    let mut cartridge = Cartridge::load(rom_path).unwrap();
+    select(&mut cartridge, 0);
+    assert_eq!(cartridge.read(0xa010), 0x55);
+    select(&mut cartridge, 0xa);
+    assert_eq!(cartridge.read(0xa000), 3);
+
+    // The clock kept counting since
+    cartridge.write(0x6000, 0);
+    cartridge.write(0x6000, 1);
+    assert_eq!(cartridge.read(0xa000), 3);
+    select(&mut cartridge, 0xb);
+    assert_eq!(cartridge.read(0xa000), 1);
+
+    let data = cartridge.export_save().unwrap();
+    assert_eq!(data.len(), SRAM_SIZE + 48);
+    let (_, exported) = save::decode(&data);
+    let exported = exported.unwrap();
+    assert_eq!(exported.latched[2..4], [3, 1]);
+    assert!(exported.timestamp >= footer.timestamp + 24 * 3600);
+
+    // Latching the clock changes nothing saved, setting it does
+    assert!(!cartridge.is_dirty());
+    cartridge.write(0xa000, 2);
+    assert!(cartridge.is_dirty());
+}
+
+#[test]
+fn sizes() {
+    let dir = dir("sizes");
+
+    // MBC1+RAM+BATTERY with 2 KiB of RAM
+    let mut rom = build_rom(0x03);
+    rom[0x149] = 0x01;
+    let mut cartridge = Cartridge::load(common::write_rom(&dir, &rom)).unwrap();
+    assert_eq!(cartridge.export_save().unwrap().len(), 0x800);
+    assert_eq!(cartridge.sram().unwrap().len(), 0x800);
+
+    // MBC3+TIMER+BATTERY, only the clock is saved
+    let mut rom = build_rom(0x0f);
+    rom[0x149] = 0x00;
+    let mut cartridge = Cartridge::load(common::write_rom(&dir, &rom)).unwrap();
+    let data = cartridge.export_save().unwrap();
+    assert_eq!(data.len(), 48);
+    assert!(save::decode(&data).1.is_some());
+}
+
+#[test]
+fn autosave() {
+    let dir = dir("autosave");
+    let rom_path = write_rom(&dir, 0x1b);
+    let save_path = dir.join("game.sav");
+
+    let mut cartridge = Cartridge::load(rom_path).unwrap();
+    let start = Instant::now();
+    cartridge.autosave_at(start);
+    assert!(!cartridge.is_dirty());
+
+    // Writes ignored by the disabled RAM are not saved
+    cartridge.write(0xa000, 0x34);
+    cartridge.autosave_at(start);
+    assert!(!cartridge.is_dirty());
+
+    select(&mut cartridge, 0);
+    cartridge.write(0xa000, 0x12);
+    assert!(cartridge.is_dirty());
+    cartridge.autosave_at(start);
+    cartridge.autosave_at(start + Duration::from_millis(2900));
+    assert!(!save_path.exists());
+
+    // Saved once the game stopped writing for a while
+    cartridge.autosave_at(start + Duration::from_secs(3));
+    assert!(!cartridge.is_dirty());
+    assert_eq!(std::fs::read(&save_path).unwrap()[0], 0x12);
+    assert!(!dir.join("game.sav.tmp").exists());
+
+    // Or while it keeps writing, every so often
+    for secs in 10..40 {
+        cartridge.write(0xa000, secs as u8);
+        cartridge.autosave_at(start + Duration::from_secs(secs));
+    }
+    assert!(cartridge.is_dirty());
+    cartridge.write(0xa000, 40);
+    cartridge.autosave_at(start + Duration::from_secs(40));
+    assert!(!cartridge.is_dirty());
+    assert_eq!(std::fs::read(&save_path).unwrap()[0], 40);
+}
+
+#[test]
+fn headless() {
+    let dir = dir("headless");
+    let mut rom = common::build_rom(&[
+        0x3e, 0x0a, // LD A, 0x0a
+        0xea, 0x00, 0x00, // LD (0x0000), A: enable the RAM
+        0x3e, 0x42, // LD A, 0x42
+        0xea, 0x00, 0xa0, // LD (0xa000), A
+        0x18, 0xfe, // JR -2
+    ]);
+    rom[0x147] = 0x1b;
+    rom[0x149] = 0x03;
+    let rom_path = common::write_rom(&dir, &rom);
+
+    // Saved when the run stops, long before the autosave
+    let status = Command::new(env!("CARGO_BIN_EXE_xenogb"))
+        .current_dir(&dir)
+        .arg("--cartridge")
+        .arg(rom_path)
+        .args(["--headless", "--stop-condition", "FRAMES(3)"])
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(2));
+    assert_eq!(std::fs::read(dir.join("game.sav")).unwrap()[0], 0x42);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use common::LOOP;
+
+fn xenogb(dir: &Path, args: &[&str]) -> Output {
+    let rom_path = common::write_rom(dir, &common::build_rom(&LOOP));
+    // Run from the test directory, which gets the logs
+    Command::new(env!("CARGO_BIN_EXE_xenogb"))
+        .current_dir(dir)
+        .arg("--cartridge")
+        .arg(rom_path)
+        .args(["--headless", "--test-out-dir"])
+        .arg(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn screenshot_at() {
+    let dir = common::temp_dir("screenshot_at");
+    let output = xenogb(
+        &dir,
+        &["--stop-condition", "FRAMES(3)", "--screenshot-at", "1,3"],
+    );
+    // Frame limits end the run as a timeout
+    assert_eq!(output.status.code(), Some(2));
+
+    for frame in [1, 3] {
+        let png = image::open(dir.join(format!("frame_{frame}.png"))).unwrap();
+        assert_eq!((png.width(), png.height()), (160, 144));
+    }
+    assert!(!dir.join("frame_2.png").exists());
+}
+
+#[test]
+fn screenshot_at_frame_0() {
+    // Never drawn, so rejected instead of silently ignored
+    let dir = common::temp_dir("screenshot_at_0");
+    let output = xenogb(
+        &dir,
+        &["--stop-condition", "FRAMES(1)", "--screenshot-at", "0,1"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--screenshot-at"));
+    assert!(!dir.join("frame_1.png").exists());
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use common::{run_until_ldbb, LD_B_B};
+use xenogb::core::cpu::instructions::CPURegisterId;
+use xenogb::core::cpu::interrupts::InterruptFlags;
+use xenogb::core::cpu::{CPUSpeed, LR35902CPU};
+use xenogb::core::io::joypad::JOYPAD_INPUT;
+
+const MAX_STEPS: usize = 100_000;
+
+fn setup_cpu(name: &str, code: &[u8], cgb: bool) -> LR35902CPU {
+    let mut rom = common::build_rom(code);
+    if cgb {
+        rom[0x143] = 0x80;
+    }
+    let dir = common::temp_dir(&format!("stop_{name}"));
+    common::setup_cpu(common::write_rom(&dir, &rom))
+}
+
+#[test]
+fn speed_switch() {
+    let mut cpu = setup_cpu(
+        "speed_switch",
+        &[
+            0x3e, 0x01, // LD A, 1
+            0xe0, 0x4d, // LDH (KEY1), A
+            0x10, 0x00, // STOP
+            0xf0, 0x4d, // LDH A, (KEY1)
+            0x47, // LD B, A
+            0xf0, 0x04, // LDH A, (DIV)
+            0x4f, // LD C, A
+            LD_B_B,
+        ],
+        true,
+    );
+
+    assert!(run_until_ldbb(&mut cpu, MAX_STEPS));
+    assert!(matches!(cpu.clock.speed_mode, CPUSpeed::DOUBLE));
+    assert_eq!(cpu.get_register(&CPURegisterId::B), 0xfe);
+    // DIV was reset, then kept counting during the ~2050 M-cycles pause
+    assert_eq!(cpu.get_register(&CPURegisterId::C), 0x20);
+}
+
+#[test]
+fn speed_switch_dmg() {
+    let mut cpu = setup_cpu(
+        "speed_switch_dmg",
+        &[
+            0x3e, 0x01, // LD A, 1
+            0xe0, 0x4d, // LDH (KEY1), A
+            0x3e, 0x10, // LD A, 0x10
+            0xe0, 0x00, // LDH (P1), A
+            0x10, 0x00, // STOP
+            0x00, // NOP
+            LD_B_B,
+        ],
+        false,
+    );
+
+    // No speed switch on DMG, the CPU enters STOP mode instead
+    assert!(!run_until_ldbb(&mut cpu, 1000));
+    assert!(cpu.stopped);
+    assert!(matches!(cpu.clock.speed_mode, CPUSpeed::CUSTOM));
+}
+
+#[test]
+fn stop_mode_joypad_wakeup() {
+    let mut cpu = setup_cpu(
+        "joypad_wakeup",
+        &[
+            0x3e, 0x10, // LD A, 0x10 (select action buttons)
+            0xe0, 0x00, // LDH (P1), A
+            0x10, 0x00, // STOP
+            0xf0, 0x04, // LDH A, (DIV)
+            0x47, // LD B, A
+            LD_B_B,
+        ],
+        false,
+    );
+
+    assert!(!run_until_ldbb(&mut cpu, 1000));
+    assert!(cpu.stopped);
+
+    // Pressing a button of an unselected group doesn't wake the CPU up
+    cpu.bus
+        .io
+        .joypad
+        .press(JOYPAD_INPUT::DOWN, &mut cpu.bus.interrupts);
+    assert!(!run_until_ldbb(&mut cpu, 1000));
+
+    cpu.bus
+        .io
+        .joypad
+        .press(JOYPAD_INPUT::A, &mut cpu.bus.interrupts);
+    assert!(run_until_ldbb(&mut cpu, MAX_STEPS));
+    assert!(!cpu.stopped);
+    // DIV was reset when entering STOP mode and frozen since
+    assert_eq!(cpu.get_register(&CPURegisterId::B), 0x00);
+}
+
+#[test]
+fn button_held_interrupt_pending() {
+    let mut cpu = setup_cpu(
+        "held_int_pending",
+        &[
+            0xf3, // DI
+            0x3e, 0x10, // LD A, 0x10
+            0xe0, 0x00, // LDH (P1), A
+            0x3e, 0x04, // LD A, TIMER
+            0xe0, 0xff, // LDH (IE), A
+            0xe0, 0x0f, // LDH (IF), A
+            0x10, // STOP, 1-byte opcode
+            0x0c, // INC C
+            LD_B_B,
+        ],
+        false,
+    );
+    cpu.bus
+        .io
+        .joypad
+        .press(JOYPAD_INPUT::A, &mut cpu.bus.interrupts);
+    let c = cpu.get_register(&CPURegisterId::C);
+
+    assert!(run_until_ldbb(&mut cpu, MAX_STEPS));
+    assert!(!cpu.stopped);
+    assert_eq!(cpu.get_register(&CPURegisterId::C), c + 1);
+}
+
+#[test]
+fn button_held_halt() {
+    let mut cpu = setup_cpu(
+        "held_halt",
+        &[
+            0xf3, // DI
+            0x3e, 0x10, // LD A, 0x10
+            0xe0, 0x00, // LDH (P1), A
+            0x3e, 0x04, // LD A, TIMER
+            0xe0, 0xff, // LDH (IE), A
+            0xaf, // XOR A
+            0xe0, 0x0f, // LDH (IF), A
+            0x10, 0x0c, // STOP, 2-byte opcode
+            0x14, // INC D
+            LD_B_B,
+        ],
+        false,
+    );
+    cpu.bus
+        .io
+        .joypad
+        .press(JOYPAD_INPUT::A, &mut cpu.bus.interrupts);
+    let c = cpu.get_register(&CPURegisterId::C);
+    let d = cpu.get_register(&CPURegisterId::D);
+
+    // STOP behaves as HALT, waiting for an interrupt
+    assert!(!run_until_ldbb(&mut cpu, 1000));
+    assert!(cpu.halt);
+    assert!(!cpu.stopped);
+
+    cpu.bus.interrupts.request(InterruptFlags::TIMER);
+    assert!(run_until_ldbb(&mut cpu, MAX_STEPS));
+    assert_eq!(cpu.get_register(&CPURegisterId::C), c);
+    assert_eq!(cpu.get_register(&CPURegisterId::D), d + 1);
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::time::Instant;
+
+use common::{LD_B_B, LOOP};
+use xenogb::core::cpu::LR35902CPU;
+use xenogb::core::stop_condition::{StopCondition, StopOutcome};
+
+const MAX_STEPS: usize = 100_000;
+
+fn setup_cpu(name: &str, code: &[u8]) -> LR35902CPU {
+    let dir = common::temp_dir(&format!("stop_condition_{name}"));
+    common::setup_cpu(common::write_rom(&dir, &common::build_rom(code)))
+}
+
+// Loads the mooneye registers signature, then hits LD B, B
+fn fib_code(values: [u8; 6]) -> Vec<u8> {
+    let mut code = Vec::new();
+    for (opcode, value) in [0x06, 0x0e, 0x16, 0x1e, 0x26, 0x2e].iter().zip(values) {
+        code.extend([*opcode, value]);
+    }
+    code.push(LD_B_B);
+    code.extend(LOOP);
+    code
+}
+
+// Sends the text over serial, with the internal clock
+fn serial_code(text: &[u8]) -> Vec<u8> {
+    let mut code = Vec::new();
+    for byte in text {
+        code.extend([
+            0x3e, *byte, // LD A, byte
+            0xe0, 0x01, // LDH (SB), A
+            0x3e, 0x81, // LD A, 0x81
+            0xe0, 0x02, // LDH (SC), A
+        ]);
+    }
+    code.extend(LOOP);
+    code
+}
+
+fn run(cpu: &mut LR35902CPU, condition: &str) -> Option<StopOutcome> {
+    let mut condition: StopCondition = condition.parse().unwrap();
+    let start = Instant::now();
+
+    for _ in 0..MAX_STEPS {
+        cpu.step();
+        if let Some(outcome) = condition.check(cpu, start) {
+            return Some(outcome);
+        }
+    }
+    None
+}
+
+#[test]
+fn parse() {
+    for condition in [
+        "ldbb",
+        "timer(10)",
+        "frames(600)",
+        "cycles(0x100000)",
+        "pc(0x150)",
+        "mem(0xc000=0x42)",
+        "serial",
+        "fib | frames(10)",
+        "ldbb & fib or timer(5)",
+    ] {
+        assert!(condition.parse::<StopCondition>().is_ok(), "{condition}");
+    }
+
+    for condition in [
+        "",
+        "foo",
+        "pc(0x10000)",
+        "mem(0xc000)",
+        "frames(x)",
+        "fib and",
+    ] {
+        assert!(condition.parse::<StopCondition>().is_err(), "{condition}");
+    }
+}
+
+#[test]
+fn fibonacci() {
+    let mut cpu = setup_cpu("fib_pass", &fib_code([3, 5, 8, 13, 21, 34]));
+    assert_eq!(run(&mut cpu, "FIB"), Some(StopOutcome::PASS));
+
+    let mut cpu = setup_cpu("fib_fail", &fib_code([0x42; 6]));
+    assert_eq!(run(&mut cpu, "FIB"), Some(StopOutcome::FAIL));
+
+    // Any other signature is not a mooneye result
+    let mut cpu = setup_cpu("fib_none", &fib_code([0; 6]));
+    assert_eq!(
+        run(&mut cpu, "FIB OR CYCLES(1000)"),
+        Some(StopOutcome::TIMEOUT)
+    );
+}
+
+#[test]
+fn serial() {
+    let mut cpu = setup_cpu("serial_pass", &serial_code(b"Test\nPassed\n"));
+    assert_eq!(run(&mut cpu, "SERIAL"), Some(StopOutcome::PASS));
+    // Stopped as soon as the word was sent
+    assert_eq!(cpu.bus.io.serial.output(), b"Test\nPassed");
+
+    let mut cpu = setup_cpu("serial_fail", &serial_code(b"Failed #1"));
+    assert_eq!(
+        run(&mut cpu, "SERIAL | CYCLES(10000)"),
+        Some(StopOutcome::FAIL)
+    );
+}
+
+#[test]
+fn composition() {
+    let code = fib_code([3, 5, 8, 13, 21, 34]);
+
+    // AND waits for every condition, and reports the worst outcome
+    let mut cpu = setup_cpu("and", &code);
+    assert_eq!(
+        run(&mut cpu, "FIB AND CYCLES(500)"),
+        Some(StopOutcome::TIMEOUT)
+    );
+    assert!(cpu.total_cycles >= 500);
+
+    // OR stops on the first one met
+    let mut cpu = setup_cpu("or", &code);
+    assert_eq!(run(&mut cpu, "FIB OR CYCLES(500)"), Some(StopOutcome::PASS));
+    assert!(cpu.total_cycles < 500);
+
+    let mut cpu = setup_cpu("pc", &code);
+    assert_eq!(run(&mut cpu, "PC(0x15C)"), Some(StopOutcome::DONE));
+}
+
+#[test]
+fn exit_codes() {
+    assert_eq!(StopOutcome::DONE.exit_code(), 0);
+    assert_eq!(StopOutcome::PASS.exit_code(), 0);
+    assert_eq!(StopOutcome::FAIL.exit_code(), 1);
+    assert_eq!(StopOutcome::TIMEOUT.exit_code(), 2);
+}
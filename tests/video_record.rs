@@ -0,0 +1,93 @@
+mod common;
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use common::LOOP;
+use xenogb::core::io::video::capture::VideoFormat;
+
+const FRAMES: u64 = 10;
+
+fn record(name: &str, format: VideoFormat, audio: bool) -> PathBuf {
+    let dir = common::temp_dir(&format!("video_{name}"));
+    let mut cpu = common::setup_cpu(common::write_rom(&dir, &common::build_rom(&LOOP)));
+
+    let path = dir.join("video");
+    cpu.bus
+        .io
+        .start_video_recording(&path, format, audio)
+        .unwrap();
+    while cpu.bus.io.ppu.frames < FRAMES {
+        cpu.step();
+    }
+    cpu.bus.io.stop_video_recording();
+
+    path
+}
+
+#[test]
+fn record_gif() {
+    let path = record("gif", VideoFormat::GIF, true);
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (160, 144));
+
+    // Every other frame, their delays adding up to the emulated time
+    let (mut frames, mut delay) = (0, 0);
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        assert_eq!((frame.width, frame.height), (160, 144));
+        frames += 1;
+        delay += frame.delay;
+    }
+    assert_eq!(frames, FRAMES / 2);
+    assert_eq!(delay, (FRAMES as f64 * 100.0 / 59.7275).round() as u16);
+    assert!(path.with_extension("wav").exists());
+}
+
+#[test]
+fn single_audio_recorder() {
+    let dir = common::temp_dir("video_single_recorder");
+    let mut cpu = common::setup_cpu(common::write_rom(&dir, &common::build_rom(&LOOP)));
+    let io = &mut cpu.bus.io;
+
+    // The audio is already recorded on its own
+    io.apu
+        .start_recording(&dir.join("audio.wav"), false)
+        .unwrap();
+    let err = io
+        .start_video_recording(&dir.join("video"), VideoFormat::PNG, true)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+    io.start_video_recording(&dir.join("video"), VideoFormat::PNG, false)
+        .unwrap();
+    io.stop_video_recording();
+    io.apu.stop_recording();
+
+    // Stopping the standalone recording leaves the video's audio alone
+    io.start_video_recording(&dir.join("video_audio"), VideoFormat::PNG, true)
+        .unwrap();
+    assert!(io
+        .apu
+        .start_recording(&dir.join("audio.wav"), false)
+        .is_err());
+    io.stop_audio_recording();
+    assert!(io
+        .apu
+        .start_recording(&dir.join("audio.wav"), false)
+        .is_err());
+    io.stop_video_recording();
+    io.apu
+        .start_recording(&dir.join("audio.wav"), false)
+        .unwrap();
+}
+
+#[test]
+fn record_png_sequence() {
+    let path = record("png", VideoFormat::PNG, false);
+
+    let frames = std::fs::read_dir(&path).unwrap().count();
+    assert_eq!(frames as u64, FRAMES);
+    assert!(path.join("000000.png").exists());
+}
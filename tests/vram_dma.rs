@@ -0,0 +1,150 @@
+mod common;
+
+use common::LOOP;
+use xenogb::core::cpu::instructions::CPURegisterId;
+use xenogb::core::cpu::LR35902CPU;
+
+const SRC: u16 = 0xc000;
+const DST: u16 = 0x8800;
+const HDMA5: u16 = 0xff55;
+const LCDC: u16 = 0xff40;
+// More than a frame
+const MAX_CYCLES: usize = 20_000;
+
+// Starts a 2 blocks general DMA, then loops
+const GENERAL: [u8; 6] = [
+    0x3e, 0x01, // LD A, 0x01
+    0xe0, 0x55, // LDH (HDMA5), A
+    0x18, 0xfe, // JR -2
+];
+
+fn setup_cpu(name: &str, code: &[u8], lcd_on: bool) -> LR35902CPU {
+    let mut rom = common::build_rom(code);
+    rom[0x143] = 0x80;
+    let dir = common::temp_dir(&format!("vram_dma_{name}"));
+    let mut cpu = common::setup_cpu(common::write_rom(&dir, &rom));
+
+    cpu.bus.write(LCDC, if lcd_on { 0x80 } else { 0x00 });
+    for i in 0..0x40 {
+        cpu.bus.write(SRC + i, i as u8 + 1);
+    }
+    for (addr, value) in [
+        (0xff51, SRC >> 8),
+        (0xff52, SRC),
+        (0xff53, DST >> 8),
+        (0xff54, DST),
+    ] {
+        cpu.bus.write(addr, value as u8);
+    }
+    cpu
+}
+
+// VRAM bytes copied so far
+fn copied(cpu: &LR35902CPU) -> usize {
+    (0..0x40).take_while(|i| cpu.bus.read(DST + i) != 0).count()
+}
+
+fn run_until_hblank_block(cpu: &mut LR35902CPU) {
+    let before = copied(cpu);
+    for _ in 0..MAX_CYCLES {
+        cpu.tick_cycle();
+        if copied(cpu) > before && !cpu.bus.vram_dma_stalling() {
+            return;
+        }
+    }
+    panic!("No HBlank block copied");
+}
+
+#[test]
+fn general_timing() {
+    let mut cpu = setup_cpu("general", &GENERAL, false);
+    // JP 0x150, LD A
+    cpu.tick();
+    cpu.tick();
+
+    // 2 bytes are copied per M-cycle, starting with the write's own M-cycle,
+    // the CPU stalls until the end
+    assert_eq!(cpu.tick(), 3 + 15);
+    assert!(!cpu.bus.vram_dma_stalling());
+    assert_eq!(copied(&cpu), 0x20);
+    assert_eq!(cpu.bus.read(HDMA5), 0xff);
+    assert_eq!(cpu.get_register(&CPURegisterId::A), 0x01);
+}
+
+#[test]
+fn general_timing_double_speed() {
+    let mut cpu = setup_cpu(
+        "general_double",
+        &[
+            0x3e, 0x01, // LD A, 1
+            0xe0, 0x4d, // LDH (KEY1), A
+            0x10, 0x00, // STOP
+            0x3e, 0x01, // LD A, 0x01
+            0xe0, 0x55, // LDH (HDMA5), A
+            0x18, 0xfe, // JR -2
+        ],
+        false,
+    );
+    // Up to the second LD A
+    for _ in 0..5 {
+        cpu.tick();
+    }
+
+    // A single byte per M-cycle in double speed
+    assert_eq!(cpu.tick(), 3 + 31);
+    assert_eq!(copied(&cpu), 0x20);
+}
+
+#[test]
+fn hblank_blocks() {
+    let mut cpu = setup_cpu("hblank", &LOOP, true);
+    cpu.bus.write(HDMA5, 0x82);
+
+    // Nothing copied before the next HBlank
+    assert_eq!(copied(&cpu), 0);
+    assert_eq!(cpu.bus.read(HDMA5), 0x02);
+
+    // One block per HBlank, the remaining blocks minus one read back
+    run_until_hblank_block(&mut cpu);
+    assert_eq!(copied(&cpu), 0x10);
+    assert_eq!(cpu.bus.read(HDMA5), 0x01);
+    run_until_hblank_block(&mut cpu);
+    assert_eq!(copied(&cpu), 0x20);
+    assert_eq!(cpu.bus.read(HDMA5), 0x00);
+    run_until_hblank_block(&mut cpu);
+    assert_eq!(copied(&cpu), 0x30);
+    assert_eq!(cpu.bus.read(HDMA5), 0xff);
+}
+
+#[test]
+fn hblank_lcd_off() {
+    let mut cpu = setup_cpu("hblank_lcd_off", &LOOP, false);
+    cpu.bus.write(HDMA5, 0x81);
+
+    // The first block is copied right away, the next one waits for an HBlank
+    for _ in 0..8 {
+        cpu.tick_cycle();
+    }
+    assert_eq!(copied(&cpu), 0x10);
+    for _ in 0..1000 {
+        cpu.tick_cycle();
+    }
+    assert_eq!(copied(&cpu), 0x10);
+    assert_eq!(cpu.bus.read(HDMA5), 0x00);
+}
+
+#[test]
+fn hblank_cancel() {
+    let mut cpu = setup_cpu("hblank_cancel", &LOOP, true);
+    cpu.bus.write(HDMA5, 0x83);
+    run_until_hblank_block(&mut cpu);
+
+    // Bit 7 is set once stopped, the remaining blocks are kept
+    cpu.bus.write(HDMA5, 0x00);
+    assert_eq!(cpu.bus.read(HDMA5), 0x82);
+    for _ in 0..MAX_CYCLES {
+        cpu.tick_cycle();
+    }
+    assert_eq!(copied(&cpu), 0x10);
+    assert!(!cpu.bus.vram_dma_stalling());
+}